@@ -1,11 +1,16 @@
 use crate::prelude::*;
 
+use crate::blob_store::Acl;
+use crate::blob_store::BlobStore;
+use crate::blob_store::BlobStoreExt as _;
+use crate::blob_store::Headers;
 use crate::version::BuildKind;
 use anyhow::Context;
 use aws_sdk_s3::model::ObjectCannedAcl;
 use aws_sdk_s3::output::PutObjectOutput;
 use aws_sdk_s3::types::ByteStream;
 use bytes::Buf;
+use bytes::Bytes;
 use ide_ci::models::config::RepoContext;
 use serde::de::DeserializeOwned;
 
@@ -20,7 +25,7 @@ pub const MANIFEST_FILENAME: &str = "manifest.yaml";
 
 
 
-#[derive(Clone, Debug, Display, Serialize, Deserialize, Shrinkwrap)]
+#[derive(Clone, Debug, Display, Serialize, Deserialize, Shrinkwrap, PartialEq, Eq)]
 pub struct Edition(pub String);
 
 impl AsRef<str> for Edition {
@@ -43,6 +48,11 @@ impl Edition {
                 .as_ref()
                 .map_or(false, |version| BuildKind::Nightly.matches(version))
     }
+
+    /// Name of the file (relative to the bucket's key prefix) describing this edition.
+    pub fn file_name(&self) -> String {
+        format!("{}.yaml", self.0)
+    }
 }
 
 
@@ -77,6 +87,39 @@ impl Manifest {
         let new_manifest = Manifest { editions: new_editions.into_iter().cloned().collect() };
         (new_manifest, nightlies_to_remove.to_vec())
     }
+
+    pub fn contains(&self, edition: &Edition) -> bool {
+        self.editions.contains(edition)
+    }
+
+    /// Select the editions whose files can be safely removed from the bucket.
+    ///
+    /// `self` should be the new manifest and `evicted` the editions dropped from it. An edition is
+    /// kept if it is not a nightly, if it is still referenced by the manifest, or if its file is
+    /// not present among the `existing_files`.
+    pub fn editions_to_delete<'a>(
+        &self,
+        evicted: impl IntoIterator<Item = &'a Edition>,
+        existing_files: &BTreeSet<String>,
+    ) -> Vec<&'a Edition> {
+        evicted
+            .into_iter()
+            .filter(|edition| {
+                if !edition.is_nightly() {
+                    warn!("Refusing to delete {edition}, as it is not a nightly edition.");
+                    false
+                } else if self.contains(edition) {
+                    warn!("Refusing to delete {edition}, as it is still in the manifest.");
+                    false
+                } else if !existing_files.contains(&edition.file_name()) {
+                    warn!("Cannot delete {edition}, as its file is not in the bucket.");
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -88,28 +131,53 @@ pub struct BucketContext {
 }
 
 impl BucketContext {
+    /// Context for the bucket, using the AWS configuration from the environment.
+    pub async fn from_env(bucket: impl Into<String>, key_prefix: impl Into<String>) -> Self {
+        BucketContext {
+            client:     aws_sdk_s3::Client::new(&aws_config::load_from_env().await),
+            bucket:     bucket.into(),
+            upload_acl: ObjectCannedAcl::PublicRead,
+            key_prefix: key_prefix.into(),
+        }
+    }
+
+    /// Context for the editions bucket of the given repository.
+    pub async fn editions(repo_context: &RepoContext) -> Self {
+        Self::from_env(EDITIONS_BUCKET_NAME, &repo_context.name).await
+    }
+
+    /// Full key of the object under the given path.
+    pub fn key(&self, path: &str) -> String {
+        if self.key_prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.key_prefix, path)
+        }
+    }
+
     pub async fn get(&self, path: &str) -> Result<ByteStream> {
-        Ok(self
-            .client
-            .get_object()
-            .bucket(&self.bucket)
-            .key(format!("{}/{}", self.key_prefix, path))
-            .send()
-            .await?
-            .body)
+        Ok(self.client.get_object().bucket(&self.bucket).key(self.key(path)).send().await?.body)
     }
 
     pub async fn put(&self, path: &str, data: ByteStream) -> Result<PutObjectOutput> {
-        dbg!(self
-            .client
+        self.put_with_acl(path, data, self.upload_acl.clone()).await
+    }
+
+    pub async fn put_with_acl(
+        &self,
+        path: &str,
+        data: ByteStream,
+        acl: ObjectCannedAcl,
+    ) -> Result<PutObjectOutput> {
+        self.client
             .put_object()
             .bucket(&self.bucket)
-            .acl(self.upload_acl.clone())
-            .key(format!("{}/{}", self.key_prefix, path))
-            .body(data))
-        .send()
-        .await
-        .anyhow_err()
+            .acl(acl)
+            .key(self.key(path))
+            .body(data)
+            .send()
+            .await
+            .anyhow_err()
     }
 
     pub async fn get_yaml<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
@@ -123,14 +191,89 @@ impl BucketContext {
     }
 }
 
-pub async fn update_manifest(repo_context: &RepoContext, edition_file: &Path) -> Result {
-    let bucket_context = BucketContext {
-        client:     aws_sdk_s3::Client::new(&aws_config::load_from_env().await),
-        bucket:     EDITIONS_BUCKET_NAME.to_string(),
-        upload_acl: ObjectCannedAcl::PublicRead,
-        key_prefix: repo_context.name.clone(),
-    };
+fn canned_acl(acl: Acl) -> ObjectCannedAcl {
+    match acl {
+        Acl::Private => ObjectCannedAcl::Private,
+        Acl::PublicRead => ObjectCannedAcl::PublicRead,
+    }
+}
+
+#[async_trait]
+impl BlobStore for BucketContext {
+    async fn get(&self, path: &str) -> Result<Bytes> {
+        Ok(BucketContext::get(self, path).await?.collect().await?.into_bytes())
+    }
+
+    async fn put(&self, path: &str, data: Bytes, acl: Acl) -> Result {
+        self.put_with_acl(path, data.into(), canned_acl(acl)).await?;
+        Ok(())
+    }
 
+    async fn put_with_headers(
+        &self,
+        path: &str,
+        data: Bytes,
+        acl: Acl,
+        headers: &Headers,
+    ) -> Result {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .acl(canned_acl(acl))
+            .key(self.key(path))
+            .set_content_type(headers.content_type.clone())
+            .set_cache_control(headers.cache_control.clone())
+            .body(data.into())
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn put_file(&self, path: &str, file: &Path, acl: Acl) -> Result {
+        let data = ByteStream::from_path(file)
+            .await
+            .with_context(|| format!("Failed to read {}.", file.display()))?;
+        self.put_with_acl(path, data, canned_acl(acl)).await?;
+        Ok(())
+    }
+
+    async fn list_prefix(&self, prefix: &str) -> Result<BTreeSet<String>> {
+        let key_prefix = self.key("");
+        let prefix = self.key(prefix);
+        let mut ret = BTreeSet::new();
+        let mut continuation_token = None;
+        loop {
+            let output = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await?;
+            let keys = output.contents().unwrap_or_default().iter().filter_map(|o| o.key());
+            let paths = keys.filter_map(|key| key.strip_prefix(&key_prefix));
+            ret.extend(paths.map(ToString::to_string));
+            match output.next_continuation_token() {
+                Some(token) if output.is_truncated() => continuation_token = Some(token.into()),
+                _ => break,
+            }
+        }
+        Ok(ret)
+    }
+
+    async fn delete(&self, path: &str) -> Result {
+        self.client.delete_object().bucket(&self.bucket).key(self.key(path)).send().await?;
+        Ok(())
+    }
+}
+
+/// Upload the edition file to the store and add it to the manifest.
+///
+/// The nightly editions evicted from the manifest (see [`NIGHTLY_EDITIONS_LIMIT`]) are deleted
+/// from the store after the new manifest is written. If `dry_run` is set, the store is not
+/// modified, the changes are only logged.
+pub async fn update_manifest(store: &dyn BlobStore, edition_file: &Path, dry_run: bool) -> Result {
     let new_edition_name = Edition(
         edition_file
             .file_stem()
@@ -140,15 +283,13 @@ pub async fn update_manifest(repo_context: &RepoContext, edition_file: &Path) ->
     );
     ide_ci::fs::expect_file(&edition_file)?;
 
-    let manifest = bucket_context.get_yaml::<Manifest>(MANIFEST_FILENAME).await?;
-    debug!("Got manifest index from S3: {:#?}", manifest);
-
+    let manifest = store.get_yaml::<Manifest>(MANIFEST_FILENAME).await?;
+    debug!("Got manifest index: {:#?}", manifest);
 
     let (new_manifest, nightlies_to_remove) =
         manifest.with_new_nightly(new_edition_name, NIGHTLY_EDITIONS_LIMIT);
-    for nightly_to_remove in nightlies_to_remove {
-        debug!("Should remove {}", nightly_to_remove);
-    }
+    let existing_files = store.list().await?;
+    let editions_to_delete = new_manifest.editions_to_delete(nightlies_to_remove, &existing_files);
 
     let new_edition_filename = edition_file
         .file_name()
@@ -156,15 +297,32 @@ pub async fn update_manifest(repo_context: &RepoContext, edition_file: &Path) ->
         .to_str()
         .unwrap();
 
-    bucket_context.put(new_edition_filename, ByteStream::from_path(&edition_file).await?).await?;
+    if dry_run {
+        info!("Dry run: would upload {new_edition_filename} and update the manifest.");
+        for edition in editions_to_delete {
+            info!("Dry run: would delete {}.", edition.file_name());
+        }
+        return Ok(());
+    }
+
+    store.put_file(new_edition_filename, edition_file, Acl::PublicRead).await?;
+    store.put_yaml(MANIFEST_FILENAME, &new_manifest, Acl::PublicRead).await?;
 
-    bucket_context.put_yaml("manifest.yaml", &new_manifest).await?;
+    // Deleting only after the new manifest is in place, so it never references a missing file.
+    for edition in editions_to_delete {
+        info!("Deleting evicted nightly edition {edition}.");
+        store.delete(&edition.file_name()).await?;
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blob_store::LocalDirectory;
+    use crate::test_service::ContainerService;
+    use crate::test_service::RunningService;
+    use tempfile::tempdir;
 
 
     // #[tokio::test]
@@ -195,4 +353,130 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn selecting_editions_to_delete() {
+        let manifest =
+            Manifest { editions: vec!["2022.1.1".into(), "2022.1.1-nightly.2022-02-02".into()] };
+        let evicted: Vec<Edition> = vec![
+            "2022.1.1".into(),
+            "2022.1.1-nightly.2022-02-02".into(),
+            "2022.1.1-nightly.2022-01-01".into(),
+            "2022.1.1-nightly.2022-01-02".into(),
+        ];
+        let existing_files = ["2022.1.1.yaml", "2022.1.1-nightly.2022-01-01.yaml"]
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        let to_delete = manifest.editions_to_delete(&evicted, &existing_files);
+        assert_eq!(to_delete, vec![&Edition::from("2022.1.1-nightly.2022-01-01")]);
+    }
+
+    /// MinIO server, standing in for the S3.
+    struct Minio;
+
+    impl Minio {
+        const USER: &'static str = "minio-user";
+        const PASSWORD: &'static str = "minio-password";
+
+        fn service(port: u16) -> ContainerService {
+            let mut service = ContainerService::new(
+                "enso-test-minio",
+                "minio/minio",
+                9000,
+                port,
+                "minio/health/live",
+            );
+            service.run_options.env_raw("MINIO_ROOT_USER", Self::USER);
+            service.run_options.env_raw("MINIO_ROOT_PASSWORD", Self::PASSWORD);
+            service.run_options.command = vec!["server".into(), "/data".into()];
+            service
+        }
+
+        fn client(service: &ContainerService) -> Result<aws_sdk_s3::Client> {
+            let credentials =
+                aws_sdk_s3::Credentials::new(Self::USER, Self::PASSWORD, None, None, "minio");
+            let config = aws_sdk_s3::Config::builder()
+                .region(aws_sdk_s3::Region::new("us-east-1"))
+                .credentials_provider(credentials)
+                .endpoint_resolver(aws_sdk_s3::Endpoint::immutable(service.endpoint().parse()?))
+                .build();
+            Ok(aws_sdk_s3::Client::from_conf(config))
+        }
+    }
+
+    /// Publish a new nightly to the store filled with the nightlies up to the limit.
+    async fn check_pruning(store: &dyn BlobStore) -> Result {
+        let old_editions: Vec<Edition> = (1..=NIGHTLY_EDITIONS_LIMIT)
+            .map(|day| format!("2022.1.1-nightly.2022-01-{day:02}").into())
+            .chain(once("2021.20".into()))
+            .collect();
+        for edition in &old_editions {
+            store.put(&edition.file_name(), Bytes::new(), Acl::PublicRead).await?;
+        }
+        let manifest = Manifest { editions: old_editions };
+        store.put_yaml(MANIFEST_FILENAME, &manifest, Acl::PublicRead).await?;
+
+        let temp = tempdir()?;
+        let new_edition = Edition::from("2022.1.1-nightly.2022-02-01");
+        let edition_file = temp.path().join(new_edition.file_name());
+        ide_ci::fs::write(&edition_file, "")?;
+
+        update_manifest(store, &edition_file, true).await?;
+        assert_eq!(store.list().await?.len(), NIGHTLY_EDITIONS_LIMIT + 2);
+
+        update_manifest(store, &edition_file, false).await?;
+        let files = store.list().await?;
+        let manifest = store.get_yaml::<Manifest>(MANIFEST_FILENAME).await?;
+        assert!(!files.contains("2022.1.1-nightly.2022-01-01.yaml"));
+        assert!(files.contains("2022.1.1-nightly.2022-01-02.yaml"));
+        assert!(files.contains("2021.20.yaml"));
+        assert!(files.contains(&new_edition.file_name()));
+        for edition in &manifest.editions {
+            assert!(files.contains(&edition.file_name()), "Missing file for {edition}.");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pruning_evicted_nightlies() -> Result {
+        let temp = tempdir()?;
+        check_pruning(&LocalDirectory { root: temp.path().into() }).await
+    }
+
+    #[test]
+    fn keys_under_prefix() -> Result {
+        let client = aws_sdk_s3::Client::from_conf(aws_sdk_s3::Config::builder().build());
+        let mut bucket_context = BucketContext {
+            client,
+            bucket: "editions".into(),
+            upload_acl: ObjectCannedAcl::Private,
+            key_prefix: "enso".into(),
+        };
+        assert_eq!(bucket_context.key("manifest.yaml"), "enso/manifest.yaml");
+        bucket_context.key_prefix = default();
+        assert_eq!(bucket_context.key("manifest.yaml"), "manifest.yaml");
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn pruning_evicted_nightlies_s3() -> Result {
+        let service = Minio::service(ide_ci::get_free_port()?);
+        let client = Minio::client(&service)?;
+        let minio = RunningService::start(service).await?;
+        let result = async {
+            let bucket_context = BucketContext {
+                client,
+                bucket: "editions".into(),
+                upload_acl: ObjectCannedAcl::Private,
+                key_prefix: "enso".into(),
+            };
+            bucket_context.client.create_bucket().bucket(&bucket_context.bucket).send().await?;
+            check_pruning(&bucket_context).await
+        }
+        .await;
+        minio.stop().await?;
+        result
+    }
 }
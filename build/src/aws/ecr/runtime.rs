@@ -10,15 +10,24 @@ pub const NAME: &str = "runtime";
 
 pub const REGION: &str = "eu-west-1";
 
+/// Prepare the engine package to be the build context of the runtime image.
+pub fn runtime_image_options(
+    dockerfile: &generated::RepoRootToolsCiDocker,
+    engine_package_root: &generated::EnginePackage,
+) -> Result<BuildOptions> {
+    ide_ci::fs::copy_to(&dockerfile.docker_entrypoint_sh, &engine_package_root.bin)?;
+    let mut opts = BuildOptions::new(engine_package_root);
+    opts.file = Some(dockerfile.dockerfile.to_path_buf());
+    Ok(opts)
+}
+
 #[instrument(fields(%dockerfile, %engine_package_root))]
 pub async fn build_runtime_image(
     dockerfile: generated::RepoRootToolsCiDocker,
     engine_package_root: generated::EnginePackage,
     tag: String,
 ) -> Result<ImageId> {
-    ide_ci::fs::copy_to(dockerfile.docker_entrypoint_sh, &engine_package_root.bin)?;
-    let mut opts = BuildOptions::new(&engine_package_root);
-    opts.file = Some(dockerfile.dockerfile.to_path_buf());
+    let mut opts = runtime_image_options(&dockerfile, &engine_package_root)?;
     opts.tags.push(tag);
     let id = Docker.build(opts).await?;
     Ok(id)
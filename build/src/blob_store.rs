@@ -0,0 +1,202 @@
+//! Storage of the published files, like the editions and their manifest.
+//!
+//! The production files live in the S3 buckets (see [`crate::aws::BucketContext`]), while the
+//! [`LocalDirectory`] store allows exercising the publishing logic without any credentials, e.g.
+//! for staging releases or tests.
+
+use crate::prelude::*;
+
+use crate::aws::BucketContext;
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+
+
+
+/// Who can read the stored object.
+///
+/// This is only a hint, stores without access control (like [`LocalDirectory`]) ignore it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Acl {
+    #[default]
+    Private,
+    PublicRead,
+}
+
+/// HTTP headers served with the stored object.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Headers {
+    pub content_type:  Option<String>,
+    pub cache_control: Option<String>,
+}
+
+/// Flat key-value storage of the files.
+///
+/// Paths are `/`-separated and relative to the store's root.
+#[async_trait]
+pub trait BlobStore: Debug + Send + Sync {
+    async fn get(&self, path: &str) -> Result<Bytes>;
+
+    async fn put(&self, path: &str, data: Bytes, acl: Acl) -> Result;
+
+    /// Store the object along with the headers it should be served with.
+    ///
+    /// Stores that do not serve the objects over HTTP ignore the headers.
+    async fn put_with_headers(
+        &self,
+        path: &str,
+        data: Bytes,
+        acl: Acl,
+        headers: &Headers,
+    ) -> Result {
+        let _ = headers;
+        self.put(path, data, acl).await
+    }
+
+    /// Upload the local file, without reading it whole into memory where the store allows.
+    async fn put_file(&self, path: &str, file: &Path, acl: Acl) -> Result {
+        let data = tokio::fs::read(file)
+            .await
+            .with_context(|| format!("Failed to read {}.", file.display()))?;
+        self.put(path, data.into(), acl).await
+    }
+
+    /// Paths of all the stored objects.
+    async fn list(&self) -> Result<BTreeSet<String>> {
+        self.list_prefix("").await
+    }
+
+    /// Paths of the stored objects starting with the given prefix, e.g. `2022.1.1/`.
+    async fn list_prefix(&self, prefix: &str) -> Result<BTreeSet<String>>;
+
+    /// Remove the object. Removing an object that does not exist is not an error.
+    async fn delete(&self, path: &str) -> Result;
+}
+
+/// Helpers for (de)serializing the stored objects.
+#[async_trait]
+pub trait BlobStoreExt: BlobStore {
+    async fn get_yaml<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let data = self.get(path).await?;
+        serde_yaml::from_slice(&data).with_context(|| format!("Failed to deserialize {path}."))
+    }
+
+    async fn put_yaml<T: Serialize + Sync>(&self, path: &str, data: &T, acl: Acl) -> Result {
+        let text = serde_yaml::to_string(data)?;
+        self.put(path, text.into(), acl).await
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let data = self.get(path).await?;
+        serde_json::from_slice(&data).with_context(|| format!("Failed to deserialize {path}."))
+    }
+
+    async fn put_json<T: Serialize + Sync>(&self, path: &str, data: &T, acl: Acl) -> Result {
+        let text = serde_json::to_string_pretty(data)?;
+        self.put(path, text.into(), acl).await
+    }
+}
+
+impl<T: BlobStore + ?Sized> BlobStoreExt for T {}
+
+/// Open the store described by the URL.
+///
+/// Supported schemes are:
+/// * `s3://<bucket>/<key prefix>` — the S3 bucket, using the credentials from the environment;
+/// * `file:///<path>` — the local directory, see [`LocalDirectory`].
+pub async fn open(url: &Url) -> Result<Box<dyn BlobStore>> {
+    match url.scheme() {
+        "s3" => {
+            let bucket = url.host_str().context("Missing bucket name in the S3 URL.")?;
+            let key_prefix = url.path().trim_matches('/');
+            Ok(Box::new(BucketContext::from_env(bucket, key_prefix).await))
+        }
+        "file" => {
+            let root = url.to_file_path().map_err(|_| anyhow!("Invalid file URL: {url}"))?;
+            Ok(Box::new(LocalDirectory { root }))
+        }
+        scheme => bail!("Unsupported blob store URL scheme `{scheme}` in {url}."),
+    }
+}
+
+/// Store keeping the objects as files in the local directory.
+#[derive(Clone, Debug)]
+pub struct LocalDirectory {
+    pub root: PathBuf,
+}
+
+impl LocalDirectory {
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalDirectory {
+    async fn get(&self, path: &str) -> Result<Bytes> {
+        Ok(ide_ci::fs::read(self.path(path))?.into())
+    }
+
+    async fn put(&self, path: &str, data: Bytes, _acl: Acl) -> Result {
+        ide_ci::fs::write(self.path(path), data)
+    }
+
+    async fn put_file(&self, path: &str, file: &Path, _acl: Acl) -> Result {
+        ide_ci::fs::copy(file, self.path(path))
+    }
+
+    async fn list_prefix(&self, prefix: &str) -> Result<BTreeSet<String>> {
+        let mut ret = BTreeSet::new();
+        // Only the directory containing the prefix needs to be walked.
+        let directory = self.path(prefix.rsplit_once('/').map_or("", |(directory, _)| directory));
+        if !directory.exists() {
+            return Ok(ret);
+        }
+        for entry in walkdir::WalkDir::new(&directory) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                let relative = entry.path().strip_prefix(&self.root)?;
+                let relative = relative.iter().map(|part| part.to_string_lossy()).join("/");
+                if relative.starts_with(prefix) {
+                    ret.insert(relative);
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    async fn delete(&self, path: &str) -> Result {
+        ide_ci::fs::remove_file_if_exists(self.path(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn local_directory() -> Result {
+        let temp = tempfile::tempdir()?;
+        let url = Url::from_directory_path(temp.path()).unwrap();
+        let store = open(&url).await?;
+        assert!(store.list().await?.is_empty());
+
+        store.put("enso/foo.yaml", "foo".into(), Acl::PublicRead).await?;
+        store.put_yaml("enso/bar.yaml", &vec![1, 2], Acl::Private).await?;
+        assert_eq!(
+            store.list().await?,
+            ["enso/bar.yaml", "enso/foo.yaml"].map(String::from).into()
+        );
+        assert_eq!(store.get("enso/foo.yaml").await?, "foo");
+        assert_eq!(store.get_yaml::<Vec<i32>>("enso/bar.yaml").await?, vec![1, 2]);
+
+        store.put("other/foo.yaml", "foo".into(), Acl::PublicRead).await?;
+        assert_eq!(store.list_prefix("enso/b").await?, ["enso/bar.yaml"].map(String::from).into());
+        assert_eq!(store.list_prefix("other/").await?, ["other/foo.yaml"].map(String::from).into());
+        store.delete("other/foo.yaml").await?;
+
+        store.delete("enso/foo.yaml").await?;
+        store.delete("enso/foo.yaml").await?;
+        assert_eq!(store.list().await?, ["enso/bar.yaml"].map(String::from).into());
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
 use crate::prelude::*;
+
+use crate::ide::web::AssetPins;
+use crate::project::wasm::WasmOptPresets;
 use byte_unit::Byte;
 use ide_ci::program;
 use ide_ci::programs;
@@ -43,6 +46,10 @@ impl RecognizedProgram {
 pub struct ConfigRaw {
     pub wasm_size_limit:   Option<String>,
     pub required_versions: HashMap<String, String>,
+    #[serde(default)]
+    pub wasm_opt_presets:  WasmOptPresets,
+    #[serde(default)]
+    pub gui_assets:        AssetPins,
 }
 
 /// The configuration of the script that is being provided by the external environment.
@@ -52,6 +59,10 @@ pub struct ConfigRaw {
 pub struct Config {
     pub wasm_size_limit:   Option<Byte>,
     pub required_versions: HashMap<RecognizedProgram, VersionReq>,
+    /// Named wasm-opt options, selectable with `--wasm-opt-preset`.
+    pub wasm_opt_presets:  WasmOptPresets,
+    /// Versions and checksums of the downloaded GUI inputs.
+    pub gui_assets:        AssetPins,
 }
 
 impl Config {
@@ -94,6 +105,8 @@ impl TryFrom<ConfigRaw> for Config {
                 .map(|limit_text| <Byte as FromString>::from_str(&limit_text))
                 .transpose()?,
             required_versions,
+            wasm_opt_presets: value.wasm_opt_presets,
+            gui_assets: value.gui_assets,
         })
     }
 }
@@ -113,6 +126,14 @@ required-versions:
   node: =16.15.0
   wasm-pack: ^0.10.2
   flatc: =1.12.0
+wasm-opt-presets:
+  release:
+    optimization-level: O4
+    passes: [dce]
+gui-assets:
+  ide-assets:
+    version: "0123456789abcdef0123456789abcdef01234567"
+    sha256: "0000000000000000000000000000000000000000000000000000000000000000"
 "#;
         let config = serde_yaml::from_str::<ConfigRaw>(config)?;
         dbg!(&config);
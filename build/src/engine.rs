@@ -1,6 +1,7 @@
 use crate::prelude::*;
 use std::collections::BTreeSet;
 
+use crate::enso::StandardLibraryTestOptions;
 use crate::paths::generated;
 use crate::paths::ComponentPaths;
 use crate::paths::Paths;
@@ -11,6 +12,7 @@ use ide_ci::models::config::RepoContext;
 pub mod bundle;
 pub mod context;
 pub mod env;
+pub mod plan;
 pub mod sbt;
 
 use crate::get_graal_version;
@@ -18,7 +20,8 @@ use crate::get_java_major_version;
 pub use context::RunContext;
 
 const FLATC_VERSION: Version = Version::new(1, 12, 0);
-const PARALLEL_ENSO_TESTS: AsyncPolicy = AsyncPolicy::Sequential;
+/// Version of the portable PostgreSQL build downloaded for the native test database backend.
+const POSTGRESQL_VERSION: &str = "14.5-1";
 
 pub async fn download_project_templates(client: reqwest::Client, enso_root: PathBuf) -> Result {
     // Download Project Template Files
@@ -77,13 +80,19 @@ impl Benchmarks {
     }
 }
 
+/// Build goals requested by the caller. See [`plan::Step`] for the steps they map to.
 #[derive(Clone, Debug)]
 pub struct BuildConfigurationFlags {
     /// If true, repository shall be cleaned at the build start.
     ///
     /// Makes sense given that incremental builds with SBT are currently broken.
     pub test_scala:                    bool,
+    /// Pattern passed to sbt's `testOnly`, selecting the Scala tests to run.
+    pub scala_test_filter:             Option<String>,
     pub test_standard_library:         bool,
+    pub standard_library_tests:        StandardLibraryTestOptions,
+    /// Rerun only the tests that failed in the previous run.
+    pub rerun_failed_tests:            bool,
     /// Whether benchmarks are compiled.
     ///
     /// Note that this does not run the benchmarks, only ensures that they are buildable.
@@ -110,47 +119,21 @@ impl From<BuildConfigurationFlags> for BuildConfigurationResolved {
     }
 }
 
-#[derive(Clone, Debug, Shrinkwrap)]
-pub struct BuildConfigurationResolved(BuildConfigurationFlags);
-
-impl BuildConfigurationResolved {
-    pub fn new(mut config: BuildConfigurationFlags) -> Self {
-        if config.build_launcher_bundle {
-            config.build_launcher_package = true;
-            config.build_engine_package = true;
-        }
-
-        if config.build_project_manager_bundle {
-            config.build_project_manager_package = true;
-            config.build_engine_package = true;
-        }
-
-        if config.test_standard_library {
-            config.build_engine_package = true;
-        }
-
-        if config.test_java_generated_from_rust {
-            config.generate_java_from_rust = true;
-        }
-
-        Self(config)
-    }
+/// Build configuration with the requested goals resolved into the build plan.
+///
+/// Dereferences to the flags, so the step-specific options (like the benchmarks to run) remain
+/// accessible.
+#[derive(Clone, Debug, derive_more::Deref)]
+pub struct BuildConfigurationResolved {
+    #[deref]
+    pub flags: BuildConfigurationFlags,
+    pub plan:  plan::Plan,
 }
 
-impl BuildConfigurationFlags {
-    pub fn build_engine_package(&self) -> bool {
-        self.build_engine_package
-            || self.build_launcher_bundle
-            || self.build_project_manager_bundle
-            || self.test_standard_library
-    }
-
-    pub fn build_project_manager_package(&self) -> bool {
-        self.build_project_manager_package || self.build_project_manager_bundle
-    }
-
-    pub fn build_launcher_package(&self) -> bool {
-        self.build_launcher_package || self.build_launcher_bundle
+impl BuildConfigurationResolved {
+    pub fn new(flags: BuildConfigurationFlags) -> Self {
+        let plan = plan::Plan::new(flags.goals());
+        Self { flags, plan }
     }
 }
 
@@ -158,7 +141,10 @@ impl Default for BuildConfigurationFlags {
     fn default() -> Self {
         Self {
             test_scala:                    false,
+            scala_test_filter:             None,
             test_standard_library:         false,
+            standard_library_tests:        default(),
+            rerun_failed_tests:            false,
             build_benchmarks:              false,
             execute_benchmarks:            default(),
             execute_benchmarks_once:       false,
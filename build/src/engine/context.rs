@@ -7,6 +7,7 @@ use sysinfo::SystemExt;
 use crate::engine;
 use crate::engine::download_project_templates;
 use crate::engine::env;
+use crate::engine::plan::Step;
 use crate::engine::BuildConfigurationResolved;
 use crate::engine::BuiltArtifacts;
 use crate::engine::ComponentPathExt;
@@ -14,7 +15,7 @@ use crate::engine::Operation;
 use crate::engine::ReleaseCommand;
 use crate::engine::ReleaseOperation;
 use crate::engine::FLATC_VERSION;
-use crate::engine::PARALLEL_ENSO_TESTS;
+use crate::engine::POSTGRESQL_VERSION;
 use crate::paths::cache_directory;
 use crate::paths::Paths;
 use crate::paths::TargetTriple;
@@ -89,13 +90,12 @@ impl RunContext {
 
         // Other programs.
         ide_ci::programs::Git::new_current().await?.require_present().await?;
-        ide_ci::programs::Go.require_present().await?;
         ide_ci::programs::Cargo.require_present().await?;
         ide_ci::programs::Node.require_present().await?;
         ide_ci::programs::Npm.require_present().await?;
 
         let prepare_simple_library_server = {
-            if self.config.test_scala {
+            if self.config.plan.contains(Step::TestScala) {
                 let simple_server_path = &self.paths.repo_root.tools.simple_library_server;
                 ide_ci::programs::Git::new(simple_server_path)
                     .await?
@@ -115,6 +115,21 @@ impl RunContext {
         };
         let prepare_simple_library_server = tokio::spawn(prepare_simple_library_server);
 
+        // Native Postgres for the Standard Library tests. Linux distributions are expected to
+        // provide the binaries through their packages, elsewhere we can download a portable
+        // build.
+        let postgres_backend = self.config.standard_library_tests.postgres;
+        let native_postgres = postgres_backend.or_else(crate::postgres::Backend::deduce)
+            == Some(crate::postgres::Backend::Native);
+        if self.config.plan.contains(Step::TestStandardLibraryWithoutIrCaches)
+            && native_postgres
+            && TARGET_OS != OS::Linux
+        {
+            let postgresql =
+                cache::goodie::postgresql::Postgresql { version: POSTGRESQL_VERSION.to_string() };
+            postgresql.install_if_missing(&self.cache).await?;
+        }
+
         // Setup Conda Environment
         // Install FlatBuffers Compiler
         // If it is not available, we require conda to install it. We should not require conda in
@@ -198,8 +213,11 @@ impl RunContext {
             ide_ci::fs::remove_if_exists(&self.paths.repo_root.engine.runtime.bench_report_xml)?;
         }
 
-        if self.config.test_standard_library {
+        if self.config.plan.contains(Step::TestStandardLibraryWithoutIrCaches)
+            && !self.config.rerun_failed_tests
+        {
             // If we run tests, make sure that old and new results won't end up mixed together.
+            // When rerunning the failed tests, the old results are needed to know what to run.
             ide_ci::fs::reset_dir(&self.paths.test_results)?;
         }
 
@@ -213,18 +231,10 @@ impl RunContext {
         // org.enso.interpreter.node.expression.builtin.bool.True
         ide_ci::fs::remove_if_exists(&self.paths.repo_root.engine.runtime.target)?;
 
-        // We want to start this earlier, and await only before Engine build starts.
-        let perhaps_generate_java_from_rust_job =
-            ide_ci::future::perhaps(self.config.generate_java_from_rust, || {
-                crate::rust::parser::generate_java(&self.paths.repo_root)
-            });
-
         // Download Project Template Files
         let client = reqwest::Client::new();
         download_project_templates(client.clone(), self.paths.repo_root.path.clone()).await?;
 
-        // let sbt = WithCwd::new(Sbt, &self.paths.repo_root);
-
         let mut system = sysinfo::System::new();
         system.refresh_memory();
         trace!("Total memory: {}", system.total_memory());
@@ -232,8 +242,12 @@ impl RunContext {
         trace!("Used memory: {}", system.used_memory());
         trace!("Free memory: {}", system.free_memory());
 
-        // Build packages.
-        debug!("Bootstrapping Enso project.");
+        // If we have much memory, we can try building everything in a single batch. Reducing number
+        // of SBT invocations significantly helps build time. However, it is more memory heavy, so
+        // we don't want to call this in environments like GH-hosted runners.
+        let github_hosted_macos_memory = 15_032_385;
+        let batch_sbt_tasks = system.total_memory() > github_hosted_macos_memory;
+
         let sbt = engine::sbt::Context {
             repo_root:         self.paths.repo_root.path.clone(),
             system_properties: vec![sbt::SystemProperty::new(
@@ -241,234 +255,354 @@ impl RunContext {
                 self.config.execute_benchmarks_once.to_string(),
             )],
         };
+        let mut sbt = engine::sbt::Session::start(sbt).await;
+        let result = self.run_plan(&mut sbt, batch_sbt_tasks, &mut ret).await;
+        // The server is shut down also when the build fails, so it does not outlive the build.
+        let shutdown = sbt.shutdown().await;
+        result?;
+        shutdown?;
+        Ok(ret)
+    }
 
-        sbt.call_arg("bootstrap").await?;
-
-        perhaps_generate_java_from_rust_job.await.transpose()?;
-        let perhaps_test_java_generated_from_rust_job =
-            ide_ci::future::perhaps(self.config.test_java_generated_from_rust, || {
-                crate::rust::parser::run_self_tests(&self.paths.repo_root)
-            });
-
-        // If we have much memory, we can try building everything in a single batch. Reducing number
-        // of SBT invocations significantly helps build time. However, it is more memory heavy, so
-        // we don't want to call this in environments like GH-hosted runners.
-        let github_hosted_macos_memory = 15_032_385;
-        if system.total_memory() > github_hosted_macos_memory {
-            let mut tasks = vec![];
-
-            if self.config.build_engine_package() {
-                tasks.push("buildEngineDistribution");
-                tasks.push("engine-runner/assembly");
-                ret.packages.engine = Some(self.paths.engine.clone());
-            }
-
-            if TARGET_OS != OS::Windows {
-                // FIXME [mwu] apparently this is broken on Windows because of the line endings
-                // mismatch
-                tasks.push("verifyLicensePackages");
-            }
-
-            if self.config.build_project_manager_package() {
-                tasks.push("buildProjectManagerDistribution");
-                ret.packages.project_manager = Some(self.paths.project_manager.clone());
-            }
-
-            if self.config.build_launcher_package() {
-                tasks.push("buildLauncherDistribution");
-                ret.packages.launcher = Some(self.paths.launcher.clone());
-            }
-
-            // This just compiles benchmarks, not run them. At least we'll know that they can be
-            // run. Actually running them, as part of this routine, would be too heavy.
-            // TODO [mwu] It should be possible to run them through context config option.
-            if self.config.build_benchmarks {
-                tasks.extend([
-                    "runtime/Benchmark/compile",
-                    "language-server/Benchmark/compile",
-                    "searcher/Benchmark/compile",
-                ]);
-            }
-
-            for benchmark in &self.config.execute_benchmarks {
-                tasks.push(benchmark.sbt_task());
-            }
-
-            if !tasks.is_empty() {
-                let build_stuff = Sbt::concurrent_tasks(tasks);
-                sbt.call_arg(build_stuff).await?;
-            }
+    /// Run all the steps of the build plan.
+    async fn run_plan(
+        &self,
+        sbt: &mut engine::sbt::Session,
+        batch_sbt_tasks: bool,
+        ret: &mut BuiltArtifacts,
+    ) -> Result {
+        debug!("Build plan: {:?}", self.config.plan);
+        let plan = &self.config.plan;
+        // In the batch mode, the license packages are always verified along with the first batch,
+        // as this is almost free then. The full verification is a separate step.
+        // FIXME [mwu] apparently this is broken on Windows because of the line endings mismatch
+        let mut extra_tasks = if batch_sbt_tasks
+            && TARGET_OS != OS::Windows
+            && plan.contains(Step::Bootstrap)
+            && !plan.contains(Step::VerifyPackages)
+        {
+            vec!["verifyLicensePackages"]
         } else {
-            // Compile
-            sbt.call_arg("compile").await?;
-
-            // Build the Runner & Runtime Uberjars
-            sbt.call_arg("engine-runner/assembly").await?;
-
-            // Build the Launcher Native Image
-            sbt.call_arg("launcher/assembly").await?;
-            sbt.call_args(&["--mem", "1536", "launcher/buildNativeImage"]).await?;
-
-            // Build the PM Native Image
-            sbt.call_arg("project-manager/assembly").await?;
-            sbt.call_args(&["--mem", "1536", "project-manager/buildNativeImage"]).await?;
-
-            // Prepare Launcher Distribution
-            //create_launcher_package(&paths)?;
-            sbt.call_arg("buildLauncherDistribution").await?;
-
-            // Prepare Engine Distribution
-            sbt.call_arg("buildEngineDistribution").await?;
-
-            // Prepare Project Manager Distribution
-            sbt.call_arg("buildProjectManagerDistribution").await?;
-
-            if self.config.build_benchmarks {
-                // Check Runtime Benchmark Compilation
-                sbt.call_arg("runtime/Benchmark/compile").await?;
-
-                // Check Language Server Benchmark Compilation
-                sbt.call_arg("language-server/Benchmark/compile").await?;
-
-                // Check Searcher Benchmark Compilation
-                sbt.call_arg("searcher/Benchmark/compile").await?;
+            vec![]
+        };
+        let mut batch = Vec::new();
+        let mut background = Vec::new();
+        let result = async {
+            for step in plan.steps() {
+                self.join_background_steps(&mut background, Some(step), ret).await?;
+                if let Some(job) = self.background_job(step) {
+                    // The job may depend on the batched steps.
+                    self.run_sbt_batch(sbt, std::mem::take(&mut batch), &mut extra_tasks, ret)
+                        .await?;
+                    debug!("Running build step in the background: {step}.");
+                    background.push((step, tokio::spawn(job)));
+                    continue;
+                }
+                match self.batchable_sbt_tasks(step) {
+                    Some(tasks) if batch_sbt_tasks => batch.push((step, tasks)),
+                    _ => {
+                        self.run_sbt_batch(sbt, std::mem::take(&mut batch), &mut extra_tasks, ret)
+                            .await?;
+                        self.run_step(step, sbt, ret).await?;
+                    }
+                }
             }
-
-            for benchmark in &self.config.execute_benchmarks {
-                sbt.call_arg(benchmark.sbt_task()).await?;
+            self.run_sbt_batch(sbt, batch, &mut extra_tasks, ret).await?;
+            // There was no batch to join.
+            if !extra_tasks.is_empty() {
+                sbt.call_arg(Sbt::concurrent_tasks(extra_tasks)).await?;
             }
+            self.join_background_steps(&mut background, None, ret).await
         }
-
-        // If we were running any benchmarks, they are complete by now. Upload the report.
-        if is_in_env() {
-            let path = &self.paths.repo_root.engine.runtime.bench_report_xml;
-            if path.exists() {
-                ide_ci::actions::artifacts::upload_single_file(
-                    &self.paths.repo_root.engine.runtime.bench_report_xml,
-                    "Runtime Benchmark Report",
-                )
-                .await?;
-            } else {
-                info!("No benchmark file found at {}, nothing to upload.", path.display());
+        .await;
+        if result.is_err() {
+            for (_, job) in background {
+                job.abort();
             }
         }
+        result
+    }
 
-        if self.config.test_scala {
-            // Test Enso
-            sbt.call_arg("set Global / parallelExecution := false; test").await?;
-        }
-
-        perhaps_test_java_generated_from_rust_job.await.transpose()?;
-
-        // === Build Distribution ===
-        if self.config.generate_documentation {
-            // FIXME [mwu]
-            //  docs-generator fails on Windows because it can't understand non-Unix-style paths.
-            if TARGET_OS != OS::Windows {
-                // Build the docs from standard library sources.
-                sbt.call_arg("docs-generator/run").await?;
-            }
+    /// Wait for the background steps that must finish before the `next` step, or for all of them
+    /// if there is no next step.
+    async fn join_background_steps(
+        &self,
+        background: &mut Vec<(Step, tokio::task::JoinHandle<Result>)>,
+        next: Option<Step>,
+        ret: &mut BuiltArtifacts,
+    ) -> Result {
+        let must_finish = |step: &Step| next.map_or(true, |next| step.must_finish_before(next));
+        while let Some(index) = background.iter().position(|(step, _)| must_finish(step)) {
+            let (step, job) = background.remove(index);
+            job.await??;
+            debug!("Background build step finished: {step}.");
+            self.finish_step(step, ret).await?;
         }
+        Ok(())
+    }
 
-        if self.config.build_js_parser {
-            // Build the Parser JS Bundle
-            sbt.call_arg("syntaxJS/fullOptJS").await?;
-            ide_ci::fs::copy_to(
-                self.paths.target.join("scala-parser.js"),
-                self.paths.target.join("parser-upload"),
-            )?;
+    /// The job of a step that does not need sbt, so it can run concurrently with the following
+    /// steps. See [`Step::joined_before`].
+    fn background_job(&self, step: Step) -> Option<BoxFuture<'static, Result>> {
+        let repo_root = self.paths.repo_root.clone();
+        match step {
+            Step::GenerateJavaFromRust =>
+                Some(async move { crate::rust::parser::generate_java(&repo_root).await }.boxed()),
+            Step::TestJavaGeneratedFromRust =>
+                Some(async move { crate::rust::parser::run_self_tests(&repo_root).await }.boxed()),
+            _ => None,
         }
+    }
 
+    /// Human-readable description of what [`RunContext::build`] would do.
+    pub fn describe_plan(&self) -> Result<String> {
+        self.config.plan.describe(&self.paths)
+    }
 
-        let enso = BuiltEnso { paths: self.paths.clone() };
-        if self.config.test_standard_library {
-            // Prepare Engine Test Environment
-            if let Ok(gdoc_key) = std::env::var("GDOC_KEY") {
-                let google_api_test_data_dir =
-                    self.paths.repo_root.join("test").join("Google_Api_Test").join("data");
-                ide_ci::fs::create_dir_if_missing(&google_api_test_data_dir)?;
-                ide_ci::fs::write(google_api_test_data_dir.join("secret.json"), &gdoc_key)?;
-            }
-            enso.run_tests(IrCaches::No, PARALLEL_ENSO_TESTS).await?;
+    /// The sbt tasks of the step, if the step consists only of sbt tasks that can be run together
+    /// with other such steps in a single sbt command.
+    fn batchable_sbt_tasks(&self, step: Step) -> Option<Vec<&'static str>> {
+        match step {
+            Step::EngineDistribution =>
+                Some(vec!["buildEngineDistribution", "engine-runner/assembly"]),
+            Step::LauncherDistribution => Some(vec!["buildLauncherDistribution"]),
+            Step::ProjectManagerDistribution => Some(vec!["buildProjectManagerDistribution"]),
+            Step::BuildBenchmarks => Some(vec![
+                "runtime/Benchmark/compile",
+                "language-server/Benchmark/compile",
+                "searcher/Benchmark/compile",
+            ]),
+            Step::ExecuteBenchmarks =>
+                Some(self.config.execute_benchmarks.iter().map(|b| b.sbt_task()).collect()),
+            _ => None,
         }
+    }
 
-        if self.config.build_engine_package() {
-            let std_libs = self.paths.engine.dir.join("lib").join("Standard");
-            // Compile the Standard Libraries (Unix)
-            debug!("Compiling standard libraries under {}", std_libs.display());
-            for entry in ide_ci::fs::read_dir(&std_libs)? {
-                let entry = entry?;
-                let target = entry.path().join(self.paths.version().to_string());
-                enso.compile_lib(target)?.run_ok().await?;
-            }
+    /// Run the sbt tasks of all the given steps in a single sbt command, then finish the steps.
+    ///
+    /// If the batch is not empty, the extra tasks are taken and run as a part of it.
+    async fn run_sbt_batch(
+        &self,
+        sbt: &mut engine::sbt::Session,
+        batch: Vec<(Step, Vec<&'static str>)>,
+        extra_tasks: &mut Vec<&'static str>,
+        ret: &mut BuiltArtifacts,
+    ) -> Result {
+        let mut tasks = batch.iter().flat_map(|(_, tasks)| tasks.iter().copied()).collect_vec();
+        if !batch.is_empty() {
+            tasks.append(extra_tasks);
         }
-
-        if self.config.test_standard_library {
-            enso.run_tests(IrCaches::Yes, PARALLEL_ENSO_TESTS).await?;
+        if !tasks.is_empty() {
+            sbt.call_arg(Sbt::concurrent_tasks(tasks)).await?;
         }
+        for (step, _) in batch {
+            self.finish_step(step, ret).await?;
+        }
+        Ok(())
+    }
 
-        // Verify License Packages in Distributions
-        // FIXME apparently this does not work on Windows due to some CRLF issues?
-        if self.config.verify_packages && TARGET_OS != OS::Windows {
-            /*  refversion=${{ env.ENSO_VERSION }}
-                binversion=${{ env.DIST_VERSION }}
-                engineversion=$(${{ env.ENGINE_DIST_DIR }}/bin/enso --version --json | jq -r '.version')
-                test $binversion = $refversion || (echo "Tag version $refversion and the launcher version $binversion do not match" && false)
-                test $engineversion = $refversion || (echo "Tag version $refversion and the engine version $engineversion do not match" && false)
-            */
-
-            if self.config.build_engine_package() {
-                sbt.verify_generated_package("engine", &self.paths.engine.dir).await?;
+    /// Run a single step of the build plan.
+    async fn run_step(
+        &self,
+        step: Step,
+        sbt: &mut engine::sbt::Session,
+        ret: &mut BuiltArtifacts,
+    ) -> Result {
+        debug!("Running build step: {step}.");
+        match step {
+            Step::GenerateJavaFromRust | Step::TestJavaGeneratedFromRust =>
+                if let Some(job) = self.background_job(step) {
+                    job.await?;
+                },
+            Step::Bootstrap => {
+                debug!("Bootstrapping Enso project.");
+                sbt.call_arg("bootstrap").await?;
             }
-            if self.config.build_launcher_package() {
-                sbt.verify_generated_package("launcher", &self.paths.launcher.dir).await?;
+            Step::EngineDistribution => {
+                // Compile
+                sbt.call_arg("compile").await?;
+                // Build the Runner & Runtime Uberjars
+                sbt.call_arg("engine-runner/assembly").await?;
+                // Prepare Engine Distribution
+                sbt.call_arg("buildEngineDistribution").await?;
             }
-            if self.config.build_project_manager_package() {
-                sbt.verify_generated_package("project-manager", &self.paths.project_manager.dir)
+            Step::LauncherDistribution => {
+                // Build the Launcher Native Image
+                sbt.call_arg("launcher/assembly").await?;
+                let sbt_one_shot = sbt.one_shot().await?;
+                sbt_one_shot.call_args(&["--mem", "1536", "launcher/buildNativeImage"]).await?;
+                // Prepare Launcher Distribution
+                sbt.call_arg("buildLauncherDistribution").await?;
+            }
+            Step::ProjectManagerDistribution => {
+                // Build the PM Native Image
+                sbt.call_arg("project-manager/assembly").await?;
+                let sbt_one_shot = sbt.one_shot().await?;
+                sbt_one_shot
+                    .call_args(&["--mem", "1536", "project-manager/buildNativeImage"])
                     .await?;
+                // Prepare Project Manager Distribution
+                sbt.call_arg("buildProjectManagerDistribution").await?;
+            }
+            Step::BuildBenchmarks | Step::ExecuteBenchmarks =>
+                for task in self.batchable_sbt_tasks(step).unwrap_or_default() {
+                    sbt.call_arg(task).await?;
+                },
+            Step::TestScala => {
+                // Test Enso
+                // `testQuick` runs only the tests that failed or were affected by changes since
+                // the last run.
+                let task = match (self.config.rerun_failed_tests, &self.config.scala_test_filter) {
+                    (true, Some(filter)) => format!("testQuick {filter}"),
+                    (true, None) => "testQuick".into(),
+                    (false, Some(filter)) => format!("testOnly {filter}"),
+                    (false, None) => "test".into(),
+                };
+                sbt.call_arg(format!("set Global / parallelExecution := false; {task}")).await?;
             }
-            if self.config.build_engine_package {
-                for libname in ["Base", "Table", "Image", "Database"] {
-                    let lib_path = self
-                        .paths
-                        .engine
-                        .dir
-                        .join_iter(["lib", "Standard", libname])
-                        .join(self.paths.version().to_string());
-                    sbt.verify_generated_package(libname, lib_path).await?;
+            Step::GenerateDocumentation => {
+                // FIXME [mwu]
+                //  docs-generator fails on Windows because it can't understand non-Unix-style
+                //  paths.
+                if TARGET_OS != OS::Windows {
+                    // Build the docs from standard library sources.
+                    sbt.call_arg("docs-generator/run").await?;
                 }
             }
-        }
-
-        if self.config.build_engine_package {
-            if TARGET_OS == OS::Linux && ide_ci::ci::run_in_ci() {
-                self.paths.upload_edition_file_artifact().await?;
+            Step::BuildJsParser => {
+                // Build the Parser JS Bundle
+                sbt.call_arg("syntaxJS/fullOptJS").await?;
+                ide_ci::fs::copy_to(
+                    self.paths.target.join("scala-parser.js"),
+                    self.paths.target.join("parser-upload"),
+                )?;
             }
-
-            let schema_dir = self.paths.repo_root.join_iter([
-                "engine",
-                "language-server",
-                "src",
-                "main",
-                "schema",
-            ]);
-            if is_in_env() {
-                ide_ci::actions::artifacts::upload_compressed_directory(&schema_dir, "fbs-schema")
-                    .await?;
+            Step::CompileStandardLibraries => {
+                let enso = BuiltEnso { paths: self.paths.clone() };
+                let std_libs = self.paths.engine.dir.join("lib").join("Standard");
+                // Compile the Standard Libraries (Unix)
+                debug!("Compiling standard libraries under {}", std_libs.display());
+                for entry in ide_ci::fs::read_dir(&std_libs)? {
+                    let entry = entry?;
+                    let target = entry.path().join(self.paths.version().to_string());
+                    enso.compile_lib(target)?.run_ok().await?;
+                }
+            }
+            // When rerunning the failed tests, both runs are done by `TestStandardLibrary`.
+            Step::TestStandardLibraryWithoutIrCaches =>
+                if !self.config.rerun_failed_tests {
+                    let enso = BuiltEnso { paths: self.paths.clone() };
+                    let options = &self.config.standard_library_tests;
+                    enso.run_tests(IrCaches::No, options, &self.cache).await?;
+                },
+            Step::TestStandardLibrary => {
+                let enso = BuiltEnso { paths: self.paths.clone() };
+                let options = &self.config.standard_library_tests;
+                if self.config.rerun_failed_tests {
+                    enso.rerun_failed_tests(options, &self.cache).await?;
+                } else {
+                    enso.run_tests(IrCaches::Yes, options, &self.cache).await?;
+                }
+                enso.summarize_test_results()?;
+            }
+            // Verify License Packages in Distributions
+            // FIXME apparently this does not work on Windows due to some CRLF issues?
+            Step::VerifyPackages =>
+                if TARGET_OS != OS::Windows {
+                    /*  refversion=${{ env.ENSO_VERSION }}
+                        binversion=${{ env.DIST_VERSION }}
+                        engineversion=$(${{ env.ENGINE_DIST_DIR }}/bin/enso --version --json | jq -r '.version')
+                        test $binversion = $refversion || (echo "Tag version $refversion and the launcher version $binversion do not match" && false)
+                        test $engineversion = $refversion || (echo "Tag version $refversion and the engine version $engineversion do not match" && false)
+                    */
+                    let plan = &self.config.plan;
+                    sbt.call_arg("verifyLicensePackages").await?;
+                    if plan.contains(Step::EngineDistribution) {
+                        sbt.verify_generated_package("engine", &self.paths.engine.dir).await?;
+                        for libname in ["Base", "Table", "Image", "Database"] {
+                            let lib_path = self
+                                .paths
+                                .engine
+                                .dir
+                                .join_iter(["lib", "Standard", libname])
+                                .join(self.paths.version().to_string());
+                            sbt.verify_generated_package(libname, lib_path).await?;
+                        }
+                    }
+                    if plan.contains(Step::LauncherDistribution) {
+                        sbt.verify_generated_package("launcher", &self.paths.launcher.dir).await?;
+                    }
+                    if plan.contains(Step::ProjectManagerDistribution) {
+                        sbt.verify_generated_package(
+                            "project-manager",
+                            &self.paths.project_manager.dir,
+                        )
+                        .await?;
+                    }
+                },
+            Step::LauncherBundle => {
+                ret.bundles.launcher =
+                    Some(crate::engine::bundle::Launcher::create(&self.paths).await?);
+            }
+            Step::ProjectManagerBundle => {
+                ret.bundles.project_manager =
+                    Some(crate::engine::bundle::ProjectManager::create(&self.paths).await?);
             }
         }
+        self.finish_step(step, ret).await
+    }
 
-        if self.config.build_launcher_bundle {
-            ret.bundles.launcher =
-                Some(crate::engine::bundle::Launcher::create(&self.paths).await?);
-        }
+    /// Record the artifacts produced by the step and upload its CI artifacts, if any.
+    async fn finish_step(&self, step: Step, ret: &mut BuiltArtifacts) -> Result {
+        match step {
+            Step::EngineDistribution => {
+                ret.packages.engine = Some(self.paths.engine.clone());
+                // Only when explicitly requested, as the artifacts need to be uploaded only once
+                // per CI run.
+                if self.config.plan.is_goal(Step::EngineDistribution) {
+                    if TARGET_OS == OS::Linux && ide_ci::ci::run_in_ci() {
+                        self.paths.upload_edition_file_artifact().await?;
+                    }
 
-        if self.config.build_project_manager_bundle {
-            ret.bundles.project_manager =
-                Some(crate::engine::bundle::ProjectManager::create(&self.paths).await?);
+                    let schema_dir = self.paths.repo_root.join_iter([
+                        "engine",
+                        "language-server",
+                        "src",
+                        "main",
+                        "schema",
+                    ]);
+                    if is_in_env() {
+                        ide_ci::actions::artifacts::upload_compressed_directory(
+                            &schema_dir,
+                            "fbs-schema",
+                        )
+                        .await?;
+                    }
+                }
+            }
+            Step::LauncherDistribution => {
+                ret.packages.launcher = Some(self.paths.launcher.clone());
+            }
+            Step::ProjectManagerDistribution => {
+                ret.packages.project_manager = Some(self.paths.project_manager.clone());
+            }
+            Step::ExecuteBenchmarks =>
+                if is_in_env() {
+                    // The benchmarks are complete by now. Upload the report.
+                    let path = &self.paths.repo_root.engine.runtime.bench_report_xml;
+                    if path.exists() {
+                        ide_ci::actions::artifacts::upload_single_file(
+                            path,
+                            "Runtime Benchmark Report",
+                        )
+                        .await?;
+                    } else {
+                        info!("No benchmark file found at {}, nothing to upload.", path.display());
+                    }
+                },
+            _ => {}
         }
-
-        Ok(ret)
+        Ok(())
     }
 
     pub async fn execute(&self, operation: Operation) -> Result {
@@ -17,3 +17,12 @@ impl Variable for CiFlakyTestEnable {
     const NAME: &'static str = "CI_TEST_FLAKY_ENABLE";
     type Value = bool;
 }
+
+/// Whether the build script should use a persistent sbt server (through the thin client) rather
+/// than spawning a new sbt process for each task. Enabled by default.
+#[derive(Clone, Copy, Debug)]
+pub struct SbtClient;
+impl Variable for SbtClient {
+    const NAME: &'static str = "ENSO_BUILD_SBT_CLIENT";
+    type Value = bool;
+}
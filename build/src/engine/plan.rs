@@ -0,0 +1,304 @@
+//! Declarative description of the Engine build.
+//!
+//! The build consists of [`Step`]s, each declaring its dependencies, inputs and outputs. The
+//! requested goals are resolved into a [`Plan`]: the set of steps that need to be run to achieve
+//! them, in the execution order. Some steps run in the background, concurrently with the steps
+//! following them, see [`Step::joined_before`].
+
+use crate::prelude::*;
+
+use crate::engine::bundle::Bundle;
+use crate::engine::bundle::Launcher;
+use crate::engine::bundle::ProjectManager;
+use crate::engine::BuildConfigurationFlags;
+use crate::paths::Paths;
+
+use std::fmt::Write;
+
+
+
+/// A single step of the Engine build.
+///
+/// The declaration order is the execution order. Every step must be declared after all its
+/// dependencies, this is checked by the `steps_are_topologically_ordered` test.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, strum::EnumIter)]
+pub enum Step {
+    /// Generate Java sources for the new parser from its Rust definition.
+    GenerateJavaFromRust,
+    /// Bootstrap the sbt project. Prerequisite for all sbt-based steps.
+    Bootstrap,
+    /// Run the self-tests of the Java code generated from Rust.
+    TestJavaGeneratedFromRust,
+    /// Build the Engine distribution along with the runner and runtime uberjars.
+    EngineDistribution,
+    /// Build the Launcher native image and its distribution.
+    LauncherDistribution,
+    /// Build the Project Manager native image and its distribution.
+    ProjectManagerDistribution,
+    /// Compile the benchmarks, without running them.
+    BuildBenchmarks,
+    /// Run the benchmarks requested in the configuration.
+    ExecuteBenchmarks,
+    /// Run the Scala test suite.
+    TestScala,
+    /// Generate the documentation from the Standard Library sources.
+    GenerateDocumentation,
+    /// Build the JS bundle of the (old) Scala parser.
+    BuildJsParser,
+    /// Run the Standard Library test suites without IR caches, i.e. before they are precompiled.
+    TestStandardLibraryWithoutIrCaches,
+    /// Precompile the Standard Libraries shipped with the Engine distribution.
+    CompileStandardLibraries,
+    /// Run the Standard Library test suites with the IR caches and summarize the results of both
+    /// runs.
+    TestStandardLibrary,
+    /// Verify the license packages of the built distributions.
+    VerifyPackages,
+    /// Create the Launcher bundle (Launcher with Engine and GraalVM).
+    LauncherBundle,
+    /// Create the Project Manager bundle (Project Manager with Engine and GraalVM).
+    ProjectManagerBundle,
+}
+
+impl Step {
+    /// Steps that must be run before this one. Requesting this step implies requesting them.
+    pub fn dependencies(self) -> &'static [Step] {
+        use Step::*;
+        match self {
+            GenerateJavaFromRust => &[],
+            Bootstrap => &[],
+            EngineDistribution => &[Bootstrap],
+            LauncherDistribution => &[Bootstrap],
+            ProjectManagerDistribution => &[Bootstrap],
+            BuildBenchmarks => &[Bootstrap],
+            ExecuteBenchmarks => &[Bootstrap],
+            TestScala => &[Bootstrap],
+            TestJavaGeneratedFromRust => &[GenerateJavaFromRust, Bootstrap],
+            GenerateDocumentation => &[Bootstrap],
+            BuildJsParser => &[Bootstrap],
+            TestStandardLibraryWithoutIrCaches => &[EngineDistribution],
+            CompileStandardLibraries => &[EngineDistribution],
+            TestStandardLibrary =>
+                &[EngineDistribution, TestStandardLibraryWithoutIrCaches, CompileStandardLibraries],
+            VerifyPackages => &[Bootstrap],
+            LauncherBundle => &[LauncherDistribution, EngineDistribution, CompileStandardLibraries],
+            ProjectManagerBundle =>
+                &[ProjectManagerDistribution, EngineDistribution, CompileStandardLibraries],
+        }
+    }
+
+    /// Repository subtrees (relative to its root) whose contents affect the step.
+    pub fn inputs(self) -> &'static [&'static str] {
+        use Step::*;
+        match self {
+            GenerateJavaFromRust => &["lib/rust/parser"],
+            Bootstrap => &["build.sbt", "project"],
+            EngineDistribution => &["engine", "lib/scala", "distribution/lib"],
+            LauncherDistribution => &["engine/launcher", "lib/scala"],
+            ProjectManagerDistribution => &["lib/scala/project-manager", "lib/scala"],
+            BuildBenchmarks | ExecuteBenchmarks => &["engine", "lib/scala"],
+            TestScala => &["engine", "lib/scala"],
+            TestJavaGeneratedFromRust => &["lib/rust/parser"],
+            GenerateDocumentation => &["lib/scala/docs-generator", "distribution/lib"],
+            BuildJsParser => &["lib/scala/syntax"],
+            CompileStandardLibraries => &["distribution/lib"],
+            TestStandardLibraryWithoutIrCaches | TestStandardLibrary => &["test"],
+            VerifyPackages => &["distribution", "tools/legal-review"],
+            LauncherBundle | ProjectManagerBundle => &["distribution/enso.bundle.template"],
+        }
+    }
+
+    /// Locations written by the step.
+    pub fn outputs(self, paths: &Paths) -> Vec<PathBuf> {
+        use Step::*;
+        match self {
+            GenerateJavaFromRust => vec![paths.repo_root.target.generated_java.to_path_buf()],
+            Bootstrap => vec![],
+            EngineDistribution => vec![paths.engine.root.clone()],
+            LauncherDistribution => vec![paths.launcher.root.clone()],
+            ProjectManagerDistribution => vec![paths.project_manager.root.clone()],
+            BuildBenchmarks => vec![],
+            ExecuteBenchmarks =>
+                vec![paths.repo_root.engine.runtime.bench_report_xml.to_path_buf()],
+            TestScala | TestJavaGeneratedFromRust => vec![],
+            GenerateDocumentation => vec![],
+            BuildJsParser => vec![paths.target.join("parser-upload")],
+            CompileStandardLibraries => vec![paths.engine.dir.join("lib").join("Standard")],
+            TestStandardLibraryWithoutIrCaches | TestStandardLibrary =>
+                vec![paths.test_results.clone()],
+            VerifyPackages => vec![],
+            LauncherBundle => vec![Launcher::suggest_paths(paths).root],
+            ProjectManagerBundle => vec![ProjectManager::suggest_paths(paths).root],
+        }
+    }
+
+    /// If the step runs in the background, the step that must not start before this one is
+    /// finished. The steps declared in between run concurrently with this one.
+    ///
+    /// The background steps do not use sbt, so they do not compete for the sbt server.
+    pub fn joined_before(self) -> Option<Step> {
+        use Step::*;
+        match self {
+            GenerateJavaFromRust => Some(EngineDistribution),
+            TestJavaGeneratedFromRust => Some(GenerateDocumentation),
+            _ => None,
+        }
+    }
+
+    /// Whether this step must be finished before the `next` one starts.
+    pub fn must_finish_before(self, next: Step) -> bool {
+        let is_joined = self.joined_before().map_or(true, |joined_before| joined_before <= next);
+        is_joined || next.dependencies().contains(&self)
+    }
+}
+
+/// Steps to be run to achieve the requested goals.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Plan {
+    /// Steps that were explicitly requested.
+    goals: BTreeSet<Step>,
+    /// Goals with all their transitive dependencies.
+    steps: BTreeSet<Step>,
+}
+
+impl Plan {
+    /// Resolve the goals into a plan, pulling in all their (transitive) dependencies.
+    pub fn new(goals: impl IntoIterator<Item = Step>) -> Self {
+        let goals: BTreeSet<_> = goals.into_iter().collect();
+        let mut steps = BTreeSet::new();
+        let mut to_visit = goals.iter().copied().collect_vec();
+        while let Some(step) = to_visit.pop() {
+            if steps.insert(step) {
+                to_visit.extend(step.dependencies());
+            }
+        }
+        Self { goals, steps }
+    }
+
+    /// Whether the step will be run.
+    pub fn contains(&self, step: Step) -> bool {
+        self.steps.contains(&step)
+    }
+
+    /// Whether the step was explicitly requested, rather than being a dependency of some goal.
+    pub fn is_goal(&self, step: Step) -> bool {
+        self.goals.contains(&step)
+    }
+
+    /// Steps in the execution order.
+    pub fn steps(&self) -> impl Iterator<Item = Step> + '_ {
+        self.steps.iter().copied()
+    }
+
+    /// Human-readable description of the plan, as printed by the dry run.
+    pub fn describe(&self, paths: &Paths) -> Result<String> {
+        let mut ret = String::new();
+        if self.steps.is_empty() {
+            writeln!(ret, "Nothing to do.")?;
+        }
+        for (index, step) in self.steps().enumerate() {
+            let kind = if self.is_goal(step) { "goal" } else { "dependency" };
+            writeln!(ret, "{}. {step} ({kind})", index + 1)?;
+            let dependencies = step.dependencies();
+            if !dependencies.is_empty() {
+                writeln!(ret, "    after:   {}", dependencies.iter().join(", "))?;
+            }
+            if let Some(joined_before) = step.joined_before() {
+                writeln!(ret, "    in the background, until: {joined_before}")?;
+            }
+            writeln!(ret, "    inputs:  {}", step.inputs().iter().join(", "))?;
+            for output in step.outputs(paths) {
+                writeln!(ret, "    output:  {}", output.display())?;
+            }
+        }
+        Ok(ret)
+    }
+}
+
+impl BuildConfigurationFlags {
+    /// Steps requested by these flags.
+    pub fn goals(&self) -> BTreeSet<Step> {
+        let requested = [
+            (self.generate_java_from_rust, Step::GenerateJavaFromRust),
+            (self.build_engine_package, Step::EngineDistribution),
+            (self.build_engine_package, Step::CompileStandardLibraries),
+            (self.build_launcher_package, Step::LauncherDistribution),
+            (self.build_project_manager_package, Step::ProjectManagerDistribution),
+            (self.build_benchmarks, Step::BuildBenchmarks),
+            (!self.execute_benchmarks.is_empty(), Step::ExecuteBenchmarks),
+            (self.test_scala, Step::TestScala),
+            (self.test_java_generated_from_rust, Step::TestJavaGeneratedFromRust),
+            (self.generate_documentation, Step::GenerateDocumentation),
+            (self.build_js_parser, Step::BuildJsParser),
+            (self.test_standard_library, Step::TestStandardLibrary),
+            (self.verify_packages, Step::VerifyPackages),
+            (self.build_launcher_bundle, Step::LauncherBundle),
+            (self.build_project_manager_bundle, Step::ProjectManagerBundle),
+        ];
+        requested.into_iter().filter_map(|(requested, step)| requested.then_some(step)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn steps_are_topologically_ordered() {
+        for step in Step::iter() {
+            for dependency in step.dependencies() {
+                assert!(
+                    dependency < &step,
+                    "{step} is declared before its dependency {dependency}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn background_steps_are_joined_later() {
+        for step in Step::iter() {
+            if let Some(joined_before) = step.joined_before() {
+                assert!(step < joined_before, "{step} is joined before it starts");
+            }
+        }
+        use Step::*;
+        assert!(!TestJavaGeneratedFromRust.must_finish_before(TestScala));
+        assert!(TestJavaGeneratedFromRust.must_finish_before(GenerateDocumentation));
+        assert!(TestJavaGeneratedFromRust.must_finish_before(ProjectManagerBundle));
+        assert!(!GenerateJavaFromRust.must_finish_before(Bootstrap));
+        assert!(GenerateJavaFromRust.must_finish_before(TestJavaGeneratedFromRust));
+        assert!(Bootstrap.must_finish_before(EngineDistribution));
+    }
+
+    #[test]
+    fn bundle_pulls_in_distributions() {
+        let plan = Plan::new([Step::ProjectManagerBundle]);
+        assert!(plan.is_goal(Step::ProjectManagerBundle));
+        assert!(plan.contains(Step::ProjectManagerDistribution));
+        assert!(plan.contains(Step::EngineDistribution));
+        assert!(plan.contains(Step::CompileStandardLibraries));
+        assert!(plan.contains(Step::Bootstrap));
+        assert!(!plan.is_goal(Step::EngineDistribution));
+        assert!(!plan.contains(Step::LauncherDistribution));
+        assert_eq!(plan.steps().last(), Some(Step::ProjectManagerBundle));
+    }
+
+    #[test]
+    fn standard_library_tests_need_engine() {
+        let flags = BuildConfigurationFlags {
+            test_standard_library: true,
+            generate_java_from_rust: false,
+            ..default()
+        };
+        let plan = Plan::new(flags.goals());
+        assert_eq!(plan.steps().collect_vec(), vec![
+            Step::Bootstrap,
+            Step::EngineDistribution,
+            Step::TestStandardLibraryWithoutIrCaches,
+            Step::CompileStandardLibraries,
+            Step::TestStandardLibrary
+        ]);
+    }
+}
@@ -3,6 +3,9 @@
 use crate::prelude::*;
 use ide_ci::program::command::provider::CommandProviderExt;
 
+use crate::engine::env::SbtClient;
+use ide_ci::env::Variable;
+
 use ide_ci::programs::sbt;
 use ide_ci::programs::Sbt;
 
@@ -30,15 +33,154 @@ pub struct Context {
     pub system_properties: Vec<sbt::SystemProperty>,
 }
 
+impl Context {
+    /// Arguments given to each one-shot sbt invocation.
+    fn args(&self) -> Vec<String> {
+        self.system_properties.iter().flatten().collect()
+    }
+}
+
 impl CommandProvider for Context {
     fn command(&self) -> Result<Command> {
         let mut cmd = Sbt.cmd()?;
-        cmd.current_dir(&self.repo_root);
-        for property in &self.system_properties {
-            cmd.args(property);
-        }
+        cmd.current_dir(&self.repo_root).args(self.args());
         Ok(cmd)
     }
 }
 
 impl SbtCommandProvider for Context {}
+
+/// sbt build driver that keeps a single sbt server alive for the whole build.
+///
+/// Tasks are sent to the server through the thin client, so the JVM startup and project loading
+/// costs are paid only once. If the server cannot be started (or its use is disabled through
+/// [`SbtClient`] environment variable), each task is run by a new, one-shot sbt process.
+///
+/// The server must be stopped with [`Session::shutdown`]. Dropping a session with a running server
+/// only logs a warning, as the server cannot be stopped without blocking the async runtime.
+#[derive(Debug)]
+pub struct Session {
+    pub context:    Context,
+    server_running: bool,
+}
+
+impl Session {
+    /// Start the sbt server for the given context.
+    ///
+    /// This never fails: if the server does not start, the session falls back to one-shot sbt
+    /// invocations.
+    pub async fn start(context: Context) -> Self {
+        let use_client = SbtClient.fetch().unwrap_or(true);
+        let server_running = if use_client {
+            debug!("Starting sbt server.");
+            let start = context
+                .command()
+                .and_then_async(|mut cmd| cmd.arg(sbt::CLIENT_FLAG).arg("about").run_ok());
+            match start.await {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Failed to start sbt server, falling back to one-shot sbt calls: {e}");
+                    false
+                }
+            }
+        } else {
+            debug!("Use of sbt server is disabled, sbt will be called in one-shot mode.");
+            false
+        };
+        Self { context, server_running }
+    }
+
+    /// Whether tasks are sent to the persistent sbt server.
+    pub fn is_server_running(&self) -> bool {
+        self.server_running
+    }
+
+    /// Provider that always runs tasks in a new sbt process.
+    ///
+    /// Needed for the commands that need custom JVM options (like `--mem`), as these cannot be
+    /// applied to an already running server. The server is shut down first, so two sbt instances
+    /// never operate on the same project; subsequent tasks use one-shot invocations as well.
+    pub async fn one_shot(&mut self) -> Result<&Context> {
+        self.shutdown().await?;
+        Ok(&self.context)
+    }
+
+    /// Stop the sbt server. Subsequent tasks will use one-shot sbt invocations.
+    pub async fn shutdown(&mut self) -> Result {
+        if std::mem::replace(&mut self.server_running, false) {
+            debug!("Shutting down sbt server.");
+            self.client_command()?.arg(sbt::SHUTDOWN_COMMAND).run_ok().await?;
+        }
+        Ok(())
+    }
+
+    /// Arguments given to each sbt invocation, depending on whether the server is used.
+    fn args(&self) -> Vec<String> {
+        if self.server_running {
+            // System properties are not passed here, as they were given to the server on startup.
+            vec![sbt::CLIENT_FLAG.into()]
+        } else {
+            self.context.args()
+        }
+    }
+
+    fn client_command(&self) -> Result<Command> {
+        let mut cmd = Sbt.cmd()?;
+        cmd.current_dir(&self.context.repo_root).arg(sbt::CLIENT_FLAG);
+        Ok(cmd)
+    }
+}
+
+impl CommandProvider for Session {
+    fn command(&self) -> Result<Command> {
+        let mut cmd = Sbt.cmd()?;
+        cmd.current_dir(&self.context.repo_root).args(self.args());
+        Ok(cmd)
+    }
+}
+
+impl SbtCommandProvider for Session {}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if self.server_running {
+            warn!(
+                "The sbt session was dropped without being shut down. The sbt server for {} might \
+                be left running.",
+                self.context.repo_root.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> Context {
+        Context {
+            repo_root:         PathBuf::from("/nonexistent/enso"),
+            system_properties: vec![sbt::SystemProperty::new("bench.compileOnly", "true")],
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_one_shot_when_server_does_not_start() -> Result {
+        let mut session = Session::start(context()).await;
+        assert!(!session.is_server_running());
+        assert_eq!(session.args(), vec!["-Dbench.compileOnly=true"]);
+        // Nothing to shut down, so this must not try calling sbt.
+        session.shutdown().await?;
+        Ok(())
+    }
+
+    #[test]
+    fn selecting_client_or_one_shot_arguments() {
+        let mut session = Session { context: context(), server_running: true };
+        assert_eq!(session.args(), vec![sbt::CLIENT_FLAG]);
+        // Dropping a session with the server only warns, as it cannot block on the shutdown.
+        session.server_running = false;
+        assert_eq!(session.args(), session.context.args());
+        drop(Session { context: context(), server_running: true });
+    }
+}
@@ -1,13 +1,22 @@
 use crate::prelude::*;
 
+use ide_ci::cache::Cache;
 use ide_ci::env::Variable;
 use ide_ci::future::AsyncPolicy;
+use ide_ci::models::junit;
 use ide_ci::programs::docker::ContainerId;
 
+use crate::httpbin::Httpbin;
 use crate::paths::Paths;
+use crate::paths::ENSO_TEST_JUNIT_DIR;
 use crate::postgres;
 use crate::postgres::EndpointConfiguration;
+use crate::postgres::NativePostgresql;
 use crate::postgres::Postgresql;
+use crate::test_service::Services;
+use crate::test_service::TestService;
+
+use std::str::FromStr;
 
 ide_ci::define_env_var! {
     ENSO_JVM_OPTS, String;
@@ -34,6 +43,112 @@ impl AsRef<OsStr> for IrCaches {
     }
 }
 
+/// Subset of the test suites to be run by this job, allowing to split them across many CI jobs.
+///
+/// Written as `i/n`, meaning the `i`-th of `n` shards (counting from 1). The suites are assigned to
+/// the shards in a round-robin manner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    /// Select the items that belong to this shard.
+    pub fn select<T>(self, items: impl IntoIterator<Item = T>) -> impl Iterator<Item = T> {
+        items
+            .into_iter()
+            .enumerate()
+            .filter_map(move |(i, item)| (i % self.count == self.index - 1).then_some(item))
+    }
+}
+
+impl FromStr for Shard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (index, count) =
+            s.split_once('/').with_context(|| format!("Shard must be given as `i/n`, got {s}."))?;
+        let index = index.trim().parse2::<usize>()?;
+        let count = count.trim().parse2::<usize>()?;
+        ensure!(
+            (1..=count).contains(&index),
+            "Shard index must be between 1 and {count}, got {index}."
+        );
+        Ok(Self { index, count })
+    }
+}
+
+impl Display for Shard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// How the Standard Library test suites should be run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StandardLibraryTestOptions {
+    /// If set, only this shard of the test suites is run.
+    pub shard:     Option<Shard>,
+    /// Run the test suites concurrently, each with its own httpbin and database.
+    pub parallel:  bool,
+    /// Run only the test suites of these libraries, e.g. `Table_Tests`. Empty means all.
+    pub libraries: Vec<String>,
+    /// Regular expression selecting the specs to run. Forwarded to the test runner.
+    pub filter:    Option<String>,
+    /// Names of the previously failed specs to rerun, keyed by the library. The libraries not
+    /// listed run all their specs.
+    pub rerun:     BTreeMap<String, BTreeSet<String>>,
+    /// How to run the database for the tests. If not set, it is deduced from the available tools.
+    pub postgres:  Option<postgres::Backend>,
+}
+
+impl StandardLibraryTestOptions {
+    /// Names of the test suites to run, i.e. directories under the repository's `test`.
+    pub fn selected_tests(&self) -> Result<Vec<&'static str>> {
+        let all = crate::paths::LIBRARIES_TO_TEST;
+        for library in &self.libraries {
+            ensure!(
+                all.contains(&library.as_str()),
+                "Unknown test library {library}. Known libraries are: {}.",
+                all.join(", ")
+            );
+        }
+        let is_selected = |test: &&str| {
+            self.libraries.is_empty() || self.libraries.iter().any(|library| library == test)
+        };
+        let selected = all.into_iter().filter(is_selected);
+        Ok(match self.shard {
+            Some(shard) => shard.select(selected).collect(),
+            None => selected.collect(),
+        })
+    }
+
+    /// Regular expression passed to the test runner of the library, combining [`Self::filter`]
+    /// and the library's [`Self::rerun`] specs.
+    ///
+    /// When both are set, a spec is run only if it is one of the rerun specs and it also matches
+    /// the user's filter.
+    pub fn spec_pattern(&self, library: &str) -> Option<String> {
+        let rerun = self.rerun.get(library).filter(|names| !names.is_empty()).map(|names| {
+            format!("^(?:{})$", names.iter().map(|name| regex::escape(name)).join("|"))
+        });
+        match (&self.filter, rerun) {
+            (Some(filter), Some(rerun)) => Some(format!("(?=.*(?:{filter})){rerun}")),
+            (Some(filter), None) => Some(filter.clone()),
+            (None, rerun) => rerun,
+        }
+    }
+
+    pub fn async_policy(&self) -> AsyncPolicy {
+        if self.parallel {
+            AsyncPolicy::FutureParallelism
+        } else {
+            AsyncPolicy::Sequential
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BuiltEnso {
     pub paths: Paths,
@@ -67,7 +182,17 @@ impl BuiltEnso {
         Ok(command)
     }
 
-    pub async fn run_tests(&self, ir_caches: IrCaches, async_policy: AsyncPolicy) -> Result {
+    /// Run the Standard Library test suites.
+    ///
+    /// Every suite gets its own httpbin instance and database, so the suites can be run
+    /// concurrently without interfering with each other. See [`Self::shared_test_services`] and
+    /// [`Self::per_suite_test_services`] for the services being started.
+    pub async fn run_tests(
+        &self,
+        ir_caches: IrCaches,
+        options: &StandardLibraryTestOptions,
+        cache: &Cache,
+    ) -> Result {
         let paths = &self.paths;
         // Prepare Engine Test Environment
         if let Ok(gdoc_key) = std::env::var("GDOC_KEY") {
@@ -77,9 +202,43 @@ impl BuiltEnso {
             ide_ci::fs::write(google_api_test_data_dir.join("secret.json"), &gdoc_key)?;
         }
 
-        let _httpbin = crate::httpbin::get_and_spawn_httpbin_on_free_port().await?;
-        let _postgres = match TARGET_OS {
-            OS::Linux => {
+        let tests = options.selected_tests()?;
+        if let Some(shard) = options.shard {
+            info!("Running shard {shard} of the test suites: {}.", tests.iter().join(", "));
+        }
+
+        let shared_services = Services::start(self.shared_test_services(options)?).await?;
+        let per_suite_services = match self.per_suite_test_services(tests.len(), cache).await {
+            Ok(services) => Services::start(services).await,
+            Err(e) => Err(e),
+        };
+        let per_suite_services = match per_suite_services {
+            Ok(services) => services,
+            Err(e) => return Err(shared_services.stop_after(e).await),
+        };
+
+        let result = self
+            .run_test_suites(&tests, ir_caches, options, &shared_services, &per_suite_services)
+            .await;
+        // The services must outlive all the test runs, so they are stopped only here.
+        let stopped = shared_services.stop().await.and(per_suite_services.stop().await);
+        result?;
+        stopped
+    }
+
+    /// Services used by all the test suites at once.
+    ///
+    /// Each suite still gets isolated within the service, see [`TestService::prepare_suite`].
+    pub fn shared_test_services(
+        &self,
+        options: &StandardLibraryTestOptions,
+    ) -> Result<Vec<Box<dyn TestService>>> {
+        let mut ret: Vec<Box<dyn TestService>> = Vec::new();
+        let database_name = postgres::TEST_DATABASE_NAME;
+        let user = postgres::TEST_USER;
+        let password = postgres::TEST_PASSWORD;
+        match options.postgres.or_else(postgres::Backend::deduce) {
+            Some(postgres::Backend::Docker) => {
                 let runner_context_string = crate::env::RunnerContainerName
                     .fetch()
                     .map(|name| name.0)
@@ -91,30 +250,203 @@ impl BuiltEnso {
                     iformat!("postgres-for-{runner_context_string}").replace(' ', "_");
                 let config = postgres::Configuration {
                     postgres_container: ContainerId(container_name),
-                    database_name:      "enso_test_db".to_string(),
-                    user:               "enso_test_user".to_string(),
-                    password:           "enso_test_password".to_string(),
+                    database_name:      database_name.to_string(),
+                    user:               user.to_string(),
+                    password:           password.to_string(),
                     endpoint:           EndpointConfiguration::deduce()?,
                     version:            "latest".to_string(),
                 };
-                let postgres = Postgresql::start(config).await?;
-                Some(postgres)
+                ret.push(Box::new(Postgresql { config }));
             }
-            _ => None,
-        };
+            Some(postgres::Backend::Native) =>
+                ret.push(Box::new(NativePostgresql::new(database_name, user, password)?)),
+            None => warn!(
+                "Neither Docker nor the Postgres binaries are available. Database tests will not \
+                have a database to connect to."
+            ),
+        }
+        Ok(ret)
+    }
 
-        let futures = crate::paths::LIBRARIES_TO_TEST.map(ToString::to_string).map(|test| {
-            let command = self.run_test(test, ir_caches);
-            async move { command?.run_ok().await }
-        });
+    /// Services of which each test suite gets its own instance, in the order of the suites.
+    pub async fn per_suite_test_services(
+        &self,
+        suite_count: usize,
+        cache: &Cache,
+    ) -> Result<Vec<Box<dyn TestService>>> {
+        let httpbin_program = crate::httpbin::install(cache).await?;
+        ide_ci::get_free_ports(suite_count)?
+            .into_iter()
+            .map(|port| -> Result<Box<dyn TestService>> {
+                Ok(Box::new(Httpbin::new(&httpbin_program, port)?))
+            })
+            .collect()
+    }
 
-        let _result = ide_ci::future::try_join_all(futures, async_policy).await?;
+    async fn run_test_suites(
+        &self,
+        tests: &[&str],
+        ir_caches: IrCaches,
+        options: &StandardLibraryTestOptions,
+        shared_services: &Services,
+        per_suite_services: &Services,
+    ) -> Result {
+        let mut commands = Vec::new();
+        for (test, own_service) in tests.iter().zip(&per_suite_services.services) {
+            let mut command = self.run_test(test, ir_caches)?;
+            if let Some(pattern) = options.spec_pattern(test) {
+                // Passed to the test program, which uses it to select the specs.
+                command.arg(pattern);
+            }
+            command.set_env(ENSO_TEST_JUNIT_DIR, &self.paths.test_results)?;
+            shared_services.apply_env(&mut command, test).await?;
+            own_service.apply_env(&mut command, test).await?;
+            commands.push((test.to_string(), command));
+        }
 
-        // We need to join all the test tasks here, as they require postgres and httpbin alive.
-        // Could share them with Arc but then scenario of multiple test runs being run in parallel
-        // should be handled, e.g. avoiding port collisions.
+        let futures = commands.into_iter().map(|(test, mut command)| async move {
+            let started = std::time::Instant::now();
+            let result = command.run_ok().await;
+            info!("Test suite {test} finished in {:.1?}.", started.elapsed());
+            result
+        });
+        let _result = ide_ci::future::try_join_all(futures, options.async_policy()).await?;
         Ok(())
     }
+
+    /// Rerun only the tests that failed in the previous run, as recorded in its JUnit reports.
+    ///
+    /// Only the libraries selected in the `options` are rerun (all if none are selected). Their
+    /// previous results are moved to [`Paths::test_results_before_rerun`] for the time of the
+    /// rerun and restored if it fails. Tests that pass on the retry are reported as flaky, see
+    /// [`Paths::flaky_tests_report`].
+    pub async fn rerun_failed_tests(
+        &self,
+        options: &StandardLibraryTestOptions,
+        cache: &Cache,
+    ) -> Result {
+        let backup = self.paths.test_results_before_rerun();
+        if backup.exists() {
+            warn!(
+                "Restoring the test results left by an interrupted rerun in {}.",
+                backup.display()
+            );
+            self.restore_test_results(&backup)?;
+        }
+        let is_selected =
+            |library: &String| options.libraries.is_empty() || options.libraries.contains(library);
+        let previously_failed: BTreeMap<_, _> =
+            self.failed_tests()?.into_iter().filter(|(library, _)| is_selected(library)).collect();
+        if previously_failed.is_empty() {
+            info!("No failed tests recorded in {}.", self.paths.test_results.display());
+            return Ok(());
+        }
+        ide_ci::fs::create_dir_if_missing(&backup)?;
+        for library in previously_failed.keys() {
+            ide_ci::fs::rename(self.paths.test_results.join(library), backup.join(library))?;
+        }
+
+        // The failed tests may belong to any shard, so all of them are rerun.
+        let rerun_options = StandardLibraryTestOptions {
+            shard: None,
+            libraries: previously_failed.keys().cloned().collect(),
+            rerun: previously_failed.clone(),
+            ..options.clone()
+        };
+        let mut result = self.run_tests(IrCaches::No, &rerun_options, cache).await;
+        if result.is_ok() {
+            result = self.run_tests(IrCaches::Yes, &rerun_options, cache).await;
+        }
+
+        // Report the flaky tests even if some tests are still failing.
+        let reports = self.reports_by_library()?;
+        let mut flaky = Vec::new();
+        for (library, names) in &previously_failed {
+            let passed = reports.get(library).into_iter().flat_map(|report| &report.suites);
+            let passed = passed
+                .flat_map(|suite| &suite.test_cases)
+                .filter(|case| case.outcome == junit::Outcome::Passed)
+                .map(|case| &case.name)
+                .collect::<BTreeSet<_>>();
+            for name in names.iter().filter(|name| passed.contains(name)) {
+                warn!("Flaky test in {library}: {name} failed before but passed on retry.");
+                flaky.push(format!("{library}: {name}"));
+            }
+        }
+        if !flaky.is_empty() {
+            let report_path = self.paths.flaky_tests_report();
+            ide_ci::fs::write(&report_path, flaky.iter().join("\n"))?;
+            info!("Flaky tests report written to {}.", report_path.display());
+        }
+        match result {
+            Ok(()) => ide_ci::fs::remove_dir_if_exists(&backup),
+            Err(e) => {
+                self.restore_test_results(&backup)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Move the test results of each library from the `backup` directory back to
+    /// [`Paths::test_results`], replacing the current ones. Then remove the `backup`.
+    pub fn restore_test_results(&self, backup: &Path) -> Result {
+        for entry in ide_ci::fs::read_dir(backup)? {
+            let entry = entry?;
+            let target = self.paths.test_results.join(entry.file_name());
+            ide_ci::fs::remove_dir_if_exists(&target)?;
+            ide_ci::fs::rename(entry.path(), &target)?;
+        }
+        ide_ci::fs::remove_dir_if_exists(backup)
+    }
+
+    /// JUnit reports of the previous test run, keyed by the test library.
+    ///
+    /// Each library's test suite writes its reports into a subdirectory named after it.
+    pub fn reports_by_library(&self) -> Result<BTreeMap<String, junit::Report>> {
+        let mut ret = BTreeMap::new();
+        for library in crate::paths::LIBRARIES_TO_TEST {
+            let dir = self.paths.test_results.join(library);
+            if dir.exists() {
+                ret.insert(library.to_string(), junit::Report::read_dir(dir)?);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Names of the failed test cases, keyed by the test library.
+    pub fn failed_tests(&self) -> Result<BTreeMap<String, BTreeSet<String>>> {
+        let mut ret = BTreeMap::new();
+        for (library, report) in self.reports_by_library()? {
+            let failed: BTreeSet<_> =
+                report.failed_cases().map(|(_, case)| case.name.clone()).collect();
+            if !failed.is_empty() {
+                ret.insert(library, failed);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Merge all the JUnit reports written by the test suites into a single summary report.
+    ///
+    /// The per-suite results and timings are logged.
+    pub fn summarize_test_results(&self) -> Result<junit::Report> {
+        let report = junit::Report::read_dir(&self.paths.test_results)?;
+        for suite in &report.suites {
+            info!(
+                "{}: {} tests, {} failed, {} errors, {} skipped in {:.1}s.",
+                suite.name,
+                suite.test_cases.len(),
+                suite.failures(),
+                suite.errors(),
+                suite.skipped(),
+                suite.time
+            );
+        }
+        let summary_path = self.paths.test_results_summary();
+        report.write(&summary_path)?;
+        info!("Merged test report written to {}.", summary_path.display());
+        Ok(report)
+    }
 }
 
 #[async_trait]
@@ -144,3 +476,53 @@ impl Program for BuiltEnso {
         Ok(version.version)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shards_cover_all_tests() -> Result {
+        let shards = ["1/3", "2/3", "3/3"].map(str::parse::<Shard>);
+        let mut covered = Vec::new();
+        for shard in shards {
+            let options = StandardLibraryTestOptions { shard: Some(shard?), ..default() };
+            covered.extend(options.selected_tests()?);
+        }
+        covered.sort();
+        let mut all = crate::paths::LIBRARIES_TO_TEST.to_vec();
+        all.sort();
+        assert_eq!(covered, all);
+        assert!("0/3".parse::<Shard>().is_err());
+        assert!("4/3".parse::<Shard>().is_err());
+        assert_eq!("2/3".parse::<Shard>()?.to_string(), "2/3");
+        Ok(())
+    }
+
+    #[test]
+    fn select_libraries() -> Result {
+        let options =
+            StandardLibraryTestOptions { libraries: vec!["Table_Tests".into()], ..default() };
+        assert_eq!(options.selected_tests()?, vec!["Table_Tests"]);
+        let options = StandardLibraryTestOptions { libraries: vec!["Nope".into()], ..default() };
+        assert!(options.selected_tests().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn spec_pattern_combines_filter_and_rerun() {
+        let filter = Some("Table".to_string());
+        let names = ["a.b".to_string(), "c".to_string()].into_iter().collect();
+        let rerun: BTreeMap<_, _> = [("Table_Tests".to_string(), names)].into_iter().collect();
+        let options = StandardLibraryTestOptions { filter: filter.clone(), ..default() };
+        assert_eq!(options.spec_pattern("Table_Tests").as_deref(), Some("Table"));
+        let options = StandardLibraryTestOptions { rerun: rerun.clone(), ..default() };
+        assert_eq!(options.spec_pattern("Table_Tests").as_deref(), Some(r"^(?:a\.b|c)$"));
+        assert_eq!(options.spec_pattern("Tests"), None);
+        let options = StandardLibraryTestOptions { filter, rerun, ..default() };
+        let pattern = options.spec_pattern("Table_Tests");
+        assert_eq!(pattern.as_deref(), Some(r"(?=.*(?:Table))^(?:a\.b|c)$"));
+        assert_eq!(options.spec_pattern("Tests").as_deref(), Some("Table"));
+        assert_eq!(StandardLibraryTestOptions::default().spec_pattern("Tests"), None);
+    }
+}
@@ -1,8 +1,25 @@
 use crate::prelude::*;
 
+use crate::test_service::Environment;
+use crate::test_service::Readiness;
+use crate::test_service::RunningService;
+use crate::test_service::TestService;
+
+use ide_ci::cache::Cache;
+use ide_ci::cache::Storable;
 use ide_ci::env::Variable;
 use ide_ci::programs::Go;
-use tokio::process::Child;
+
+
+
+/// Go package providing the httpbin server.
+pub const PACKAGE: &str = "github.com/ahmetb/go-httpbin/cmd/httpbin";
+
+/// Go module version query used to build httpbin.
+///
+/// The built binary is cached under this value, so it is built only once. Changing it triggers a
+/// rebuild.
+pub const VERSION: &str = "master";
 
 pub mod env {
     /// Environment variable that stores URL under which spawned httpbin server is available.
@@ -14,58 +31,128 @@ pub mod env {
     }
 }
 
-#[derive(Debug)]
-pub struct Spawned {
-    pub process: Child,
+/// The httpbin server, used by the HTTP tests of the Standard Library.
+#[derive(Clone, Debug)]
+pub struct Httpbin {
+    /// Path to the httpbin binary, see [`install`].
+    pub program: PathBuf,
+    pub port:    u16,
     pub url:     Url,
 }
 
-pub async fn get_and_spawn_httpbin(port: u16) -> Result<Spawned> {
-    Go.cmd()?
-        .args(["install", "-v", "github.com/ahmetb/go-httpbin/cmd/httpbin@latest"])
-        .run_ok()
-        .await?;
-    let gopath = Go.cmd()?.args(["env", "GOPATH"]).run_stdout().await?;
-    let gopath = gopath.trim();
-    let gopath = PathBuf::from(gopath); // be careful of trailing newline!
-    let program = gopath.join("bin").join("httpbin");
-    debug!("Will spawn {}", program.display());
-    let process = Command::new(program) // TODO? wrap in Program?
-        .args(["-host", &format!(":{port}")])
-        .kill_on_drop(true)
-        .spawn_intercepting()
-        .anyhow_err()?;
-
-    let url_string = format!("http://localhost:{port}");
-    let url = Url::parse(&url_string)?;
-    env::Url.set(&url);
-    Ok(Spawned { url, process })
+impl Httpbin {
+    pub fn new(program: impl Into<PathBuf>, port: u16) -> Result<Self> {
+        let url = Url::parse(&format!("http://localhost:{port}"))?;
+        Ok(Self { program: program.into(), port, url })
+    }
 }
 
-impl Drop for Spawned {
-    fn drop(&mut self) {
-        debug!("Dropping the httpbin wrapper.");
-        env::Url.remove();
+#[async_trait]
+impl TestService for Httpbin {
+    fn name(&self) -> String {
+        format!("httpbin:{}", self.port)
+    }
+
+    fn command(&self) -> Result<Command> {
+        let mut command = Command::new(&self.program); // TODO? wrap in Program?
+        command.args(["-host", &format!(":{}", self.port)]);
+        Ok(command)
+    }
+
+    fn readiness(&self) -> Readiness {
+        Readiness::HttpGet { url: self.url.clone() }
+    }
+
+    fn env(&self) -> Environment {
+        vec![(env::Url::NAME.into(), self.url.to_string())]
     }
 }
 
-pub async fn get_and_spawn_httpbin_on_free_port() -> Result<Spawned> {
-    get_and_spawn_httpbin(ide_ci::get_free_port()?).await
+/// Key of the cached httpbin binary.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Key {
+    pub package: String,
+    pub version: String,
+    pub os:      String,
+    pub arch:    String,
+}
+
+/// The httpbin binary built from the Go sources. Requires Go only when not yet cached.
+#[derive(Clone, Debug)]
+pub struct BuiltHttpbin {
+    pub version: String,
+}
+
+impl Storable for BuiltHttpbin {
+    /// Path to the binary, relative to the cache entry.
+    type Metadata = PathBuf;
+    type Output = PathBuf;
+    type Key = Key;
+
+    fn generate(
+        &self,
+        _cache: Cache,
+        store: PathBuf,
+    ) -> BoxFuture<'static, Result<Self::Metadata>> {
+        let version = self.version.clone();
+        async move {
+            Go.cmd()?
+                .args(["install", "-v"])
+                .arg(format!("{PACKAGE}@{version}"))
+                .env("GOBIN", &store)
+                .run_ok()
+                .await?;
+            let binary = PathBuf::from("httpbin").with_extension(std::env::consts::EXE_EXTENSION);
+            ide_ci::fs::require_exist(store.join(&binary))?;
+            Ok(binary)
+        }
+        .boxed()
+    }
+
+    fn adapt(
+        &self,
+        store: PathBuf,
+        metadata: Self::Metadata,
+    ) -> BoxFuture<'static, Result<Self::Output>> {
+        ready(Ok(store.join(metadata))).boxed()
+    }
+
+    fn key(&self) -> Self::Key {
+        Key {
+            package: PACKAGE.into(),
+            version: self.version.clone(),
+            os:      TARGET_OS.to_string(),
+            arch:    TARGET_ARCH.to_string(),
+        }
+    }
+}
+
+/// Get the httpbin binary of the [`VERSION`] from the cache, building it if needed.
+pub async fn install(cache: &Cache) -> Result<PathBuf> {
+    cache.get(BuiltHttpbin { version: VERSION.into() }).await
+}
+
+pub async fn get_and_spawn_httpbin(cache: &Cache, port: u16) -> Result<RunningService> {
+    let program = install(cache).await?;
+    RunningService::start(Httpbin::new(program, port)?).await
+}
+
+pub async fn get_and_spawn_httpbin_on_free_port(cache: &Cache) -> Result<RunningService> {
+    get_and_spawn_httpbin(cache, ide_ci::get_free_port()?).await
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::project::ProcessWrapper;
-
     use super::*;
 
 
     #[tokio::test]
     #[ignore]
     async fn spawn() -> Result {
-        let mut spawned = get_and_spawn_httpbin_on_free_port().await?;
+        let cache = Cache::new_default().await?;
+        let spawned = get_and_spawn_httpbin_on_free_port(&cache).await?;
         dbg!(&spawned);
-        spawned.process.wait_ok().await?;
+        spawned.stop().await?;
         Ok(())
     }
 }
@@ -8,7 +8,9 @@ use crate::ide::web::env::CSC_KEY_PASSWORD;
 use crate::paths::generated;
 use anyhow::Context;
 use futures_util::future::try_join;
-use futures_util::future::try_join4;
+use futures_util::future::try_join3;
+use ide_ci::cache::pinned::PinnedDownload;
+use ide_ci::cache::Cache;
 use ide_ci::io::download_all;
 use ide_ci::models::config::RepoContext;
 use ide_ci::program::command;
@@ -37,6 +39,9 @@ pub const GOOGLE_FONTS_REPOSITORY: &str = "google/fonts";
 
 pub const GOOGLE_FONT_DIRECTORY: &str = "ofl";
 
+/// The Google font family bundled with the GUI.
+pub const GOOGLE_FONT_FAMILY: &str = "mplus1";
+
 pub mod env {
     use super::*;
 
@@ -122,6 +127,93 @@ pub async fn download_js_assets(output_path: impl AsRef<Path>) -> Result {
     Ok(())
 }
 
+/// Version of a downloaded file or archive, along with its expected SHA-256 digest.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Pin {
+    /// Commit hash of the repository that the file comes from.
+    pub version: String,
+    pub sha256:  String,
+}
+
+/// Files of the [`GOOGLE_FONT_FAMILY`] from the [`GOOGLE_FONTS_REPOSITORY`] at the given commit.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FontPin {
+    pub version: String,
+    /// SHA-256 digests of the font files, by file name.
+    pub files:   BTreeMap<String, String>,
+}
+
+/// The downloaded GUI inputs, pinned in the `gui-assets` section of the build configuration.
+///
+/// The inputs that are not pinned are downloaded in their latest version on every build and
+/// cannot be used in the offline mode.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct AssetPins {
+    /// The `enso-org/ide-assets` repository archive.
+    pub ide_assets: Option<Pin>,
+    pub font:       Option<FontPin>,
+}
+
+impl AssetPins {
+    /// Get the assets from the cache (downloading them if needed) and copy them to the directory.
+    pub async fn get(&self, cache: &Cache, octocrab: &Octocrab, output_path: &Path) -> Result {
+        let js_assets = async {
+            match &self.ide_assets {
+                Some(pin) => get_js_assets(cache, pin, output_path).await,
+                None => {
+                    ensure!(
+                        !cache.is_offline(),
+                        "IDE assets are not pinned, cannot use them offline."
+                    );
+                    warn!("IDE assets are not pinned, downloading the latest version.");
+                    download_js_assets(output_path).await
+                }
+            }
+        };
+        let font = async {
+            match &self.font {
+                Some(pin) => get_google_font(cache, pin, output_path).await,
+                None => {
+                    ensure!(!cache.is_offline(), "The font is not pinned, cannot use it offline.");
+                    warn!("The font is not pinned, downloading the latest version.");
+                    download_google_font(octocrab, GOOGLE_FONT_FAMILY, output_path).await?;
+                    Ok(())
+                }
+            }
+        };
+        try_join(js_assets, font).await?;
+        Ok(())
+    }
+}
+
+/// Get the pinned version of the `enso-org/ide-assets` archive and extract its assets to the
+/// directory.
+pub async fn get_js_assets(cache: &Cache, pin: &Pin, output_path: &Path) -> Result {
+    let url = format!("https://github.com/enso-org/ide-assets/archive/{}.zip", pin.version);
+    let download = PinnedDownload::new(&pin.version, url, &pin.sha256)?;
+    let archive = cache.get(download).await?;
+    let archived_asset_prefix =
+        PathBuf::from(format!("ide-assets-{}/content/assets/", pin.version));
+    let mut archive = zip::ZipArchive::new(ide_ci::fs::open(&archive)?)?;
+    ide_ci::archive::zip::extract_subtree(&mut archive, &archived_asset_prefix, &output_path)?;
+    Ok(())
+}
+
+/// Get the pinned font files and copy them to the directory.
+pub async fn get_google_font(cache: &Cache, pin: &FontPin, output_path: &Path) -> Result {
+    for (name, sha256) in &pin.files {
+        let url = format!(
+            "https://raw.githubusercontent.com/{GOOGLE_FONTS_REPOSITORY}/{}/{GOOGLE_FONT_DIRECTORY}/{GOOGLE_FONT_FAMILY}/{name}",
+            pin.version
+        );
+        let download = PinnedDownload::new(&pin.version, url, sha256)?;
+        let file = cache.get(download).await?;
+        ide_ci::fs::copy(&file, output_path.join(name))?;
+    }
+    Ok(())
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum Workspaces {
     Icons,
@@ -159,14 +251,13 @@ impl<Output: AsRef<Path>> ContentEnvironment<TempDir, Output> {
         ide: &IdeDesktop,
         wasm: impl Future<Output = Result<wasm::Artifact>>,
         build_info: &BuildInfo,
+        assets: &AssetPins,
         output_path: Output,
     ) -> Result<Self> {
         let installation = ide.install();
         let asset_dir = TempDir::new()?;
-        let assets_download = download_js_assets(&asset_dir);
-        let fonts_download = download_google_font(&ide.octocrab, "mplus1", &asset_dir);
-        let (wasm, _, _, _) =
-            try_join4(wasm, installation, assets_download, fonts_download).await?;
+        let assets_download = assets.get(&ide.cache, &ide.octocrab, asset_dir.path());
+        let (wasm, _, _) = try_join3(wasm, installation, assets_download).await?;
         ide.write_build_info(&build_info)?;
         Ok(ContentEnvironment { asset_dir, wasm, output_path })
     }
@@ -260,9 +351,10 @@ impl IdeDesktop {
         &self,
         wasm: impl Future<Output = Result<wasm::Artifact>>,
         build_info: &BuildInfo,
+        assets: &AssetPins,
         output_path: impl AsRef<Path>,
     ) -> Result {
-        let env = ContentEnvironment::new(self, wasm, build_info, output_path).await?;
+        let env = ContentEnvironment::new(self, wasm, build_info, assets, output_path).await?;
         //env.apply();
         self.npm()?
             .try_applying(&env)?
@@ -284,6 +376,7 @@ impl IdeDesktop {
         &self,
         wasm: impl Future<Output = Result<wasm::Artifact>>,
         build_info: &BuildInfo,
+        assets: &AssetPins,
         shell: bool,
     ) -> Result<Watcher> {
         // When watching we expect our artifacts to be served through server, not appear in any
@@ -292,7 +385,7 @@ impl IdeDesktop {
         // let span = tracing::
         // let wasm = wasm.inspect()
         let watch_environment =
-            ContentEnvironment::new(self, wasm, build_info, output_path).await?;
+            ContentEnvironment::new(self, wasm, build_info, assets, output_path).await?;
         Span::current().record("wasm", &watch_environment.wasm.as_str());
         let child_process = if shell {
             ide_ci::os::default_shell()
@@ -40,6 +40,7 @@ pub mod prelude {
 }
 
 pub mod aws;
+pub mod blob_store;
 pub mod build2;
 pub mod bump_version;
 pub mod changelog;
@@ -56,10 +57,13 @@ pub mod prettier;
 pub mod programs;
 pub mod project;
 pub mod project_manager;
+pub mod registry;
 pub mod release;
 pub mod repo;
 pub mod rust;
 pub mod source;
+pub mod test_env;
+pub mod test_service;
 pub mod version;
 
 /// Get version of Enso from the `build.sbt` file contents.
@@ -164,6 +164,26 @@ impl Paths {
         })
     }
 
+    /// The JUnit report merged from all the reports under [`Paths::test_results`].
+    ///
+    /// Placed outside that directory, so it is not picked up again by the next merge or by the
+    /// CI test reporter.
+    pub fn test_results_summary(&self) -> PathBuf {
+        self.target.join("test-results-summary.xml")
+    }
+
+    /// Results of the previous test run, moved aside while its failed tests are rerun.
+    ///
+    /// Placed outside [`Paths::test_results`] for the same reasons as the summary.
+    pub fn test_results_before_rerun(&self) -> PathBuf {
+        self.target.join("test-results-before-rerun")
+    }
+
+    /// List of the tests that failed at first but passed when rerun with `--rerun-failed`.
+    pub fn flaky_tests_report(&self) -> PathBuf {
+        self.target.join("flaky-tests.txt")
+    }
+
     /// Create a new set of paths for building the Enso with a given version number.
     pub fn new_versions(repo_root: impl Into<PathBuf>, versions: Versions) -> Result<Self> {
         let triple = TargetTriple::new(versions);
@@ -1,21 +1,33 @@
 use crate::prelude::*;
 
+use crate::test_service::Environment;
+use crate::test_service::Readiness;
+use crate::test_service::TestService;
+
 use ide_ci::env::new::RawVariable;
-use ide_ci::env::new::TypedVariable;
 use ide_ci::get_free_port;
 use ide_ci::programs::docker::ContainerId;
 use ide_ci::programs::docker::ImageId;
 use ide_ci::programs::docker::Network;
 use ide_ci::programs::docker::RunOptions;
+use ide_ci::programs::postgres::CreateDb;
+use ide_ci::programs::postgres::InitDb;
 use ide_ci::programs::Docker;
-use std::process::Stdio;
-use tokio::io::AsyncBufReadExt;
-use tokio::io::AsyncRead;
-use tokio::io::BufReader;
-use tokio::process::Child;
+use regex::Regex;
+use std::time::Duration;
+use tempfile::TempDir;
 
 /// Port used by Postgres in its container.
-const POSTGRES_CONTAINER_DEFAULT_PORT: u16 = 5432;
+pub const POSTGRES_CONTAINER_DEFAULT_PORT: u16 = 5432;
+
+/// Name of the database created for the Standard Library tests.
+pub const TEST_DATABASE_NAME: &str = "enso_test_db";
+
+/// User owning the [`TEST_DATABASE_NAME`] database.
+pub const TEST_USER: &str = "enso_test_user";
+
+/// Password of the [`TEST_USER`].
+pub const TEST_PASSWORD: &str = "enso_test_password";
 
 /// Environment variables used to configure the Postgres container.
 pub mod env {
@@ -37,6 +49,41 @@ pub mod env {
     }
 }
 
+/// How to run the Postgres server for the tests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum Backend {
+    /// Run the official image in a Docker container. Supported only on Linux.
+    Docker,
+    /// Run the locally installed (or downloaded portable) server binaries.
+    Native,
+}
+
+impl Backend {
+    /// The backend to use when none was requested.
+    ///
+    /// Outside Linux the native backend is always available, as the portable binaries can be
+    /// downloaded, see [`ide_ci::cache::goodie::postgresql`].
+    pub fn deduce() -> Option<Self> {
+        if TARGET_OS == OS::Linux && Docker.lookup().is_ok() {
+            Some(Backend::Docker)
+        } else if TARGET_OS != OS::Linux || InitDb.lookup().is_ok() {
+            Some(Backend::Native)
+        } else {
+            None
+        }
+    }
+}
+
+/// Environment variables pointing the Enso tests to the given database.
+pub fn enso_test_env(host: &str, database_name: &str, user: &str, password: &str) -> Environment {
+    vec![
+        (env::tests::ENSO_DATABASE_TEST_DB_NAME.name().into(), database_name.into()),
+        (env::tests::ENSO_DATABASE_TEST_HOST.name().into(), host.into()),
+        (env::tests::ENSO_DATABASE_TEST_DB_USER.name().into(), user.into()),
+        (env::tests::ENSO_DATABASE_TEST_DB_PASSWORD.name().into(), password.into()),
+    ]
+}
+
 #[derive(Clone, Debug)]
 pub enum EndpointConfiguration {
     /// Used when the Postgres container is started directly from host (rather than Docker
@@ -82,23 +129,18 @@ impl Configuration {
         ImageId(format!("postgres:{}", &self.version))
     }
 
-    pub fn set_enso_test_env(&self) -> Result {
-        env::tests::ENSO_DATABASE_TEST_DB_NAME.set(&self.database_name)?;
-        env::tests::ENSO_DATABASE_TEST_HOST.set(match &self.endpoint {
+    /// Address under which the database server is available to the tests.
+    pub fn host(&self) -> String {
+        match &self.endpoint {
             EndpointConfiguration::Host { port } => format!("localhost:{port}"),
             EndpointConfiguration::Container { .. } =>
                 format!("localhost:{POSTGRES_CONTAINER_DEFAULT_PORT}"),
-        })?;
-        env::tests::ENSO_DATABASE_TEST_DB_USER.set(&self.user)?;
-        env::tests::ENSO_DATABASE_TEST_DB_PASSWORD.set(&self.password)?;
-        Ok(())
+        }
     }
 
-    pub fn clear_enso_test_env(&self) {
-        env::tests::ENSO_DATABASE_TEST_DB_NAME.remove();
-        env::tests::ENSO_DATABASE_TEST_HOST.remove();
-        env::tests::ENSO_DATABASE_TEST_DB_USER.remove();
-        env::tests::ENSO_DATABASE_TEST_DB_PASSWORD.remove();
+    /// Environment variables pointing the Enso tests to the database with the given name.
+    pub fn enso_test_env(&self, database_name: &str) -> Environment {
+        enso_test_env(&self.host(), database_name, &self.user, &self.password)
     }
 
     pub async fn cleanup(&self) -> Result {
@@ -106,66 +148,28 @@ impl Configuration {
     }
 }
 
-/// Retrieve input from asynchronous reader line by line and feed them into the given function.
-pub async fn process_lines<R: AsyncRead + Unpin>(reader: R, f: impl Fn(String)) -> Result<R> {
-    debug!("Started line processor.");
-    let mut reader = BufReader::new(reader);
-    let mut line = String::new();
-    while reader.read_line(&mut line).await? != 0 {
-        f(std::mem::take(&mut line));
-    }
-    Ok(reader.into_inner())
+/// Regex matching the log line printed by the server once it accepts connections.
+fn ready_log_line() -> Regex {
+    // The pattern is a constant, so it is known to be valid.
+    Regex::new("database system is ready to accept connections").unwrap()
 }
 
-pub async fn process_lines_until<R: AsyncRead + Unpin>(
-    reader: R,
-    f: &impl Fn(&str) -> bool,
-) -> Result<R> {
-    let mut reader = BufReader::new(reader);
-    let mut line = String::new();
-    loop {
-        let bytes_read = reader.read_line(&mut line).await?;
-        ensure!(bytes_read != 0, "Postgresql container closed without being ready!");
-        if f(&line) {
-            break;
-        }
-        line.clear();
-    }
-    Ok(reader.into_inner())
-}
-
-#[derive(Debug)]
-pub struct PostgresContainer {
-    _docker_run: Child,
-    config:      Configuration,
+/// Postgres server running in a Docker container.
+///
+/// Each test suite gets its own database, see [`TestService::prepare_suite`].
+#[derive(Clone, Debug)]
+pub struct Postgresql {
+    pub config: Configuration,
 }
 
-impl Drop for PostgresContainer {
-    fn drop(&mut self) {
-        self.config.clear_enso_test_env();
-
-        debug!("Will remove the postgres container");
-        let cleanup_future = self.config.cleanup();
-        if let Err(e) = futures::executor::block_on(cleanup_future) {
-            debug!(
-                "Failed to kill the Postgres container named {}: {}",
-                self.config.postgres_container, e
-            );
-        } else {
-            debug!("Postgres container killed.");
-        }
+#[async_trait]
+impl TestService for Postgresql {
+    fn name(&self) -> String {
+        format!("postgres ({})", self.config.postgres_container)
     }
-}
-
-#[derive(Clone, Copy, Debug)]
-pub struct Postgresql;
-
-impl Postgresql {
-    pub async fn start(config: Configuration) -> Result<PostgresContainer> {
-        // Attempt cleanup in case previous script run crashed in the middle of this.
-        // Otherwise, postgres container names could collide.
-        let _ = config.cleanup().await;
 
+    fn command(&self) -> Result<Command> {
+        let config = &self.config;
         let mut opts = RunOptions::new(config.image_id());
         opts.env(&env::container::POSTGRES_DB, &*config.database_name)?;
         opts.env(&env::container::POSTGRES_USER, &*config.user)?;
@@ -180,27 +184,124 @@ impl Postgresql {
         }
         opts.sig_proxy = Some(true);
         opts.name = Some(config.postgres_container.to_string());
+        // Checking over TCP, as the temporary server run during the initialization listens only on
+        // the Unix socket.
+        opts.health_cmd = Some(format!(
+            "pg_isready --host=127.0.0.1 --username={} --dbname={}",
+            config.user, config.database_name
+        ));
+        opts.health_interval = Some(Duration::from_secs(1));
+        Docker.run_cmd(&opts)
+    }
 
-        let mut cmd = Docker.run_cmd(&opts)?;
-        cmd.stderr(Stdio::piped());
-        cmd.kill_on_drop(true);
-        let mut child = cmd.spawn().anyhow_err()?;
-        let stderr = child
-            .stderr
-            .ok_or_else(|| anyhow!("Failed to access standard output of the spawned process!"))?;
-
-        // Wait until container is ready.
-        let check_line = |line: &str| {
-            debug!("ERR: {}", line);
-            line.contains("database system is ready to accept connections")
-        };
-        let stderr = process_lines_until(stderr, &check_line).await?;
+    fn readiness(&self) -> Readiness {
+        Readiness::ContainerHealthy { container: self.config.postgres_container.clone() }
+    }
+
+    fn env(&self) -> Environment {
+        self.config.enso_test_env(&self.config.database_name)
+    }
+
+    async fn prepare_suite(&self, suite: &str) -> Result<Environment> {
+        let database_name = format!("{}_{}", self.config.database_name, suite.to_lowercase());
+        debug!("Creating database {database_name}.");
+        let container = &self.config.postgres_container;
+        let user = self.config.user.as_str();
+        Docker.exec(container, ["createdb", "--username", user, &database_name]).await?;
+        Ok(self.config.enso_test_env(&database_name))
+    }
+
+    async fn cleanup(&self) -> Result {
+        self.config.cleanup().await
+    }
+}
+
+/// Postgres server run directly from the server binaries, without Docker.
+///
+/// The binaries are looked up in `PATH` and the usual installation directories, see
+/// [`ide_ci::programs::postgres`]. On Windows and macOS they can also be downloaded, see
+/// [`ide_ci::cache::goodie::postgresql`].
+///
+/// The cluster is created in a throwaway data directory, removed when this value is dropped.
+/// Authentication is disabled, so the password is accepted but not checked.
+#[derive(Debug)]
+pub struct NativePostgresql {
+    /// Prefix of the per-suite database names.
+    pub database_name: String,
+    pub user:          String,
+    pub password:      String,
+    pub port:          u16,
+    data_dir:          TempDir,
+}
+
+impl NativePostgresql {
+    pub fn new(database_name: &str, user: &str, password: &str) -> Result<Self> {
+        Ok(Self {
+            database_name: database_name.into(),
+            user:          user.into(),
+            password:      password.into(),
+            port:          get_free_port()?,
+            data_dir:      tempfile::tempdir()?,
+        })
+    }
 
-        // Put back stream we've been reading and pack the whole thing back for the caller.
-        child.stderr = Some(stderr);
+    pub fn cluster_dir(&self) -> PathBuf {
+        self.data_dir.path().join("data")
+    }
 
-        config.set_enso_test_env()?;
-        Ok(PostgresContainer { _docker_run: child, config })
+    pub fn host(&self) -> String {
+        format!("localhost:{}", self.port)
+    }
+}
+
+#[async_trait]
+impl TestService for NativePostgresql {
+    fn name(&self) -> String {
+        format!("postgres:{}", self.port)
+    }
+
+    fn command(&self) -> Result<Command> {
+        let mut command = ide_ci::programs::postgres::Postgres.cmd()?;
+        command.arg("-D").arg(self.cluster_dir()).args([
+            "-p",
+            self.port.to_string().as_str(),
+            "-c",
+            "listen_addresses=localhost",
+        ]);
+        if TARGET_OS != OS::Windows {
+            // The default socket directory (like `/var/run/postgresql`) might be not writable.
+            command.arg("-k").arg(self.data_dir.path());
+        }
+        Ok(command)
+    }
+
+    fn readiness(&self) -> Readiness {
+        Readiness::LogLine { pattern: ready_log_line() }
+    }
+
+    fn env(&self) -> Environment {
+        // The maintenance database, always present in a fresh cluster.
+        enso_test_env(&self.host(), "postgres", &self.user, &self.password)
+    }
+
+    async fn setup(&self) -> Result {
+        ensure!(
+            TARGET_OS == OS::Windows || whoami::username() != "root",
+            "The Postgres server refuses to run as root. Use the Docker backend instead."
+        );
+        InitDb.init_trusting(self.cluster_dir(), &self.user)?.run_ok().await
+    }
+
+    async fn prepare_suite(&self, suite: &str) -> Result<Environment> {
+        let database_name = format!("{}_{}", self.database_name, suite.to_lowercase());
+        debug!("Creating database {database_name}.");
+        CreateDb
+            .cmd()?
+            .args(["--host", "localhost", "--port", self.port.to_string().as_str()])
+            .args(["--username", self.user.as_str(), database_name.as_str()])
+            .run_ok()
+            .await?;
+        Ok(enso_test_env(&self.host(), &database_name, &self.user, &self.password))
     }
 }
 
@@ -219,12 +320,26 @@ mod tests {
             password:           "test".into(),
             database_name:      "test".into(),
         };
-        let child = Postgresql::start(config).await?;
-        // drop(child);
+        let child = crate::test_service::RunningService::start(Postgresql { config }).await?;
+        // child.stop().await?;
         std::mem::forget(child);
         Ok(())
     }
 
+    #[tokio::test]
+    async fn native_postgres() -> Result {
+        if InitDb.lookup().is_err() || whoami::username() == "root" {
+            // Only where the server binaries are installed and usable.
+            return Ok(());
+        }
+        let service = NativePostgresql::new("test", "test_user", "test_password")?;
+        let running = crate::test_service::RunningService::start(service).await?;
+        let env = running.service.prepare_suite("Table_Tests").await?;
+        assert!(env.contains(&("ENSO_DATABASE_TEST_DB_NAME".into(), "test_table_tests".into())));
+        running.stop().await?;
+        Ok(())
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_postgres() -> Result {
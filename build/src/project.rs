@@ -24,6 +24,7 @@ pub mod ide;
 pub mod project_manager;
 pub mod runtime;
 pub mod wasm;
+pub mod watch;
 
 pub use backend::Backend;
 pub use gui::Gui;
@@ -114,6 +115,13 @@ pub trait IsTarget: Clone + Debug + Sized + Send + Sync + 'static {
     /// Create a full artifact description from an on-disk representation.
     fn adapt_artifact(self, path: impl AsRef<Path>) -> BoxFuture<'static, Result<Self::Artifact>>;
 
+    /// Directories with the sources of this target. Changes in them trigger rebuilds when the
+    /// target is watched, see [`watch::watch`].
+    fn source_roots(&self, context: &Context, input: &Self::BuildInput) -> Vec<PathBuf> {
+        let _ = input;
+        vec![context.repo_root.to_path_buf()]
+    }
+
     fn get(
         &self,
         context: Context,
@@ -317,7 +325,8 @@ pub struct Watcher<Target: IsWatchable, Proc> {
     pub artifact:      Target::Artifact,
     /// The process performing the watch.
     ///
-    /// For example, an instance of cargo-watch.
+    /// For example, an instance of the esbuild watcher. Targets built in-process are watched by
+    /// [`watch::NativeWatcher`] instead.
     pub watch_process: Proc,
 }
 
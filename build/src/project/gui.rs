@@ -1,5 +1,7 @@
 use crate::prelude::*;
 
+use crate::blob_store::BlobStore;
+use crate::ide::web::AssetPins;
 use crate::ide::web::IdeDesktop;
 use crate::project::Context;
 use crate::project::IsTarget;
@@ -18,8 +20,42 @@ use derivative::Derivative;
 use futures_util::future::try_join;
 use ide_ci::ok_ready_boxed;
 
+pub mod manifest;
+
+pub use manifest::Manifest;
+
+
+
 pub type Artifact = PlainArtifact<Gui>;
 
+impl Artifact {
+    /// The manifest written into the artifact by the build.
+    ///
+    /// If the artifact has none (e.g. it was built by an older version of this tool), the manifest
+    /// is generated from the artifact's contents.
+    pub fn manifest(&self) -> Result<Manifest> {
+        if self.path.join(manifest::MANIFEST_FILENAME).exists() {
+            Manifest::read(&self.path)
+        } else {
+            Manifest::generate(&self.path)
+        }
+    }
+
+    /// Upload the files that are not in the store yet, under their content-hashed and original
+    /// names. All are placed under the `prefix`, e.g. `gui/`.
+    ///
+    /// Returns the number of uploaded files. See [`manifest::deploy`].
+    pub async fn deploy(&self, store: &dyn BlobStore, prefix: &str) -> Result<usize> {
+        let manifest = self.manifest()?;
+        info!(
+            "Deploying GUI: {} files, {} bytes in total.",
+            manifest.files.len(),
+            manifest.total_size()
+        );
+        manifest::deploy(&self.path, &manifest, store, prefix).await
+    }
+}
+
 #[derive(Clone, Derivative, derive_more::Deref)]
 #[derivative(Debug)]
 pub struct WatchInput {
@@ -37,6 +73,7 @@ pub struct BuildInput {
     /// BoxFuture<'static, Result<wasm::Artifact>>,
     #[derivative(Debug = "ignore")]
     pub build_info: BoxFuture<'static, Result<BuildInfo>>,
+    pub assets:     AssetPins,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -64,7 +101,9 @@ impl IsTarget for Gui {
         async move {
             let ide = ide_desktop_from_context(&context);
             let wasm = Wasm.get(context, inner.wasm);
-            ide.build_content(wasm, &inner.build_info.await?, &destination).await?;
+            let build_info = inner.build_info.await?;
+            ide.build_content(wasm, &build_info, &inner.assets, &destination).await?;
+            Manifest::generate(&destination)?.write(&destination)?;
             Ok(Artifact::new(destination))
         }
         .boxed()
@@ -116,14 +155,15 @@ impl IsWatchable for Gui {
         job: WatchTargetJob<Self>,
     ) -> BoxFuture<'static, Result<Self::Watcher>> {
         let WatchTargetJob { watch_input, build: WithDestination { inner, destination } } = job;
-        let BuildInput { build_info, wasm } = inner;
+        let BuildInput { build_info, wasm, assets } = inner;
         let perhaps_watched_wasm = perhaps_watch(Wasm, context.clone(), wasm, watch_input.wasm);
         let ide = ide_desktop_from_context(&context);
         async move {
             let perhaps_watched_wasm = perhaps_watched_wasm.await?;
             let wasm_artifacts = ok_ready_boxed(perhaps_watched_wasm.as_ref().clone());
+            let build_info = build_info.await?;
             let watch_process =
-                ide.watch_content(wasm_artifacts, &build_info.await?, watch_input.shell).await?;
+                ide.watch_content(wasm_artifacts, &build_info, &assets, watch_input.shell).await?;
             let artifact = Self::Artifact::from_existing(destination).await?;
             let web_watcher = crate::project::Watcher { watch_process, artifact };
             Ok(Self::Watcher { wasm: perhaps_watched_wasm, web: web_watcher })
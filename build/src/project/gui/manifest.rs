@@ -0,0 +1,257 @@
+//! Description of the files in the GUI artifact, allowing them to be served with cache-busting
+//! names.
+//!
+//! Every file gets a content-hashed name, e.g. `assets/index.js` becomes
+//! `assets/index.0123456789abcdef.js`. As the name changes whenever the contents do, the files can
+//! be cached indefinitely. The [`Manifest`] maps the original names to the hashed ones.
+//!
+//! The built files refer to each other by their original names, so every file is also deployed
+//! under its original name, to be revalidated on every load.
+
+use crate::prelude::*;
+
+use crate::blob_store::Acl;
+use crate::blob_store::BlobStore;
+use crate::blob_store::Headers;
+use bytes::Bytes;
+use sha2::Digest;
+
+
+
+/// Name of the manifest file, placed in the artifact's root directory.
+pub const MANIFEST_FILENAME: &str = "asset-manifest.json";
+
+/// Number of the SHA-256 hex digits put in the hashed file names.
+pub const HASH_LENGTH: usize = 16;
+
+/// `Cache-Control` of the files with hashed names.
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// `Cache-Control` of the manifest and the files with original names, which must be revalidated
+/// to pick up the new deployments.
+pub const NO_CACHE_CONTROL: &str = "no-cache";
+
+/// Description of a single file.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    /// `/`-separated path relative to the artifact's root.
+    pub path:         String,
+    /// The `path` with the content hash inserted before the extension.
+    pub hashed_path:  String,
+    /// Hex-encoded SHA-256 digest of the contents.
+    pub sha256:       String,
+    /// Size in bytes.
+    pub size:         u64,
+    pub content_type: String,
+}
+
+impl Entry {
+    pub fn new(path: impl Into<String>, data: &[u8]) -> Self {
+        let path = path.into();
+        let sha256 = format!("{:x}", sha2::Sha256::digest(data));
+        let hashed_path = hashed_path(&path, &sha256);
+        let content_type = new_mime_guess::from_path(&path).first_or_octet_stream().to_string();
+        Self { path, hashed_path, sha256, size: data.len() as u64, content_type }
+    }
+}
+
+/// Insert the hash before the (last) extension of the file name.
+pub fn hashed_path(path: &str, sha256: &str) -> String {
+    let hash = &sha256[..HASH_LENGTH.min(sha256.len())];
+    let (directory, file_name) = match path.rsplit_once('/') {
+        Some((directory, file_name)) => (Some(directory), file_name),
+        None => (None, path),
+    };
+    let hashed_name = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{stem}.{hash}.{extension}"),
+        _ => format!("{file_name}.{hash}"),
+    };
+    match directory {
+        Some(directory) => format!("{directory}/{hashed_name}"),
+        None => hashed_name,
+    }
+}
+
+/// Contents of the [`MANIFEST_FILENAME`] file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Manifest {
+    /// Sorted by path.
+    pub files: Vec<Entry>,
+}
+
+impl Manifest {
+    /// Describe all the files in the directory, except for the manifest itself.
+    pub fn generate(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        let mut files = Vec::new();
+        for entry in walkdir::WalkDir::new(root).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(root)?;
+            let path = relative.iter().map(|part| part.to_string_lossy()).join("/");
+            if path != MANIFEST_FILENAME {
+                files.push(Entry::new(path, &ide_ci::fs::read(entry.path())?));
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self { files })
+    }
+
+    pub fn read(root: impl AsRef<Path>) -> Result<Self> {
+        let path = root.as_ref().join(MANIFEST_FILENAME);
+        serde_json::from_slice(&ide_ci::fs::read(&path)?)
+            .with_context(|| format!("Failed to deserialize {}.", path.display()))
+    }
+
+    pub fn write(&self, root: impl AsRef<Path>) -> Result {
+        ide_ci::fs::write(
+            root.as_ref().join(MANIFEST_FILENAME),
+            serde_json::to_string_pretty(self)?,
+        )
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Entry> {
+        self.files.iter().find(|entry| entry.path == path)
+    }
+
+    /// Total size of the files in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|entry| entry.size).sum()
+    }
+}
+
+/// Upload the files under their hashed and original names, followed by the manifest. All the
+/// paths are placed under the `prefix` in the store, e.g. `gui/`.
+///
+/// As the hashed name identifies the contents, the files already in the store are not uploaded
+/// again. The files under the original names are uploaded only if they differ from the previously
+/// deployed manifest. Returns the number of uploaded objects.
+pub async fn deploy(
+    root: &Path,
+    manifest: &Manifest,
+    store: &dyn BlobStore,
+    prefix: &str,
+) -> Result<usize> {
+    let key = |path: &str| format!("{prefix}{path}");
+    let existing_files = store.list_prefix(prefix).await?;
+    let manifest_key = key(MANIFEST_FILENAME);
+    let previous_manifest = if existing_files.contains(&manifest_key) {
+        serde_json::from_slice(&store.get(&manifest_key).await?)
+            .with_context(|| format!("Failed to deserialize the deployed {manifest_key}."))?
+    } else {
+        Manifest::default()
+    };
+    let mut uploaded = 0;
+    for entry in &manifest.files {
+        let hashed_key = key(&entry.hashed_path);
+        let original_key = key(&entry.path);
+        let is_hashed_deployed = existing_files.contains(&hashed_key);
+        let is_original_deployed = existing_files.contains(&original_key)
+            && previous_manifest.get(&entry.path) == Some(entry);
+        if is_hashed_deployed && is_original_deployed {
+            debug!("Skipping {}, as it is already deployed.", entry.path);
+            continue;
+        }
+        let data: Bytes = ide_ci::fs::read(root.join(&entry.path))?.into();
+        let actual_sha256 = format!("{:x}", sha2::Sha256::digest(&data));
+        ensure!(
+            actual_sha256 == entry.sha256,
+            "File {} does not match the manifest, it has been modified after the build.",
+            entry.path
+        );
+        let mut headers = Headers {
+            content_type:  Some(entry.content_type.clone()),
+            cache_control: Some(IMMUTABLE_CACHE_CONTROL.into()),
+        };
+        if !is_hashed_deployed {
+            info!("Uploading {} as {} ({} bytes).", entry.path, hashed_key, entry.size);
+            store.put_with_headers(&hashed_key, data.clone(), Acl::PublicRead, &headers).await?;
+            uploaded += 1;
+        }
+        if !is_original_deployed {
+            info!("Uploading {} as {} ({} bytes).", entry.path, original_key, entry.size);
+            headers.cache_control = Some(NO_CACHE_CONTROL.into());
+            store.put_with_headers(&original_key, data, Acl::PublicRead, &headers).await?;
+            uploaded += 1;
+        }
+    }
+    let headers = Headers {
+        content_type:  Some(mime::APPLICATION_JSON.to_string()),
+        cache_control: Some(NO_CACHE_CONTROL.into()),
+    };
+    let data = serde_json::to_string_pretty(manifest)?;
+    store.put_with_headers(&manifest_key, data.into(), Acl::PublicRead, &headers).await?;
+    Ok(uploaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob_store::LocalDirectory;
+
+    #[test]
+    fn hashing_paths() {
+        let hash = "0123456789abcdef0123";
+        assert_eq!(hashed_path("index.js", hash), "index.0123456789abcdef.js");
+        assert_eq!(
+            hashed_path("assets/index.js.map", hash),
+            "assets/index.js.0123456789abcdef.map"
+        );
+        assert_eq!(hashed_path("assets/LICENSE", hash), "assets/LICENSE.0123456789abcdef");
+        assert_eq!(hashed_path(".nojekyll", hash), ".nojekyll.0123456789abcdef");
+    }
+
+    #[tokio::test]
+    async fn deploying_only_changed_files() -> Result {
+        let dist = tempfile::tempdir()?;
+        ide_ci::fs::write(dist.path().join("index.html"), "<html></html>")?;
+        ide_ci::fs::write(dist.path().join("assets/index.js"), "main()")?;
+        let manifest = Manifest::generate(dist.path())?;
+        manifest.write(dist.path())?;
+        assert_eq!(Manifest::generate(dist.path())?, manifest);
+        assert_eq!(Manifest::read(dist.path())?, manifest);
+        assert_eq!(manifest.get("index.html").unwrap().content_type, "text/html");
+        assert_eq!(manifest.total_size(), 19);
+
+        let bucket = tempfile::tempdir()?;
+        let store = LocalDirectory { root: bucket.path().into() };
+        store.put("other/index.html", "other".into(), Acl::Private).await?;
+        // Each file under both its hashed and original name.
+        assert_eq!(deploy(dist.path(), &manifest, &store, "gui/").await?, 4);
+        assert_eq!(deploy(dist.path(), &manifest, &store, "gui/").await?, 0);
+
+        ide_ci::fs::write(dist.path().join("assets/index.js"), "main2()")?;
+        let manifest = Manifest::generate(dist.path())?;
+        assert_eq!(deploy(dist.path(), &manifest, &store, "gui/").await?, 2);
+        let deployed = store.list_prefix("gui/").await?;
+        assert_eq!(deployed.len(), 6);
+        assert!(deployed.contains("gui/index.html"));
+        assert!(deployed.contains("gui/asset-manifest.json"));
+        assert_eq!(store.get("gui/assets/index.js").await?, "main2()");
+        assert_eq!(store.get("other/index.html").await?, "other");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn deploying_referenced_files() -> Result {
+        let dist = tempfile::tempdir()?;
+        let html = r#"<html><link rel="stylesheet" href="assets/x.css"></html>"#;
+        ide_ci::fs::write(dist.path().join("index.html"), html)?;
+        ide_ci::fs::write(dist.path().join("assets/x.css"), "body {}")?;
+        let manifest = Manifest::generate(dist.path())?;
+
+        let bucket = tempfile::tempdir()?;
+        let store = LocalDirectory { root: bucket.path().into() };
+        deploy(dist.path(), &manifest, &store, "gui/").await?;
+        let index = store.get("gui/index.html").await?;
+        let reference = regex::Regex::new(r#"href="([^"]+)""#)?
+            .captures(std::str::from_utf8(&index)?)
+            .context("No reference in the deployed index.html.")?[1]
+            .to_owned();
+        assert_eq!(store.get(&format!("gui/{reference}")).await?, "body {}");
+        Ok(())
+    }
+}
@@ -6,6 +6,8 @@ use ide_ci::actions::artifacts::upload_compressed_directory;
 use ide_ci::actions::artifacts::upload_single_file;
 use ide_ci::actions::workflow::is_in_env;
 
+pub mod smoke_test;
+
 #[derive(Clone, Debug)]
 pub struct Artifact {
     /// Directory with unpacked client distribution.
@@ -19,7 +21,8 @@ pub struct Artifact {
 }
 
 impl Artifact {
-    fn new(
+    /// Describe the artifacts that the IDE build puts in the given directory.
+    pub fn new(
         target_os: OS,
         target_arch: Arch,
         version: &Version,
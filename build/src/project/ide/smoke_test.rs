@@ -0,0 +1,353 @@
+//! Checking that the built IDE package boots.
+//!
+//! The unpacked Linux package is started (under a virtual X display if needed) with an empty
+//! projects root. The IDE spawns its bundled Project Manager, which is considered ready once its
+//! JSON-RPC endpoint accepts a WebSocket connection. The test passes if the Project Manager then
+//! answers the `project/list` request. Everything is shut down afterwards, whatever the verdict.
+
+use crate::prelude::*;
+
+use crate::programs::project_manager::PROJECTS_ROOT;
+use crate::project::ide::Artifact;
+use crate::test_service::Environment;
+use crate::test_service::Readiness;
+use crate::test_service::RunningService;
+use crate::test_service::TestService;
+
+use futures_util::SinkExt;
+use ide_ci::programs::Xvfb;
+use std::time::Duration;
+use std::time::Instant;
+use tempfile::tempdir;
+use tokio::net::TcpStream;
+use tokio::process::Child;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::MaybeTlsStream;
+use tokio_tungstenite::WebSocketStream;
+
+
+
+/// Time limit for the IDE to start and answer the request.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long the IDE has to quit on its own before being killed.
+pub const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// The JSON-RPC endpoint of the Project Manager spawned by the IDE.
+pub const DEFAULT_PROJECT_MANAGER_ENDPOINT: &str = "ws://127.0.0.1:30535";
+
+/// Interval between the attempts to connect to the Project Manager.
+const CONNECTION_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The first X display number tried for the virtual display.
+const FIRST_DISPLAY_NUMBER: u32 = 99;
+
+/// How many display numbers are tried before giving up.
+const DISPLAY_NUMBERS_TRIED: u32 = 100;
+
+/// ID of the `project/list` request. It is the only request sent on the connection.
+const REQUEST_ID: u64 = 0;
+
+/// Where the IDE window is displayed.
+#[derive(clap::ArgEnum, Clone, Copy, Debug, Default, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum DisplayChoice {
+    /// Use the current display if `DISPLAY` is set, otherwise start Xvfb.
+    #[default]
+    Auto,
+    /// Start Xvfb, even if there is a display available.
+    Xvfb,
+    /// Use the display given in the `DISPLAY` environment variable.
+    Current,
+}
+
+impl DisplayChoice {
+    pub fn needs_virtual_display(self) -> bool {
+        match self {
+            DisplayChoice::Auto => std::env::var_os("DISPLAY").is_none(),
+            DisplayChoice::Xvfb => true,
+            DisplayChoice::Current => false,
+        }
+    }
+}
+
+/// An Xvfb server, providing the display for the IDE.
+#[derive(Clone, Copy, Debug)]
+pub struct VirtualDisplay {
+    pub number: u32,
+}
+
+impl VirtualDisplay {
+    /// Use the first display number that is not taken by another X server.
+    pub fn new() -> Result<Self> {
+        let last = FIRST_DISPLAY_NUMBER + DISPLAY_NUMBERS_TRIED;
+        let number = (FIRST_DISPLAY_NUMBER..last)
+            .find(|number| !Self { number: *number }.lock_file().exists())
+            .with_context(|| {
+                format!("No free X display number in {FIRST_DISPLAY_NUMBER}..{last}.")
+            })?;
+        Ok(Self { number })
+    }
+
+    /// The value of the `DISPLAY` variable.
+    pub fn name(&self) -> String {
+        format!(":{}", self.number)
+    }
+
+    /// File created by the X server that owns the display number.
+    pub fn lock_file(&self) -> PathBuf {
+        PathBuf::from(format!("/tmp/.X{}-lock", self.number))
+    }
+
+    /// The socket that the X server accepts the connections on.
+    pub fn socket(&self) -> PathBuf {
+        PathBuf::from(format!("/tmp/.X11-unix/X{}", self.number))
+    }
+}
+
+#[async_trait]
+impl TestService for VirtualDisplay {
+    fn name(&self) -> String {
+        format!("Xvfb {}", self.name())
+    }
+
+    fn command(&self) -> Result<Command> {
+        let mut command = Xvfb.cmd()?;
+        command.arg(self.name()).args(["-screen", "0", "1920x1080x24", "-nolisten", "tcp"]);
+        Ok(command)
+    }
+
+    fn readiness(&self) -> Readiness {
+        Readiness::PathExists { path: self.socket() }
+    }
+
+    fn startup_timeout(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    fn env(&self) -> Environment {
+        vec![("DISPLAY".into(), self.name())]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub display:                  DisplayChoice,
+    /// Time limit for the IDE to start and for the Project Manager to answer. Does not include
+    /// the shutdown.
+    pub timeout:                  Duration,
+    pub project_manager_endpoint: Url,
+    /// Additional arguments for the IDE.
+    pub ide_options:              Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            display:                  default(),
+            timeout:                  DEFAULT_TIMEOUT,
+            project_manager_endpoint: Url::parse(DEFAULT_PROJECT_MANAGER_ENDPOINT).unwrap(),
+            ide_options:              default(),
+        }
+    }
+}
+
+/// Result of the passed smoke test.
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    /// Time from spawning the IDE until the Project Manager accepted the connection.
+    pub startup_time: Duration,
+    /// Number of the projects listed by the Project Manager.
+    pub projects:     usize,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Project Manager was ready after {:?} and listed {} projects.",
+            self.startup_time, self.projects
+        )
+    }
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// The JSON-RPC response, either with the result or with the error.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Response {
+    /// Missing in the notifications.
+    pub id:     Option<u64>,
+    pub result: Option<serde_json::Value>,
+    pub error:  Option<ResponseError>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ResponseError {
+    pub code:    i64,
+    pub message: String,
+}
+
+impl Response {
+    /// The projects from the `project/list` result.
+    pub fn projects(self) -> Result<Vec<serde_json::Value>> {
+        if let Some(ResponseError { code, message }) = self.error {
+            bail!("Project Manager returned error {code}: {message}");
+        }
+        let result = self.result.context("Project Manager returned neither result nor error.")?;
+        let projects = result.get("projects").and_then(|projects| projects.as_array());
+        let projects = projects.context("Missing `projects` in the `project/list` result.")?;
+        Ok(projects.clone())
+    }
+}
+
+/// Connect to the Project Manager, retrying until it accepts the connection.
+pub async fn wait_for_project_manager(endpoint: &Url) -> Result<Socket> {
+    loop {
+        match tokio_tungstenite::connect_async(endpoint.as_str()).await {
+            Ok((socket, _)) => return Ok(socket),
+            Err(e) => trace!("Project Manager at {endpoint} not ready yet: {e}"),
+        }
+        tokio::time::sleep(CONNECTION_RETRY_INTERVAL).await;
+    }
+}
+
+/// Send the `project/list` request and wait for its response.
+pub async fn list_projects(socket: &mut Socket) -> Result<Vec<serde_json::Value>> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": REQUEST_ID,
+        "method": "project/list",
+        "params": {}
+    });
+    socket.send(Message::Text(request.to_string())).await?;
+    while let Some(message) = socket.next().await {
+        if let Message::Text(text) = message? {
+            let response: Response = serde_json::from_str(&text)
+                .with_context(|| format!("Invalid JSON-RPC message: {text}"))?;
+            if response.id == Some(REQUEST_ID) {
+                return response.projects();
+            }
+            trace!("Ignoring message: {text}");
+        }
+    }
+    bail!("Project Manager closed the connection without answering.")
+}
+
+/// Wait for the Project Manager spawned by the IDE started at `start` and list the projects.
+async fn check_project_manager(endpoint: &Url, start: Instant) -> Result<Outcome> {
+    let mut socket = wait_for_project_manager(endpoint).await?;
+    let startup_time = start.elapsed();
+    info!("Project Manager accepted the connection after {startup_time:?}.");
+    let projects = list_projects(&mut socket).await?;
+    Ok(Outcome { startup_time, projects: projects.len() })
+}
+
+/// Make the command start a new process group, so the IDE can be stopped together with the
+/// processes it spawned, like the Project Manager.
+#[cfg_attr(not(unix), allow(unused_variables))]
+#[allow(unsafe_code)]
+fn spawn_in_new_process_group(command: &mut Command) {
+    #[cfg(unix)]
+    // Safety: `setsid` is async-signal-safe.
+    unsafe {
+        command.inner.pre_exec(|| {
+            nix::unistd::setsid()?;
+            Ok(())
+        });
+    }
+}
+
+/// Ask the IDE's process group to quit and kill it if it does not within the
+/// [`SHUTDOWN_GRACE_PERIOD`].
+///
+/// The group is the one created by [`spawn_in_new_process_group`], identified by the IDE's PID.
+/// The processes spawned by the IDE are killed even if the IDE itself has already exited.
+#[cfg_attr(not(unix), allow(unused_variables))]
+async fn shutdown(ide: &mut Child, group: Option<u32>) -> Result {
+    #[cfg(unix)]
+    if let Some(group) = group {
+        use nix::sys::signal::killpg;
+        use nix::sys::signal::Signal;
+        use nix::unistd::Pid;
+        let group = Pid::from_raw(group as i32);
+        // Fails if all the processes in the group are already gone.
+        if killpg(group, Signal::SIGTERM).is_ok() {
+            let quit = tokio::time::timeout(SHUTDOWN_GRACE_PERIOD, ide.wait()).await;
+            if quit.is_err() {
+                warn!("The IDE did not quit within {SHUTDOWN_GRACE_PERIOD:?}, killing it.");
+            }
+            let _ = killpg(group, Signal::SIGKILL);
+        }
+    }
+    if ide.try_wait()?.is_none() {
+        ide.kill().await?;
+    }
+    Ok(())
+}
+
+/// Start the IDE and check that its Project Manager answers. Fails if it does not pass.
+pub async fn run(artifact: &Artifact, options: &Options) -> Result<Outcome> {
+    ensure!(TARGET_OS == OS::Linux, "The smoke test supports only the Linux package.");
+    let display = if options.display.needs_virtual_display() {
+        Some(RunningService::start(VirtualDisplay::new()?).await?)
+    } else {
+        None
+    };
+
+    let projects_root = tempdir()?;
+    let mut command = artifact.start_unpacked(&options.ide_options);
+    command.set_env(PROJECTS_ROOT, projects_root.path())?.kill_on_drop(true);
+    if let Some(display) = &display {
+        display.apply_env(&mut command, "ide").await?;
+    }
+    spawn_in_new_process_group(&mut command);
+    let start = Instant::now();
+    let mut ide = command.spawn_intercepting()?;
+    let group = ide.id();
+
+    let check = check_project_manager(&options.project_manager_endpoint, start);
+    let timeout = options.timeout;
+    let verdict = tokio::select! {
+        result = tokio::time::timeout(timeout, check) => result
+            .with_context(|| format!("The IDE did not pass the smoke test within {timeout:?}."))
+            .flatten(),
+        status = ide.wait() => Err(anyhow!("The IDE exited before passing the smoke test: {status:?}")),
+    };
+
+    let shutdown_result = shutdown(&mut ide, group).await;
+    if let Some(display) = display {
+        if let Err(e) = display.stop().await {
+            warn!("Failed to stop the virtual display: {e:?}");
+        }
+    }
+    let outcome = verdict?;
+    shutdown_result.context("Failed to shut down the IDE.")?;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_project_list_response() -> Result {
+        let response: Response = serde_json::from_str(
+            r#"{"jsonrpc": "2.0", "id": 0, "result": {"projects": [{"name": "Unnamed"}]}}"#,
+        )?;
+        assert_eq!(response.id, Some(REQUEST_ID));
+        assert_eq!(response.projects()?.len(), 1);
+
+        let response: Response = serde_json::from_str(
+            r#"{"jsonrpc": "2.0", "id": 0, "error": {"code": 4001, "message": "Cannot list."}}"#,
+        )?;
+        let error = response.projects().unwrap_err();
+        assert!(error.to_string().contains("Cannot list."), "{error:?}");
+
+        let notification: Response = serde_json::from_str(
+            r#"{"jsonrpc": "2.0", "method": "heartbeat/ping", "params": {}}"#,
+        )?;
+        assert_eq!(notification.id, None);
+        Ok(())
+    }
+}
@@ -4,23 +4,30 @@ use crate::prelude::*;
 
 use crate::paths::generated::RepoRootDistWasm;
 use crate::project::wasm::js_patcher::patch_js_glue_in_place;
+use crate::project::watch::NativeWatcher;
+use crate::project::watch::WatchOptions;
 use crate::project::Context;
 use crate::project::IsArtifact;
 use crate::project::IsTarget;
 use crate::project::IsWatchable;
+use crate::rust::lockfile::Lockfile;
 use crate::source::BuildTargetJob;
 use crate::source::WatchTargetJob;
 use crate::source::WithDestination;
 use derivative::Derivative;
 use ide_ci::cache;
+use ide_ci::cache::goodie::wasm_bindgen::WasmBindgenCli;
 use ide_ci::env::Variable;
 use ide_ci::fs::compressed_size;
 use ide_ci::fs::copy_file_if_different;
 use ide_ci::programs::cargo;
+use ide_ci::programs::wasm_bindgen;
 use ide_ci::programs::wasm_opt;
 use ide_ci::programs::wasm_opt::WasmOpt;
 use ide_ci::programs::wasm_pack;
 use ide_ci::programs::Cargo;
+use ide_ci::programs::Rustup;
+use ide_ci::programs::WasmBindgen;
 use ide_ci::programs::WasmPack;
 use semver::VersionReq;
 use std::time::Duration;
@@ -29,6 +36,7 @@ use tokio::process::Child;
 
 pub mod env;
 pub mod js_patcher;
+pub mod size;
 pub mod test;
 
 pub const BINARYEN_VERSION_TO_INSTALL: u32 = 108;
@@ -37,6 +45,9 @@ pub const DEFAULT_INTEGRATION_TESTS_WASM_TIMEOUT: Duration = Duration::from_secs
 
 pub const INTEGRATION_TESTS_CRATE_NAME: &str = "enso-integration-test";
 
+/// Path to the integration tests crate, relative to the repository root.
+pub const INTEGRATION_TESTS_CRATE_PATH: &str = "integration-test";
+
 pub const OUTPUT_NAME: &str = "ide";
 
 /// Name of the artifact that will be uploaded as part of CI run.
@@ -54,6 +65,20 @@ pub enum ProfilingLevel {
     Debug,
 }
 
+/// How the crate is compiled to WASM and how its JS bindings are generated.
+#[derive(
+    clap::ArgEnum, Clone, Copy, Debug, Default, PartialEq, Eq, strum::Display, strum::AsRefStr,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Builder {
+    /// Run `wasm-pack build`, which must be already installed.
+    #[default]
+    WasmPack,
+    /// Run `cargo build` and then `wasm-bindgen` in the version matching the lockfile, which is
+    /// downloaded if missing.
+    Cargo,
+}
+
 #[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, strum::Display, strum::AsRefStr)]
 #[strum(serialize_all = "kebab-case")]
 pub enum Profile {
@@ -94,6 +119,22 @@ impl Profile {
         }
     }
 
+    /// Cargo arguments selecting the profile, matching what wasm-pack does.
+    pub fn cargo_args(self) -> Vec<&'static str> {
+        match self {
+            Profile::Dev => vec![],
+            Profile::Profile | Profile::Release => vec!["--release"],
+        }
+    }
+
+    /// Name of the directory in the Cargo's target directory, where the outputs are placed.
+    pub fn cargo_output_directory(self) -> &'static str {
+        match self {
+            Profile::Dev => "debug",
+            Profile::Profile | Profile::Release => "release",
+        }
+    }
+
     pub fn optimization_level(self) -> wasm_opt::OptimizationLevel {
         match self {
             Profile::Dev => wasm_opt::OptimizationLevel::O0,
@@ -101,46 +142,184 @@ impl Profile {
             Profile::Release => wasm_opt::OptimizationLevel::O3,
         }
     }
+
+    /// The wasm-opt options to use if no preset was given.
+    pub fn default_wasm_opt_options(self) -> wasm_opt::Options {
+        wasm_opt::Options::with_level(self.optimization_level())
+    }
+}
+
+/// Named wasm-opt option presets, as defined in the build configuration.
+pub type WasmOptPresets = BTreeMap<String, wasm_opt::Options>;
+
+/// Select the wasm-opt options.
+///
+/// The explicitly requested preset must exist. Otherwise, the preset named after the profile
+/// (e.g. `release`) is used if defined, falling back to [`Profile::default_wasm_opt_options`].
+/// The raw `extra_args` are appended in any case. If they contain an optimization level (like
+/// `-O3`), it replaces the selected one.
+pub fn resolve_wasm_opt_options(
+    presets: &WasmOptPresets,
+    preset: Option<&str>,
+    profile: Profile,
+    extra_args: impl IntoIterator<Item = String>,
+) -> Result<wasm_opt::Options> {
+    let mut ret = match preset {
+        Some(name) => presets.get(name).cloned().with_context(|| {
+            format!(
+                "Unknown wasm-opt preset `{name}`. Available presets: {}.",
+                presets.keys().join(", ")
+            )
+        })?,
+        None => presets
+            .get(profile.as_ref())
+            .cloned()
+            .unwrap_or_else(|| profile.default_wasm_opt_options()),
+    };
+    ret.extra_args.extend(extra_args);
+    if ret.extra_args.iter().any(|arg| wasm_opt::OptimizationLevel::from_arg(arg).is_some()) {
+        ret.optimization_level = None;
+    }
+    Ok(ret)
+}
+
+/// Name of the library target of the crate with the given manifest, i.e. the name of the WASM
+/// file that Cargo compiles it to.
+pub fn library_name(manifest: impl AsRef<Path>) -> Result<String> {
+    let text = ide_ci::fs::read_to_string(&manifest)?;
+    library_name_from_manifest(&text).with_context(|| {
+        format!("Failed to get the library name from {}.", manifest.as_ref().display())
+    })
+}
+
+fn library_name_from_manifest(text: &str) -> Result<String> {
+    let manifest = toml::Value::from_str(text)?;
+    let explicit_name = manifest.get("lib").and_then(|lib| lib.get("name"));
+    let name = explicit_name
+        .or_else(|| manifest.get("package").and_then(|package| package.get("name")))
+        .and_then(toml::Value::as_str)
+        .context("The manifest defines neither the package nor the library name.")?;
+    Ok(name.replace('-', "_"))
+}
+
+/// Value of the Cargo option, given either as `--name value` or `--name=value`. If repeated, the
+/// last one wins, like in Cargo.
+fn cargo_option_value<'a>(cargo_options: &'a [String], name: &str) -> Option<&'a str> {
+    let mut ret = None;
+    let mut options = cargo_options.iter();
+    while let Some(option) = options.next() {
+        if option == name {
+            ret = options.next().map(String::as_str);
+        } else if let Some(value) = option.strip_prefix(name).and_then(|v| v.strip_prefix('=')) {
+            ret = Some(value);
+        }
+    }
+    ret
+}
+
+/// Directory where Cargo places the outputs built for the given target.
+///
+/// The `--target-dir` and `--profile` among the `cargo_options` take precedence over the
+/// [`env::CARGO_TARGET_DIR`] and the given profile.
+pub fn cargo_output_directory(
+    repo_root: &Path,
+    profile: Profile,
+    cargo_options: &[String],
+    target: &str,
+) -> PathBuf {
+    let target_dir = match cargo_option_value(cargo_options, "--target-dir") {
+        Some(target_dir) => PathBuf::from(target_dir),
+        None => env::CARGO_TARGET_DIR.get().unwrap_or_else(|_| "target".into()),
+    };
+    let profile_directory = match cargo_option_value(cargo_options, "--profile") {
+        Some("dev" | "test") => "debug",
+        Some("bench") => "release",
+        Some(custom) => custom,
+        None => profile.cargo_output_directory(),
+    };
+    repo_root.join(target_dir).join(target).join(profile_directory)
+}
+
+/// What [`Wasm::finalize_wasm`] has done, stored next to the WASM artifacts.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct WasmOptReport {
+    /// `None` if wasm-opt was not run.
+    pub options:       Option<wasm_opt::Options>,
+    /// The full wasm-opt arguments, i.e. the passes in the order they ran.
+    pub args:          Vec<String>,
+    pub size_before:   u64,
+    pub size_after:    u64,
+    pub duration_secs: f64,
 }
 
 #[derive(Clone, Derivative)]
 #[derivative(Debug)]
 pub struct BuildInput {
     /// Path to the crate to be compiled to WAM. Relative to the repository root.
-    pub crate_path:          PathBuf,
-    pub wasm_opt_options:    Vec<String>,
-    pub skip_wasm_opt:       bool,
-    pub extra_cargo_options: Vec<String>,
-    pub profile:             Profile,
-    pub profiling_level:     Option<ProfilingLevel>,
-    pub wasm_size_limit:     Option<byte_unit::Byte>,
+    pub crate_path:           PathBuf,
+    pub builder:              Builder,
+    pub wasm_opt:             wasm_opt::Options,
+    pub skip_wasm_opt:        bool,
+    pub extra_cargo_options:  Vec<String>,
+    pub profile:              Profile,
+    pub profiling_level:      Option<ProfilingLevel>,
+    pub wasm_size_limit:      Option<byte_unit::Byte>,
+    /// Size report of the previous build to compare with, see [`size::Report`].
+    pub wasm_size_baseline:   Option<PathBuf>,
+    /// How much the compressed WASM may grow since the baseline.
+    pub wasm_size_max_growth: byte_unit::Byte,
 }
 
 impl BuildInput {
-    pub async fn perhaps_check_size(&self, wasm_path: impl AsRef<Path>) -> Result {
-        let compressed_size = compressed_size(&wasm_path).await?.get_appropriate_unit(true);
-        info!("Compressed size of {} is {}.", wasm_path.as_ref().display(), compressed_size);
-        if let Some(wasm_size_limit) = self.wasm_size_limit {
-            let wasm_size_limit = wasm_size_limit.get_appropriate_unit(true);
-            if !self.profile.should_check_size() {
-                warn!("Skipping size check because profile is '{}'.", self.profile,);
-            } else if self.profiling_level.unwrap_or_default() != ProfilingLevel::Objective {
-                // TODO? additional leeway as sanity check
-                warn!(
-                    "Skipping size check because profiling level is {:?} rather than {}.",
-                    self.profiling_level,
-                    ProfilingLevel::Objective
-                );
-            } else {
+    /// Set up the environment of the command compiling the crate.
+    pub fn set_build_env(&self, command: &mut impl IsCommandWrapper) -> Result {
+        command
+            .env_remove(ide_ci::programs::rustup::env::Toolchain::NAME)
+            .set_env(env::ENSO_ENABLE_PROC_MACRO_SPAN, &true)?
+            .set_env_opt(env::ENSO_MAX_PROFILING_LEVEL, self.profiling_level.as_ref())?;
+        Ok(())
+    }
+
+    /// Check the size of the built WASM against the limit and the baseline (if set).
+    ///
+    /// When checking, the size report is stored next to the WASM, see [`size::Report`].
+    pub async fn perhaps_check_size(&self, dist: &RepoRootDistWasm) -> Result {
+        let compressed_size = compressed_size(&dist.wasm_main).await?.get_appropriate_unit(true);
+        info!("Compressed size of {} is {}.", dist.wasm_main.display(), compressed_size);
+        if self.wasm_size_limit.is_none() && self.wasm_size_baseline.is_none() {
+            return Ok(());
+        }
+        if !self.profile.should_check_size() {
+            warn!("Skipping size check because profile is '{}'.", self.profile,);
+        } else if self.profiling_level.unwrap_or_default() != ProfilingLevel::Objective {
+            // TODO? additional leeway as sanity check
+            warn!(
+                "Skipping size check because profiling level is {:?} rather than {}.",
+                self.profiling_level,
+                ProfilingLevel::Objective
+            );
+        } else {
+            let report =
+                size::Report::analyze(&dist.wasm_main, size::DEFAULT_TOP_FUNCTIONS).await?;
+            report.write(&dist.wasm_size_report)?;
+            debug!("WASM size breakdown:\n{}", report.summary(10));
+            if let Some(wasm_size_limit) = self.wasm_size_limit {
+                let wasm_size_limit = wasm_size_limit.get_appropriate_unit(true);
                 ensure!(
                     compressed_size < wasm_size_limit,
-                    "Compressed WASM size ~{} ({} bytes) exceeds the limit of {} ({} bytes).",
+                    "Compressed WASM size ~{} ({} bytes) exceeds the limit of {} ({} bytes).\n{}",
                     compressed_size,
                     compressed_size.get_byte(),
                     wasm_size_limit,
                     wasm_size_limit.get_byte(),
+                    report.summary(10),
                 )
             }
+            if let Some(baseline) = &self.wasm_size_baseline {
+                let baseline = size::Report::read(baseline)?;
+                let max_growth = self.wasm_size_max_growth.get_bytes() as u64;
+                report.check_against(&baseline, max_growth)?;
+            }
         }
         Ok(())
     }
@@ -162,6 +341,11 @@ impl IsTarget for Wasm {
         ready(Ok(Artifact::new(path.as_ref()))).boxed()
     }
 
+    fn source_roots(&self, context: &Context, input: &Self::BuildInput) -> Vec<PathBuf> {
+        // The crate and the libraries it depends on.
+        vec![context.repo_root.join(&input.crate_path), context.repo_root.lib.rust.to_path_buf()]
+    }
+
     fn build_internal(
         &self,
         context: Context,
@@ -175,55 +359,29 @@ impl IsTarget for Wasm {
             cargo_opts = ?inner.extra_cargo_options
         );
         async move {
-            // Old wasm-pack does not pass trailing `build` command arguments to the Cargo.
-            // We want to be able to pass --profile this way.
-            WasmPack.require_present_that(VersionReq::parse(">=0.10.1")?).await?;
-
-            let BuildInput {
-                crate_path,
-                wasm_opt_options,
-                skip_wasm_opt,
-                extra_cargo_options,
-                profile,
-                profiling_level,
-                wasm_size_limit: _wasm_size_limit,
-            } = &inner;
+            let BuildInput { builder, wasm_opt, skip_wasm_opt, profile, .. } = &inner;
 
             cache::goodie::binaryen::Binaryen { version: BINARYEN_VERSION_TO_INSTALL }
                 .install_if_missing(&cache)
                 .await?;
 
-            info!("Building wasm.");
+            info!("Building wasm with {builder}.");
             let temp_dir = tempdir()?;
             let temp_dist = RepoRootDistWasm::new_root(temp_dir.path());
-            let mut command = WasmPack.cmd()?;
-            command
-                .current_dir(&repo_root)
-                .kill_on_drop(true)
-                .env_remove(ide_ci::programs::rustup::env::Toolchain::NAME)
-                .set_env(env::ENSO_ENABLE_PROC_MACRO_SPAN, &true)?
-                .build()
-                .arg(&wasm_pack::Profile::from(*profile))
-                .target(wasm_pack::Target::Web)
-                .output_directory(&temp_dist)
-                .output_name(&OUTPUT_NAME)
-                .arg(&crate_path)
-                .arg("--")
-                .apply(&cargo::Color::Always)
-                .args(extra_cargo_options);
-
-            if let Some(profiling_level) = profiling_level {
-                command.set_env(env::ENSO_MAX_PROFILING_LEVEL, &profiling_level)?;
+            match builder {
+                Builder::WasmPack =>
+                    Self::build_with_wasm_pack(&repo_root, &inner, &temp_dist).await?,
+                Builder::Cargo =>
+                    Self::build_with_cargo(&cache, &repo_root, &inner, &temp_dist).await?,
             }
-            command.run_ok().await?;
 
-            Self::finalize_wasm(wasm_opt_options, *skip_wasm_opt, *profile, &temp_dist).await?;
+            Self::finalize_wasm(wasm_opt, *skip_wasm_opt, *profile, &temp_dist).await?;
             patch_js_glue_in_place(&temp_dist.wasm_glue)?;
 
             ide_ci::fs::create_dir_if_missing(&destination)?;
             let ret = RepoRootDistWasm::new_root(&destination);
             ide_ci::fs::copy(&temp_dist, &ret)?;
-            inner.perhaps_check_size(&ret.wasm_main).await?;
+            inner.perhaps_check_size(&ret).await?;
             Ok(Artifact(ret))
         }
         .instrument(span)
@@ -234,11 +392,11 @@ impl IsTarget for Wasm {
 #[derive(Clone, Derivative)]
 #[derivative(Debug)]
 pub struct WatchInput {
-    pub cargo_watch_options: Vec<String>,
+    pub options: WatchOptions,
 }
 
 impl IsWatchable for Wasm {
-    type Watcher = crate::project::Watcher<Self, Child>;
+    type Watcher = NativeWatcher<Self>;
     type WatchInput = WatchInput;
 
     fn watch(
@@ -246,102 +404,14 @@ impl IsWatchable for Wasm {
         context: Context,
         job: WatchTargetJob<Self>,
     ) -> BoxFuture<'static, Result<Self::Watcher>> {
-        let span = debug_span!("Watching WASM.", ?job).entered();
-
         // The esbuild watcher must succeed in its first build, or it will prematurely exit.
         // See the issue: https://github.com/evanw/esbuild/issues/1063
         //
-        // Because of this, we run first build of wasm manually, rather through cargo-watch.
-        // After it is completed, the cargo-watch gets spawned and this method yields the watcher.
-        // This forces esbuild watcher (whose setup requires the watcher artifacts) to wait until
-        // all wasm build outputs are in place, so the build won't crash.
-        //
-        // In general, much neater workaround should be possible, if we stop relying on cargo-watch
-        // and do the WASM watch directly in the build script.
-        let first_build_job = self
-            .build(context.clone(), job.build.clone())
-            .instrument(debug_span!("Initial single build of WASM before setting up cargo-watch."));
-
-        async move {
-            let first_build_output = first_build_job.await?;
-
-            let WatchTargetJob {
-                watch_input: WatchInput { cargo_watch_options: cargo_watch_flags },
-                build: WithDestination { inner, destination },
-            } = job;
-            let BuildInput {
-                crate_path,
-                wasm_opt_options,
-                skip_wasm_opt,
-                extra_cargo_options,
-                profile,
-                profiling_level,
-                wasm_size_limit,
-            } = inner;
-
-
-            let current_exe = std::env::current_exe()?;
-            // cargo-watch apparently cannot handle verbatim path prefix. We remove it and hope for
-            // the best.
-            let current_exe = current_exe.without_verbatim_prefix();
-
-
-            let mut watch_cmd = Cargo.cmd()?;
-
-            watch_cmd
-                .kill_on_drop(true)
-                .current_dir(&context.repo_root)
-                .arg("watch")
-                .args(["--ignore", "README.md"])
-                .args(cargo_watch_flags)
-                .arg("--");
-
-            // === Build Script top-level options ===
-            watch_cmd
-                // TODO [mwu]
-                // This is not nice, as this module should not be aware of the CLI
-                // parsing/generation. Rather than using `cargo watch` this should
-                // be implemented directly in Rust.
-                .arg(current_exe)
-                .arg("--skip-version-check") // We already checked in the parent process.
-                .args(["--cache-path", context.cache.path().as_str()])
-                .args(["--upload-artifacts", context.upload_artifacts.to_string().as_str()])
-                .args(["--repo-path", context.repo_root.as_str()]);
-
-            // === Build Script command and its options ===
-            watch_cmd
-                .arg("wasm")
-                .arg("build")
-                .args(["--crate-path", crate_path.as_str()])
-                .args(["--wasm-output-path", destination.as_str()])
-                .args(["--wasm-profile", profile.as_ref()]);
-            if let Some(profiling_level) = profiling_level {
-                watch_cmd.args(["--profiling-level", profiling_level.to_string().as_str()]);
-            }
-            for wasm_opt_option in wasm_opt_options {
-                watch_cmd.args(["--wasm-opt-option", &wasm_opt_option]);
-            }
-            if skip_wasm_opt {
-                watch_cmd.args(["--skip-wasm-opt"]);
-            }
-            if let Some(wasm_size_limit) = wasm_size_limit {
-                watch_cmd.args(["--wasm-size-limit", wasm_size_limit.to_string().as_str()]);
-            }
-
-            // === cargo-watch options ===
-            watch_cmd.arg("--").args(extra_cargo_options);
-
-            let watch_process = watch_cmd.spawn_intercepting()?;
-            let artifact = Artifact(RepoRootDistWasm::new_root(&destination));
-            ensure!(
-                artifact == first_build_output,
-                "First build output does not match general watch build output. First build output: \
-                {first_build_output:?}, general watch build output: {artifact:?}",
-            );
-            Ok(Self::Watcher { artifact, watch_process })
-        }
-        .instrument(span.exit())
-        .boxed()
+        // This is satisfied, as the watcher is yielded only after the first build completes.
+        let WatchTargetJob { watch_input: WatchInput { options }, build } = job;
+        crate::project::watch::watch(self.clone(), context, build, options)
+            .instrument(debug_span!("Watching WASM."))
+            .boxed()
     }
 }
 
@@ -374,6 +444,85 @@ impl AsRef<Path> for Artifact {
 impl IsArtifact for Artifact {}
 
 impl Wasm {
+    /// Compile the crate and generate its bindings into the given directory with wasm-pack.
+    pub async fn build_with_wasm_pack(
+        repo_root: &Path,
+        input: &BuildInput,
+        temp_dist: &RepoRootDistWasm,
+    ) -> Result {
+        // Old wasm-pack does not pass trailing `build` command arguments to the Cargo.
+        // We want to be able to pass --profile this way.
+        WasmPack.require_present_that(VersionReq::parse(">=0.10.1")?).await?;
+
+        let mut command = WasmPack.cmd()?;
+        input.set_build_env(&mut command)?;
+        command
+            .current_dir(repo_root)
+            .kill_on_drop(true)
+            .build()
+            .arg(&wasm_pack::Profile::from(input.profile))
+            .target(wasm_pack::Target::Web)
+            .output_directory(temp_dist)
+            .output_name(&OUTPUT_NAME)
+            .arg(&input.crate_path)
+            .arg("--")
+            .apply(&cargo::Color::Always)
+            .args(&input.extra_cargo_options)
+            .run_ok()
+            .await
+    }
+
+    /// Compile the crate with Cargo and generate its bindings into the given directory with
+    /// wasm-bindgen.
+    ///
+    /// Unlike wasm-pack, this does not run wasm-opt by itself, so no manifest patching is needed
+    /// to disable it.
+    pub async fn build_with_cargo(
+        cache: &cache::Cache,
+        repo_root: &Path,
+        input: &BuildInput,
+        temp_dist: &RepoRootDistWasm,
+    ) -> Result {
+        let lockfile = Lockfile::read(repo_root.join("Cargo.lock"))?;
+        let version = lockfile.version_of("wasm-bindgen")?.clone();
+        WasmBindgenCli { version }.install_if_missing(cache).await?;
+        Rustup.add_target(repo_root, wasm_bindgen::WASM_TARGET).await?;
+
+        let manifest = repo_root.join(&input.crate_path).join("Cargo.toml");
+        let mut command = Cargo.cmd()?;
+        input.set_build_env(&mut command)?;
+        command
+            .current_dir(repo_root)
+            .kill_on_drop(true)
+            .apply(&cargo::Command::Build)
+            .arg("--manifest-path")
+            .arg(&manifest)
+            .args(["--target", wasm_bindgen::WASM_TARGET])
+            .args(input.profile.cargo_args())
+            .args(&input.extra_cargo_options)
+            .run_ok()
+            .await?;
+
+        let output_directory = cargo_output_directory(
+            repo_root,
+            input.profile,
+            &input.extra_cargo_options,
+            wasm_bindgen::WASM_TARGET,
+        );
+        let wasm = output_directory.join(format!("{}.wasm", library_name(&manifest)?));
+        let mut command = WasmBindgen.cmd()?;
+        command
+            .input(&wasm)
+            .target(wasm_pack::Target::Web)
+            .output_directory(temp_dist)
+            .output_name(OUTPUT_NAME)
+            .typescript();
+        if input.profile == Profile::Dev {
+            command.debug();
+        }
+        command.run_ok().await
+    }
+
     pub async fn check(&self) -> Result {
         Cargo
             .cmd()?
@@ -385,7 +534,15 @@ impl Wasm {
             .await
     }
 
-    pub async fn test(&self, repo_root: PathBuf, wasm: bool, native: bool) -> Result {
+    pub async fn test(
+        &self,
+        repo_root: PathBuf,
+        cache: cache::Cache,
+        wasm: bool,
+        native: bool,
+        crates: Vec<String>,
+        options: test::Options,
+    ) -> Result {
         async fn maybe_run<Fut: Future<Output = Result>>(
             name: &str,
             enabled: bool,
@@ -415,45 +572,40 @@ impl Wasm {
         })
         .await?;
 
-        maybe_run("wasm", wasm, || test::test_all(repo_root.clone())).await?;
+        maybe_run("wasm", wasm, || test::test_all(repo_root.clone(), cache, crates, options))
+            .await?;
         Ok(())
     }
 
+    /// Run the integration tests in Chrome, with the `integration-test` Cargo profile.
+    ///
+    /// The `options.cargo_options` are appended to the profile selection.
     pub async fn integration_test(
         &self,
         source_root: PathBuf,
+        cache: cache::Cache,
         _project_manager: Option<Child>,
-        headless: bool,
-        additional_options: Vec<String>,
-        wasm_timeout: Option<Duration>,
+        mut options: test::Options,
     ) -> Result {
         info!("Running Rust WASM test suite.");
-        use wasm_pack::TestFlags::*;
-        WasmPack
-            .cmd()?
-            .current_dir(source_root)
-            .set_env_opt(
-                env::WASM_BINDGEN_TEST_TIMEOUT,
-                wasm_timeout.map(|d| d.as_secs()).as_ref(),
-            )?
-            .arg("test")
-            .apply_opt(headless.then_some(&Headless))
-            .apply(&Chrome)
-            .arg("integration-test")
-            .arg("--profile=integration-test")
-            .args(additional_options)
-            .run_ok()
-            .await
+        // The integration tests need the DOM.
+        options.browser = test::browser::BrowserChoice::Chrome;
+        options.cargo_options.insert(0, "--profile=integration-test".into());
+        let manifest = source_root.join(INTEGRATION_TESTS_CRATE_PATH).join("Cargo.toml");
+        let crates = [(INTEGRATION_TESTS_CRATE_NAME.to_owned(), manifest)];
+        test::runner::test_crates(&source_root, &cache, crates, &options).await?;
+        Ok(())
         // PM will be automatically killed by dropping the handle.
     }
 
-    /// Process "raw" WASM (as compiled) by optionally invoking wasm-opt.
+    /// Optimize the raw WASM with wasm-opt (unless skipped), recording the effect in
+    /// [`RepoRootDistWasm::wasm_opt_report`].
     pub async fn finalize_wasm(
-        wasm_opt_options: &[String],
+        wasm_opt: &wasm_opt::Options,
         skip_wasm_opt: bool,
         profile: Profile,
         temp_dist: &RepoRootDistWasm,
-    ) -> Result {
+    ) -> Result<WasmOptReport> {
         let should_call_wasm_opt = {
             if profile == Profile::Dev {
                 debug!("Skipping wasm-opt invocation, as it is not part of profile {profile}.");
@@ -466,16 +618,12 @@ impl Wasm {
             }
         };
 
+        let size_before = ide_ci::fs::metadata(&temp_dist.wasm_main_raw)?.len();
+        let start = std::time::Instant::now();
         if should_call_wasm_opt {
-            let mut wasm_opt_command = WasmOpt.cmd()?;
-            let has_custom_opt_level = wasm_opt_options.iter().any(|opt| {
-                wasm_opt::OptimizationLevel::from_str(opt.trim_start_matches('-')).is_ok()
-            });
-            if !has_custom_opt_level {
-                wasm_opt_command.apply(&profile.optimization_level());
-            }
-            wasm_opt_command
-                .args(wasm_opt_options)
+            WasmOpt
+                .cmd()?
+                .apply(wasm_opt)
                 .arg(&temp_dist.wasm_main_raw)
                 .apply(&wasm_opt::Output(&temp_dist.wasm_main))
                 .run_ok()
@@ -483,7 +631,19 @@ impl Wasm {
         } else {
             copy_file_if_different(&temp_dist.wasm_main_raw, &temp_dist.wasm_main)?;
         }
-        Ok(())
+        let report = WasmOptReport {
+            options: should_call_wasm_opt.then(|| wasm_opt.clone()),
+            args: if should_call_wasm_opt { wasm_opt.args() } else { default() },
+            size_before,
+            size_after: ide_ci::fs::metadata(&temp_dist.wasm_main)?.len(),
+            duration_secs: start.elapsed().as_secs_f64(),
+        };
+        info!(
+            "wasm-opt {:?} changed the size from {} to {} bytes in {:.1}s.",
+            report.args, report.size_before, report.size_after, report.duration_secs
+        );
+        ide_ci::fs::write(&temp_dist.wasm_opt_report, serde_json::to_string_pretty(&report)?)?;
+        Ok(report)
     }
 }
 
@@ -502,6 +662,63 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn resolving_wasm_opt_options() -> Result {
+        let presets: WasmOptPresets = serde_yaml::from_str(
+            r#"
+release:
+  optimization-level: O4
+  converge: true
+small:
+  optimization-level: Oz
+"#,
+        )?;
+        let resolve = |preset, profile| {
+            resolve_wasm_opt_options(&presets, preset, profile, ["--extra".to_string()])
+        };
+        assert_eq!(resolve(None, Profile::Release)?.args(), vec!["-O4", "--converge", "--extra"]);
+        assert_eq!(resolve(None, Profile::Profile)?.args(), vec!["-O", "--extra"]);
+        assert_eq!(resolve(Some("small"), Profile::Release)?.args(), vec!["-Oz", "--extra"]);
+        assert!(resolve(Some("large"), Profile::Release).is_err());
+        let custom_level = resolve_wasm_opt_options(&presets, None, Profile::Profile, [
+            "-O1".to_string(),
+            "--extra".to_string(),
+        ])?;
+        assert_eq!(custom_level.args(), vec!["-O1", "--extra"]);
+        Ok(())
+    }
+
+    #[test]
+    fn cargo_output_directories() {
+        let root = Path::new("/repo");
+        let directory = |profile, options: &[&str]| {
+            let mut options = options.iter().map(ToString::to_string).collect_vec();
+            options.insert(0, "--target-dir=out".into());
+            cargo_output_directory(root, profile, &options, "wasm32-unknown-unknown")
+        };
+        let in_target = |profile| root.join("out/wasm32-unknown-unknown").join(profile);
+        assert_eq!(directory(Profile::Dev, &[]), in_target("debug"));
+        assert_eq!(directory(Profile::Release, &[]), in_target("release"));
+        assert_eq!(directory(Profile::Dev, &["--profile", "dev"]), in_target("debug"));
+        assert_eq!(directory(Profile::Dev, &["--profile=bench"]), in_target("release"));
+        let custom = directory(Profile::Dev, &["--profile", "integration-test"]);
+        assert_eq!(custom, in_target("integration-test"));
+        assert_eq!(
+            directory(Profile::Release, &["--target-dir", "/elsewhere"]),
+            Path::new("/elsewhere/wasm32-unknown-unknown/release")
+        );
+    }
+
+    #[test]
+    fn library_names() -> Result {
+        let package = "[package]\nname = \"enso-gui\"\nversion = \"0.1.0\"\n";
+        assert_eq!(library_name_from_manifest(package)?, "enso_gui");
+        let lib = format!("{package}\n[lib]\nname = \"ide\"\ncrate-type = [\"cdylib\"]\n");
+        assert_eq!(library_name_from_manifest(&lib)?, "ide");
+        assert!(library_name_from_manifest("[workspace]\n").is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn build() -> Result {
         Ok(())
@@ -33,4 +33,17 @@ ide_ci::define_env_var! {
 
     /// The timeout for `wasm-bindgen-test-runner` in seconds.
     WASM_BINDGEN_TEST_TIMEOUT, u64;
+
+    /// Path to the chromedriver that `wasm-bindgen-test-runner` should use for the browser tests.
+    CHROMEDRIVER, PathBuf;
+
+    /// If set, `wasm-bindgen-test-runner` skips the suites configured to run in a browser.
+    WASM_BINDGEN_TEST_ONLY_NODE, bool;
+
+    /// If set, `wasm-bindgen-test-runner` does not run the browser in the headless mode.
+    NO_HEADLESS, bool;
+
+    /// Overrides Cargo's `target` directory. Relative paths are resolved against the repository
+    /// root, as that is where Cargo is invoked.
+    CARGO_TARGET_DIR, PathBuf;
 }
@@ -1,62 +1,284 @@
+//! Patching of the JavaScript glue generated by wasm-bindgen.
+//!
+//! We instantiate the WASM module ourselves (see
+//! <https://github.com/rustwasm/wasm-pack/issues/790>), so the glue's `init` function is changed to
+//! only return the imports, and the `after_load` function is added for passing the instantiated
+//! module back.
+//!
+//! The glue is parsed rather than matched with regular expressions, so if a new wasm-bindgen
+//! version generates it differently, patching fails instead of silently leaving the glue intact.
+
 use crate::prelude::*;
 
-use regex::Regex;
+use std::ops::Range;
+use swc_common::sync::Lrc;
+use swc_common::FileName;
+use swc_common::SourceMap;
+use swc_common::Span;
+use swc_common::Spanned;
+use swc_ecma_ast as ast;
+use swc_ecma_parser::lexer::Lexer;
+use swc_ecma_parser::Parser;
+use swc_ecma_parser::StringInput;
+use swc_ecma_parser::Syntax;
 
-lazy_static! {
-    pub static ref REPLACEMENTS: Vec<Replacement> = [
-        Replacement::new(r"(?s)if \(typeof input === 'string'.*return wasm;", "return imports"),
-        Replacement::new(
-            r"(?s)if \(typeof input === 'undefined'.*const imports = \{\};",
-            "const imports = {};",
-        ),
-        Replacement::new(r"(?s)export default init;", "export default init"),
-    ]
-    .into_iter()
-    .try_collect_vec()
-    .unwrap();
+
+
+/// The patched glue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Patched {
+    pub code:    String,
+    /// Descriptions of the applied patches, in the order of application.
+    pub applied: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
-pub struct Replacement {
-    pattern:     Regex,
+/// Replacement of a part of the original code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Edit {
+    range:       Range<usize>,
     replacement: String,
 }
 
-impl Replacement {
-    pub fn new(pattern: impl AsRef<str>, replacement: impl Into<String>) -> Result<Self> {
-        Ok(Self { pattern: Regex::new(pattern.as_ref())?, replacement: replacement.into() })
+/// The parsed glue, able to map the AST nodes back to the code.
+#[derive(Debug)]
+struct Glue {
+    module: ast::Module,
+    /// Position of the code's beginning in the source map, used as a base for the spans.
+    start:  u32,
+}
+
+impl Glue {
+    fn parse(code: &str) -> Result<Self> {
+        let source_map: Lrc<SourceMap> = default();
+        let file = source_map.new_source_file(FileName::Anon, code.to_owned());
+        let syntax = Syntax::Es(default());
+        let lexer = Lexer::new(syntax, ast::EsVersion::Es2022, StringInput::from(&*file), None);
+        let module = Parser::new_from(lexer)
+            .parse_module()
+            .map_err(|e| anyhow!("Failed to parse the JS glue: {}", e.into_kind().msg()))?;
+        Ok(Self { module, start: file.start_pos.0 })
+    }
+
+    fn range(&self, span: Span) -> Range<usize> {
+        (span.lo.0 - self.start) as usize..(span.hi.0 - self.start) as usize
+    }
+
+    /// Name of the default-exported function, i.e. the glue's `init`.
+    fn default_export(&self) -> Result<&str> {
+        self.module
+            .body
+            .iter()
+            .find_map(|item| match item {
+                ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportDefaultExpr(export)) =>
+                    match &*export.expr {
+                        ast::Expr::Ident(ident) => Some(&*ident.sym),
+                        _ => None,
+                    },
+                _ => None,
+            })
+            .context("The JS glue has no default-exported `init` function.")
+    }
+
+    /// Statements of the top-level function.
+    fn function_body(&self, name: &str) -> Result<&[ast::Stmt]> {
+        self.module
+            .body
+            .iter()
+            .find_map(|item| match item {
+                ast::ModuleItem::Stmt(ast::Stmt::Decl(ast::Decl::Fn(decl)))
+                    if &*decl.ident.sym == name =>
+                    decl.function.body.as_ref(),
+                _ => None,
+            })
+            .map(|body| body.stmts.as_slice())
+            .with_context(|| format!("The JS glue does not define the `{name}` function."))
+    }
+
+    fn declares_top_level_variable(&self, name: &str) -> bool {
+        self.module.body.iter().any(|item| match item {
+            ast::ModuleItem::Stmt(stmt) => declares_variable(stmt, name),
+            _ => false,
+        })
+    }
+}
+
+fn declares_variable(stmt: &ast::Stmt, name: &str) -> bool {
+    match stmt {
+        ast::Stmt::Decl(ast::Decl::Var(var)) => var.decls.iter().any(|declarator| {
+            matches!(&declarator.name, ast::Pat::Ident(binding) if &*binding.id.sym == name)
+        }),
+        _ => false,
+    }
+}
+
+/// Whether the expression is `typeof input === '<type_name>'`, possibly as the first operand of
+/// an `||` chain.
+fn checks_input_type(expr: &ast::Expr, type_name: &str) -> bool {
+    match expr {
+        ast::Expr::Paren(paren) => checks_input_type(&paren.expr, type_name),
+        ast::Expr::Bin(bin) if bin.op == ast::BinaryOp::LogicalOr =>
+            checks_input_type(&bin.left, type_name),
+        ast::Expr::Bin(bin) if bin.op == ast::BinaryOp::EqEqEq => {
+            let is_typeof_input = matches!(&*bin.left, ast::Expr::Unary(unary)
+                if unary.op == ast::UnaryOp::TypeOf
+                    && matches!(&*unary.arg, ast::Expr::Ident(ident) if &*ident.sym == "input"));
+            let is_type_name = matches!(&*bin.right, ast::Expr::Lit(ast::Lit::Str(text))
+                if &*text.value == type_name);
+            is_typeof_input && is_type_name
+        }
+        _ => false,
     }
+}
 
-    pub fn replace_all<'a>(&'_ self, text: &'a str) -> Cow<'a, str> {
-        self.pattern.replace_all(text, &self.replacement)
+/// Whether the statement awaits the `load` call, e.g. `const { instance, module } = await
+/// load(await input, imports);`.
+fn calls_load(stmt: &ast::Stmt) -> bool {
+    let is_load = |expr: &ast::Expr| match expr {
+        ast::Expr::Await(await_expr) => matches!(&*await_expr.arg, ast::Expr::Call(call)
+            if matches!(&call.callee, ast::Callee::Expr(callee)
+                if matches!(&**callee, ast::Expr::Ident(ident) if &*ident.sym == "load"))),
+        _ => false,
+    };
+    match stmt {
+        ast::Stmt::Decl(ast::Decl::Var(var)) =>
+            var.decls.iter().any(|declarator| declarator.init.as_deref().map_or(false, is_load)),
+        _ => false,
     }
 }
 
-pub fn multi_replace_all<'a, 'b>(
-    text: impl Into<String>,
-    replacements: impl IntoIterator<Item = &'b Replacement>,
-) -> String {
-    let init = text.into();
-    replacements
-        .into_iter()
-        .fold(init, |text, replacement| replacement.replace_all(&text).to_string())
+fn is_input_type_check(stmt: &ast::Stmt, type_name: &str) -> bool {
+    matches!(stmt, ast::Stmt::If(if_stmt) if checks_input_type(&if_stmt.test, type_name))
 }
 
-/// Workaround fix by wdanilo, see: <https://github.com/rustwasm/wasm-pack/issues/790>
-pub fn js_workaround_patcher(code: impl Into<String>) -> Result<String> {
-    let patched_code = multi_replace_all(code, REPLACEMENTS.as_slice());
-    let epilogue = r"export function after_load(w,m) { wasm = w; init.__wbindgen_wasm_module = m;}";
-    Ok(format!("{patched_code}\n{epilogue}"))
+/// Patch the glue, failing if any of the patches cannot be applied.
+pub fn patch(code: &str) -> Result<Patched> {
+    let glue = Glue::parse(code)?;
+    let init = glue.default_export()?;
+    ensure!(
+        glue.declares_top_level_variable("wasm"),
+        "The JS glue does not declare the `wasm` variable, so `after_load` cannot set it."
+    );
+    let body = glue.function_body(init)?;
+    let imports_index = body
+        .iter()
+        .position(|stmt| declares_variable(stmt, "imports"))
+        .with_context(|| format!("The `{init}` function does not declare `imports`."))?;
+    let imports_start = glue.range(body[imports_index].span()).start;
+
+    let mut edits = Vec::new();
+    let mut applied = Vec::new();
+
+    // The module URL is not needed, as we fetch the module ourselves.
+    let before_imports = &body[..imports_index];
+    ensure!(
+        !before_imports.is_empty()
+            && before_imports.iter().all(|stmt| is_input_type_check(stmt, "undefined")),
+        "Expected only the `typeof input === 'undefined'` check before `imports` are declared in \
+        `{init}`."
+    );
+    let first_start = glue.range(before_imports[0].span()).start;
+    edits.push(Edit { range: first_start..imports_start, replacement: default() });
+    applied.push(format!("Removed the default module URL from `{init}`."));
+
+    // Instead of fetching and instantiating the module, return the imports to the caller.
+    let after_imports = &body[imports_index + 1..];
+    let loading_index = after_imports
+        .iter()
+        .position(|stmt| is_input_type_check(stmt, "string"))
+        .with_context(|| {
+        format!("Expected the `typeof input === 'string'` check in `{init}` after `imports`.")
+    })?;
+    let after_check = &after_imports[loading_index + 1..];
+    let load_index = after_check.iter().position(calls_load).with_context(|| {
+        format!("Expected `{init}` to instantiate the module with `load` after checking `input`.")
+    })?;
+    let last = after_imports.last().context("Missing the end of the module loading.")?;
+    ensure!(
+        matches!(last, ast::Stmt::Return(_)),
+        "Expected `{init}` to end with returning the module."
+    );
+    // Only the fetching and the instantiation go, the statements in between (like the
+    // `initMemory(imports)` call) still prepare the imports.
+    let check_start = glue.range(after_imports[loading_index].span()).start;
+    let after_check_start = glue.range(after_check[0].span()).start;
+    edits.push(Edit { range: check_start..after_check_start, replacement: default() });
+    let load_start = glue.range(after_check[load_index].span()).start;
+    let range = load_start..glue.range(last.span()).end;
+    edits.push(Edit { range, replacement: "return imports;".into() });
+    applied.push(format!("Made `{init}` return the imports instead of loading the module."));
+
+    let mut code = code.to_owned();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+    for Edit { range, replacement } in edits {
+        code.replace_range(range, &replacement);
+    }
+
+    // Passing the module instantiated by us back to the glue.
+    let epilogue = format!(
+        "export function after_load(w,m) {{ wasm = w; {init}.__wbindgen_wasm_module = m;}}"
+    );
+    code = format!("{code}\n{epilogue}\n");
+    applied.push("Added the `after_load` function.".into());
+    Ok(Patched { code, applied })
 }
 
 pub fn patch_js_glue(input_path: impl AsRef<Path>, output_path: impl AsRef<Path>) -> Result {
     debug!("Patching {}.", output_path.as_ref().display());
     let code = ide_ci::fs::read_to_string(&input_path)?;
-    let patched_code = js_workaround_patcher(code)?;
-    ide_ci::fs::write(output_path, patched_code)?;
+    let patched = patch(&code)
+        .with_context(|| format!("Failed to patch {}.", input_path.as_ref().display()))?;
+    for description in &patched.applied {
+        debug!("{description}");
+    }
+    ide_ci::fs::write(output_path, patched.code)?;
     Ok(())
 }
 
 pub fn patch_js_glue_in_place(path: impl AsRef<Path>) -> Result {
     patch_js_glue(&path, &path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Glue generated by the supported wasm-bindgen versions, with the expected patching result.
+    const GOLDEN: &[(&str, &str, &str)] = &[
+        (
+            "0.2.79",
+            include_str!("js_patcher/wasm-bindgen-0.2.79.js"),
+            include_str!("js_patcher/wasm-bindgen-0.2.79.patched.js"),
+        ),
+        (
+            "0.2.83",
+            include_str!("js_patcher/wasm-bindgen-0.2.83.js"),
+            include_str!("js_patcher/wasm-bindgen-0.2.83.patched.js"),
+        ),
+        (
+            "0.2.83 with shared memory",
+            include_str!("js_patcher/wasm-bindgen-0.2.83-shared-memory.js"),
+            include_str!("js_patcher/wasm-bindgen-0.2.83-shared-memory.patched.js"),
+        ),
+    ];
+
+    #[test]
+    fn golden_files() -> Result {
+        for (version, input, expected) in GOLDEN {
+            let patched = patch(input).with_context(|| format!("wasm-bindgen {version}"))?;
+            assert_eq!(patched.code, *expected, "wasm-bindgen {version}");
+            assert_eq!(patched.applied.len(), 3);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unexpected_shape_fails() {
+        let (_, input, _) = GOLDEN[0];
+        let without_loading =
+            input.replace("typeof input === 'string'", "typeof input === 'other'");
+        let error = patch(&without_loading).unwrap_err().to_string();
+        assert!(error.contains("typeof input === 'string'"), "{error}");
+        assert!(patch("let wasm;\nexport default init;").is_err());
+        assert!(patch("this is not JavaScript {").is_err());
+    }
+}
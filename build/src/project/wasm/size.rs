@@ -0,0 +1,492 @@
+//! Analysis of the WASM module size, telling what contributes to it and what has grown since the
+//! previous build.
+//!
+//! The [`Report`] is stored next to the built module (see
+//! [`crate::paths::generated::RepoRootDistWasm::wasm_size_report`]), so it is a part of the
+//! `gui_wasm` CI artifact and can serve as a baseline for the later builds.
+
+use crate::prelude::*;
+
+use ide_ci::fs::brotli_compressed_size;
+use ide_ci::fs::compressed_size;
+
+
+
+/// How many of the largest functions are listed in the report.
+pub const DEFAULT_TOP_FUNCTIONS: usize = 50;
+
+/// How many of the biggest regressions are listed when the comparison with a baseline fails.
+pub const REGRESSIONS_TO_SHOW: usize = 20;
+
+/// Size of a single function body.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FunctionSize {
+    /// Name from the `name` custom section, or `function[<index>]` if there is none.
+    pub name: String,
+    pub size: u64,
+}
+
+/// Size breakdown of the WASM module. All sizes are in bytes.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Report {
+    pub total:     u64,
+    pub gzip:      u64,
+    pub brotli:    u64,
+    /// Sizes of the section contents, keyed by [`section_name`]. Custom sections are keyed as
+    /// `custom:<name>`, e.g. `custom:name` for the debug names.
+    pub sections:  BTreeMap<String, u64>,
+    /// The largest function bodies, biggest first.
+    pub functions: Vec<FunctionSize>,
+}
+
+impl Report {
+    /// Analyze the module, listing `top_functions` largest functions.
+    ///
+    /// If the module cannot be parsed, only its total and compressed sizes are reported.
+    pub async fn analyze(wasm_path: impl AsRef<Path>, top_functions: usize) -> Result<Self> {
+        let wasm_path = wasm_path.as_ref();
+        let data = ide_ci::fs::read(wasm_path)?;
+        let mut report = Self::from_module(&data, top_functions).unwrap_or_else(|e| {
+            warn!("Failed to analyze {}, reporting only its size: {e:?}", wasm_path.display());
+            Self { total: data.len() as u64, ..default() }
+        });
+        report.gzip = compressed_size(wasm_path).await?.get_bytes() as u64;
+        report.brotli = brotli_compressed_size(wasm_path).await?.get_bytes() as u64;
+        Ok(report)
+    }
+
+    /// Analyze the module contents. The compressed sizes are not computed.
+    pub fn from_module(data: &[u8], top_functions: usize) -> Result<Self> {
+        let module = Module::parse(data)?;
+        let mut sections = BTreeMap::<String, u64>::new();
+        for section in &module.sections {
+            let key = match section.id {
+                CUSTOM_SECTION_ID => format!("custom:{}", section.custom_name()?),
+                id => section_name(id).to_owned(),
+            };
+            *sections.entry(key).or_default() += section.content.len() as u64;
+        }
+        let names = module.function_names()?;
+        let first_defined_index = module.imported_function_count()?;
+        let mut functions = module
+            .function_body_sizes()?
+            .into_iter()
+            .enumerate()
+            .map(|(i, size)| {
+                let index = first_defined_index + i as u32;
+                let name =
+                    names.get(&index).cloned().unwrap_or_else(|| format!("function[{index}]"));
+                FunctionSize { name, size }
+            })
+            .collect_vec();
+        functions.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        functions.truncate(top_functions);
+        Ok(Self { total: data.len() as u64, gzip: 0, brotli: 0, sections, functions })
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let text = ide_ci::fs::read_to_string(&path)?;
+        serde_json::from_str(&text).with_context(|| {
+            format!("Failed to parse the size report {}.", path.as_ref().display())
+        })
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result {
+        ide_ci::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Human-readable summary: the sizes, the sections and the few largest functions.
+    pub fn summary(&self, functions: usize) -> String {
+        let mut ret = format!(
+            "Total: {} bytes, gzip: {} bytes, brotli: {} bytes.\nSections:\n",
+            self.total, self.gzip, self.brotli
+        );
+        let mut sections = self.sections.iter().collect_vec();
+        sections.sort_by(|a, b| b.1.cmp(a.1));
+        for (name, size) in sections {
+            ret += &format!("  {name}: {size}\n");
+        }
+        ret += "Largest functions:\n";
+        for function in self.functions.iter().take(functions) {
+            ret += &format!("  {}: {}\n", function.name, function.size);
+        }
+        ret
+    }
+
+    /// Changes of all the sizes since the baseline, the biggest growth first.
+    ///
+    /// Functions are compared only if they are listed in both reports, as the lists are
+    /// truncated.
+    pub fn compare(&self, baseline: &Report) -> Vec<Change> {
+        let mut ret = vec![
+            Change::new("total", baseline.total, self.total),
+            Change::new("gzip", baseline.gzip, self.gzip),
+            Change::new("brotli", baseline.brotli, self.brotli),
+        ];
+        let section_names: BTreeSet<_> =
+            self.sections.keys().chain(baseline.sections.keys()).collect();
+        for name in section_names {
+            let before = baseline.sections.get(name).copied().unwrap_or_default();
+            let after = self.sections.get(name).copied().unwrap_or_default();
+            ret.push(Change::new(format!("section {name}"), before, after));
+        }
+        for function in &self.functions {
+            if let Some(before) = baseline.functions.iter().find(|f| f.name == function.name) {
+                ret.push(Change::new(
+                    format!("function {}", function.name),
+                    before.size,
+                    function.size,
+                ));
+            }
+        }
+        ret.retain(|change| change.growth() != 0);
+        ret.sort_by_key(|change| std::cmp::Reverse(change.growth()));
+        ret
+    }
+
+    /// Fail if the gzip-compressed module grew by more than `max_growth` bytes since the baseline.
+    ///
+    /// The error lists the biggest regressions.
+    pub fn check_against(&self, baseline: &Report, max_growth: u64) -> Result {
+        let changes = self.compare(baseline);
+        let growth = self.gzip as i64 - baseline.gzip as i64;
+        if growth > max_growth as i64 {
+            let regressions = changes
+                .iter()
+                .filter(|change| change.growth() > 0)
+                .take(REGRESSIONS_TO_SHOW)
+                .map(|change| format!("  {change}"))
+                .join("\n");
+            bail!(
+                "Compressed WASM grew by {growth} bytes since the baseline, which is more than the \
+                allowed {max_growth} bytes. The biggest regressions:\n{regressions}"
+            );
+        }
+        debug!("Size changes since the baseline: {changes:#?}");
+        Ok(())
+    }
+}
+
+/// Change of a single size between the baseline and the current report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub item:   String,
+    pub before: u64,
+    pub after:  u64,
+}
+
+impl Change {
+    pub fn new(item: impl Into<String>, before: u64, after: u64) -> Self {
+        Self { item: item.into(), before, after }
+    }
+
+    pub fn growth(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} -> {} ({:+})", self.item, self.before, self.after, self.growth())
+    }
+}
+
+
+
+// ==============
+// === Parser ===
+// ==============
+
+const MAGIC: &[u8] = b"\0asm";
+const CUSTOM_SECTION_ID: u8 = 0;
+const IMPORT_SECTION_ID: u8 = 2;
+const CODE_SECTION_ID: u8 = 10;
+const FUNCTION_NAMES_SUBSECTION_ID: u8 = 1;
+
+/// Name of the known section, as used in the [`Report::sections`].
+pub fn section_name(id: u8) -> &'static str {
+    match id {
+        0 => "custom",
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "data_count",
+        13 => "tag",
+        _ => "unknown",
+    }
+}
+
+/// Reader of the binary format primitives.
+#[derive(Clone, Copy, Debug)]
+struct Reader<'a> {
+    data: &'a [u8],
+    pos:  usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(count).filter(|end| *end <= self.data.len());
+        let end = end.with_context(|| format!("Unexpected end of data at {}.", self.pos))?;
+        let ret = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(ret)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Unsigned LEB128 number.
+    fn leb(&mut self) -> Result<u64> {
+        let mut ret = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            ret |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(ret);
+            }
+        }
+        bail!("LEB128 number at {} is too long.", self.pos)
+    }
+
+    fn size(&mut self) -> Result<usize> {
+        Ok(usize::try_from(self.leb()?)?)
+    }
+
+    fn name(&mut self) -> Result<&'a str> {
+        let length = self.size()?;
+        Ok(std::str::from_utf8(self.bytes(length)?)?)
+    }
+
+    /// Skip the table or memory limits.
+    fn skip_limits(&mut self) -> Result {
+        let flags = self.byte()?;
+        self.leb()?;
+        if flags & 1 != 0 {
+            self.leb()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Section<'a> {
+    id:      u8,
+    content: &'a [u8],
+}
+
+impl<'a> Section<'a> {
+    fn custom_name(&self) -> Result<&'a str> {
+        Reader::new(self.content).name()
+    }
+}
+
+/// Just enough of the module structure to attribute its size.
+#[derive(Clone, Debug)]
+struct Module<'a> {
+    sections: Vec<Section<'a>>,
+}
+
+impl<'a> Module<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        ensure!(reader.bytes(4)? == MAGIC, "Not a WASM module.");
+        let version = reader.bytes(4)?;
+        ensure!(version == [1, 0, 0, 0], "Unsupported WASM version {version:?}.");
+        let mut sections = Vec::new();
+        while !reader.is_at_end() {
+            let id = reader.byte()?;
+            let size = reader.size()?;
+            sections.push(Section { id, content: reader.bytes(size)? });
+        }
+        Ok(Self { sections })
+    }
+
+    fn section(&self, id: u8) -> Option<&Section<'a>> {
+        self.sections.iter().find(|section| section.id == id)
+    }
+
+    fn custom_section(&self, name: &str) -> Option<Reader<'a>> {
+        self.sections.iter().filter(|section| section.id == CUSTOM_SECTION_ID).find_map(|section| {
+            let mut reader = Reader::new(section.content);
+            (reader.name().ok()? == name).then_some(reader)
+        })
+    }
+
+    /// Imported functions come first in the function index space.
+    fn imported_function_count(&self) -> Result<u32> {
+        let Some(section) = self.section(IMPORT_SECTION_ID) else { return Ok(0) };
+        let mut reader = Reader::new(section.content);
+        let mut ret = 0;
+        for _ in 0..reader.leb()? {
+            reader.name()?; // Module.
+            reader.name()?; // Field.
+            match reader.byte()? {
+                // Function: type index.
+                0x00 => {
+                    reader.leb()?;
+                    ret += 1;
+                }
+                // Table: reference type and limits.
+                0x01 => {
+                    reader.byte()?;
+                    reader.skip_limits()?;
+                }
+                // Memory: limits.
+                0x02 => reader.skip_limits()?,
+                // Global: value type and mutability.
+                0x03 => {
+                    reader.byte()?;
+                    reader.byte()?;
+                }
+                // Tag: attribute and type index.
+                0x04 => {
+                    reader.byte()?;
+                    reader.leb()?;
+                }
+                kind => bail!("Unknown import kind {kind:#x}."),
+            }
+        }
+        Ok(ret)
+    }
+
+    fn function_body_sizes(&self) -> Result<Vec<u64>> {
+        let Some(section) = self.section(CODE_SECTION_ID) else { return Ok(default()) };
+        let mut reader = Reader::new(section.content);
+        (0..reader.leb()?)
+            .map(|_| {
+                let size = reader.size()?;
+                reader.bytes(size)?;
+                Ok(size as u64)
+            })
+            .collect()
+    }
+
+    /// Function names from the `name` custom section, keyed by the function index.
+    fn function_names(&self) -> Result<BTreeMap<u32, String>> {
+        let mut ret = BTreeMap::new();
+        let Some(mut reader) = self.custom_section("name") else { return Ok(ret) };
+        while !reader.is_at_end() {
+            let id = reader.byte()?;
+            let size = reader.size()?;
+            let mut subsection = Reader::new(reader.bytes(size)?);
+            if id == FUNCTION_NAMES_SUBSECTION_ID {
+                for _ in 0..subsection.leb()? {
+                    let index = u32::try_from(subsection.leb()?)?;
+                    ret.insert(index, subsection.name()?.to_owned());
+                }
+            }
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(id: u8, content: &[u8]) -> Vec<u8> {
+        let mut ret = vec![id, content.len() as u8];
+        ret.extend_from_slice(content);
+        ret
+    }
+
+    fn name(text: &str) -> Vec<u8> {
+        let mut ret = vec![text.len() as u8];
+        ret.extend_from_slice(text.as_bytes());
+        ret
+    }
+
+    /// Module importing one function and defining two, named "small" and "big".
+    fn module() -> Vec<u8> {
+        let mut import = vec![1];
+        import.extend(name("env"));
+        import.extend(name("f"));
+        import.extend([0x00, 0]);
+
+        // Bodies: no locals, some nops, end.
+        let code = [2, 3, 0x00, 0x01, 0x0b, 5, 0x00, 0x01, 0x01, 0x01, 0x0b];
+
+        let mut function_names = vec![2, 1];
+        function_names.extend(name("small"));
+        function_names.push(2);
+        function_names.extend(name("big"));
+        let mut names = name("name");
+        names.push(FUNCTION_NAMES_SUBSECTION_ID);
+        names.push(function_names.len() as u8);
+        names.extend(function_names);
+
+        let mut ret = b"\0asm\x01\0\0\0".to_vec();
+        ret.extend(section(IMPORT_SECTION_ID, &import));
+        ret.extend(section(CODE_SECTION_ID, &code));
+        ret.extend(section(11, &[0]));
+        ret.extend(section(CUSTOM_SECTION_ID, &names));
+        ret
+    }
+
+    #[test]
+    fn analyzing_module() -> Result {
+        let data = module();
+        let report = Report::from_module(&data, 10)?;
+        assert_eq!(report.total, data.len() as u64);
+        assert_eq!(report.sections["code"], 11);
+        assert_eq!(report.sections["data"], 1);
+        assert!(report.sections.contains_key("custom:name"));
+        assert_eq!(report.functions, vec![
+            FunctionSize { name: "big".into(), size: 5 },
+            FunctionSize { name: "small".into(), size: 3 },
+        ]);
+        assert_eq!(Report::from_module(&data, 1)?.functions.len(), 1);
+        assert!(Report::from_module(b"not wasm", 1).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn analyzing_unparsable_module() -> Result {
+        let temp = tempfile::tempdir()?;
+        let path = temp.path().join("broken.wasm");
+        ide_ci::fs::write(&path, b"not wasm")?;
+        let report = Report::analyze(&path, 10).await?;
+        assert_eq!(report.total, 8);
+        assert!(report.gzip > 0);
+        assert!(report.brotli > 0);
+        assert!(report.sections.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn comparing_with_baseline() -> Result {
+        let baseline = Report::from_module(&module(), 10)?;
+        let mut current = baseline.clone();
+        current.gzip += 100;
+        current.sections.insert("data".into(), 90);
+        current.functions[0].size += 10;
+
+        let changes = current.compare(&baseline);
+        assert_eq!(changes[0], Change::new("gzip", 0, 100));
+        assert_eq!(changes[1], Change::new("section data", 1, 90));
+        assert_eq!(changes[2], Change::new("function big", 5, 15));
+        assert_eq!(changes.len(), 3);
+
+        assert!(current.check_against(&baseline, 100).is_ok());
+        let error = current.check_against(&baseline, 99).unwrap_err().to_string();
+        assert!(error.contains("section data: 1 -> 90 (+89)"));
+        Ok(())
+    }
+}
@@ -1,147 +1,244 @@
-use crate::project::*;
-
-use anyhow::Context;
-use ide_ci::programs::cargo;
-use ide_ci::programs::WasmPack;
-
-/// List of crates that should not be tested by wasm-pack test.
-const PACKAGE_BLACKLIST: [&str; 1] = ["integration-test"];
-
-/// Attributes that denote WASM tests.
-const WASM_TEST_ATTRIBUTES: [&str; 2] = ["#[wasm_bindgen_test]", "#[wasm_bindgen_test(async)]"];
-
-/// Subdirectories in the crate directory that contain sources for the crate.
-const SOURCE_SUBDIRECTORIES: [&str; 4] = ["src", "benches", "examples", "tests"];
-
-/// Lists members of given Cargo.toml workspace.
-fn get_all_crates(repo_root: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
-    let pattern = repo_root.as_ref().join("**/Cargo.toml");
-    let all_paths =
-        glob::glob(pattern.as_str()).context(format!("Globbing {} failed", pattern.display()))?;
-    let valid_paths = all_paths.filter_map(|path| match path {
-        // FIXME explain unwrap
-        Ok(path) => Some(path.parent().unwrap().to_owned()),
-        Err(err) => {
-            error!("{err}");
-            None
+//! Finding and running the WASM tests of the workspace crates.
+//!
+//! The crates are described by `cargo metadata`. A crate can opt out of the WASM tests with:
+//! ```toml
+//! [package.metadata.wasm-test]
+//! skip = true
+//! reason = "Tests need a GPU."
+//! ```
+
+use crate::prelude::*;
+
+use crate::project::wasm::INTEGRATION_TESTS_CRATE_NAME;
+
+use ide_ci::cache::Cache;
+use ide_ci::programs::cargo::metadata::Metadata;
+use ide_ci::programs::cargo::metadata::Package;
+
+pub mod browser;
+pub mod runner;
+
+pub use runner::Options;
+
+
+
+/// The crate providing the `#[wasm_bindgen_test]` attribute. Crates not depending on it have no
+/// WASM tests.
+pub const TEST_FRAMEWORK_CRATE: &str = "wasm-bindgen-test";
+
+/// Key in `[package.metadata]` with the crate's WASM test settings.
+pub const METADATA_KEY: &str = "wasm-test";
+
+/// Why the crate's WASM tests are not run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    ProcMacro,
+    /// The crate does not depend on [`TEST_FRAMEWORK_CRATE`].
+    NoTestFramework,
+    /// None of the crate's targets is tested by `cargo test`.
+    NoTestedTargets,
+    /// Run separately, by the `ide integration-test` command.
+    IntegrationTests,
+    /// Requested in `[package.metadata.wasm-test]`, with the optional reason.
+    OptedOut(Option<String>),
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::ProcMacro => write!(f, "proc-macro crate"),
+            SkipReason::NoTestFramework => write!(f, "does not depend on {TEST_FRAMEWORK_CRATE}"),
+            SkipReason::NoTestedTargets => write!(f, "no tested targets"),
+            SkipReason::IntegrationTests => write!(f, "run by `ide integration-test`"),
+            SkipReason::OptedOut(None) => write!(f, "opted out"),
+            SkipReason::OptedOut(Some(reason)) => write!(f, "opted out: {reason}"),
         }
-    });
-    Ok(valid_paths.collect())
+    }
 }
 
-///  Check if the given line of source code is an attribute denoting wasm test.
-fn is_wasm_test_attribute(line: &str) -> bool {
-    WASM_TEST_ATTRIBUTES.contains(&line.trim())
+/// A workspace crate and whether its WASM tests are run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedCrate {
+    pub name:          String,
+    pub manifest_path: PathBuf,
+    /// `None` if the tests are run.
+    pub skip:          Option<SkipReason>,
 }
 
-/// Check if the given workspace member contains any wasm tests in the sources.
-fn has_wasm_tests(member: &Path) -> bool {
-    if let Some(member) = member.to_str() {
-        // We go over selected subdirectories only to avoid entering into sources of other crates
-        // that are nested within this crate subtree.
-        for subdir in SOURCE_SUBDIRECTORIES {
-            let pattern = format!("{}/{}/**/*.rs", member, subdir);
-            for entry in glob::glob(&pattern).unwrap() {
-                let contents = ide_ci::fs::read_to_string(entry.unwrap()).unwrap();
-                if contents.lines().any(is_wasm_test_attribute) {
-                    return true;
-                }
+impl PlannedCrate {
+    pub fn new(package: &Package) -> Result<Self> {
+        Ok(Self {
+            name:          package.name.clone(),
+            manifest_path: package.manifest_path.clone(),
+            skip:          Self::skip_reason(package)?,
+        })
+    }
+
+    fn skip_reason(package: &Package) -> Result<Option<SkipReason>> {
+        let settings = package.metadata_entry([METADATA_KEY]);
+        if let Some(settings) = settings {
+            let skip = settings.get("skip").map(|skip| {
+                skip.as_bool().with_context(|| {
+                    format!(
+                        "`package.metadata.{METADATA_KEY}.skip` of {} must be a boolean.",
+                        package.name
+                    )
+                })
+            });
+            if skip.transpose()?.unwrap_or(false) {
+                let reason =
+                    settings.get("reason").and_then(|r| r.as_str()).map(ToString::to_string);
+                return Ok(Some(SkipReason::OptedOut(reason)));
             }
         }
-        false
-    } else {
-        println!(
-            "cargo:warning=Skipping the crate {} containing non-UTF-8 characters in its path. ",
-            member.to_string_lossy()
-        );
-        false
+        let reason = if package.name == INTEGRATION_TESTS_CRATE_NAME {
+            Some(SkipReason::IntegrationTests)
+        } else if package.is_proc_macro() {
+            Some(SkipReason::ProcMacro)
+        } else if !package.depends_on(TEST_FRAMEWORK_CRATE) {
+            Some(SkipReason::NoTestFramework)
+        } else if !package.targets.iter().any(|target| target.test) {
+            Some(SkipReason::NoTestedTargets)
+        } else {
+            None
+        };
+        Ok(reason)
     }
-}
 
-/// Parses file under given path as TOML value.
-fn parse_toml(path: impl AsRef<Path>) -> toml::Value {
-    let path = path.as_ref();
-    let data = ide_ci::fs::read_to_string(path).unwrap();
-    data.parse().unwrap()
+    pub fn is_tested(&self) -> bool {
+        self.skip.is_none()
+    }
 }
 
-/// Checks if the given member is blacklisted from running the tests.
-fn blacklisted(memeber: &Path) -> bool {
-    PACKAGE_BLACKLIST.iter().any(|blacklisted| memeber.ends_with(blacklisted))
+/// Which workspace crates have their WASM tests run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TestPlan {
+    pub crates: Vec<PlannedCrate>,
 }
 
-/// Checks if given workspace member is a proc-macro crate.
-fn is_proc_macro_crate(member: &Path) -> bool {
-    let cargo_toml_path = member.join("Cargo.toml");
-    let cargo_toml_root = parse_toml(cargo_toml_path);
-    get_proc_macro(cargo_toml_root).contains(&true)
-}
+impl TestPlan {
+    pub fn from_metadata(metadata: &Metadata) -> Result<Self> {
+        let mut crates = metadata.workspace_packages().map(PlannedCrate::new).try_collect_vec()?;
+        crates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Self { crates })
+    }
+
+    pub async fn discover(repo_root: impl AsRef<Path>) -> Result<Self> {
+        Self::from_metadata(&Metadata::of_workspace(repo_root).await?)
+    }
+
+    /// Keep only the crates with the given names. Empty list keeps all the crates.
+    ///
+    /// Fails if any name does not match a workspace crate.
+    pub fn filter(mut self, names: &[String]) -> Result<Self> {
+        if names.is_empty() {
+            return Ok(self);
+        }
+        let unknown = names.iter().filter(|name| !self.crates.iter().any(|c| &c.name == *name));
+        let unknown = unknown.collect_vec();
+        ensure!(unknown.is_empty(), "Unknown crates: {}.", unknown.iter().join(", "));
+        self.crates.retain(|c| names.contains(&c.name));
+        Ok(self)
+    }
 
-/// Retrieve a `lib.proc-macro` field from Cargo.toml
-fn get_proc_macro(cargo_toml: toml::Value) -> Option<bool> {
-    cargo_toml.get("lib")?.get("proc-macro")?.as_bool()
+    pub fn tested(&self) -> impl Iterator<Item = &PlannedCrate> {
+        self.crates.iter().filter(|c| c.is_tested())
+    }
+
+    pub async fn run(&self, repo_root: &Path, cache: &Cache, options: &Options) -> Result {
+        let crates = self.tested().map(|c| (c.name.clone(), c.manifest_path.clone()));
+        runner::test_crates(repo_root, cache, crates.collect_vec(), options).await?;
+        Ok(())
+    }
 }
 
-pub async fn test_all(repo_root: PathBuf) -> Result {
-    // FIXME args
-    //let wasm_pack_args = std::env::args().skip(1).collect::<Vec<_>>();
-    let all_members = get_all_crates(&repo_root)?;
-
-    for member in all_members {
-        let member_str = member.to_string_lossy();
-        if blacklisted(&member) {
-            info!("Skipping blacklisted crate {member_str}");
-        } else if is_proc_macro_crate(&member) {
-            info!("Skipping proc-macro crate {member_str}");
-        } else if has_wasm_tests(&member) {
-            info!("Running tests for {member_str}");
-            WasmPack
-                .cmd()?
-                .current_dir(&repo_root)
-                .arg("test")
-                .arg("--headless")
-                .arg("--chrome")
-                .env("WASM_BINDGEN_TEST_TIMEOUT", "300")
-                // .args(&wasm_pack_args)
-                .arg(&member.strip_prefix(&repo_root).unwrap())
-                .apply(&cargo::Color::Always)
-                .run_ok()
-                .await?;
-        } else {
-            println!("No wasm tests in {}", member_str);
+impl Display for TestPlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for c in &self.crates {
+            match &c.skip {
+                None => writeln!(f, "test  {}", c.name)?,
+                Some(reason) => writeln!(f, "skip  {} ({reason})", c.name)?,
+            }
         }
+        Ok(())
     }
-    Ok(())
 }
 
-// /// Call wasm-pack test for each workspace member
-// ///
-// /// This function reads workspace members list from `Cargo.toml` in current directory, and call
-// /// `wasm-pack test` each member. All script arguments are passed to `wasm-pack` process.
-// fn main() {
-//     let wasm_pack_args = std::env::args().skip(1).collect::<Vec<_>>();
-//     let all_members = get_all_crates();
-//
-//     for member in all_members {
-//         let member_str = member.to_string_lossy();
-//         if blacklisted(&member) {
-//             println!("Skipping blacklisted crate {}", member_str);
-//         } else if is_proc_macro_crate(&member) {
-//             println!("Skipping proc-macro crate {}", member_str);
-//         } else if has_wasm_tests(&member) {
-//             println!("Running tests for {}", member_str);
-//             let mut command = std::process::Command::new("wasm-pack");
-//             command.arg("test").args(&wasm_pack_args).arg(&member);
-//             println!("{:?}", command);
-//             let status = command.status().unwrap();
-//             if !status.success() {
-//                 panic!("Process for {} failed!{}", member_str, match status.code() {
-//                     Some(code) => format!(" Code: {}", code),
-//                     None => String::new(),
-//                 });
-//             }
-//         } else {
-//             println!("No wasm tests in {}", member_str);
-//         }
-//     }
-// }
+/// Find the crates with WASM tests and run them.
+///
+/// If `crates` are given, only they are considered.
+pub async fn test_all(
+    repo_root: PathBuf,
+    cache: Cache,
+    crates: Vec<String>,
+    options: Options,
+) -> Result {
+    let plan = TestPlan::discover(&repo_root).await?.filter(&crates)?;
+    info!("WASM test plan:\n{plan}");
+    plan.run(&repo_root, &cache, &options).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn planning_from_metadata() -> Result {
+        let package = |name: &str, dependencies: &str, kind: &str, metadata: &str| {
+            format!(
+                r#"{{
+                "name": "{name}", "version": "0.1.0", "id": "{name} 0.1.0",
+                "manifest_path": "/repo/{name}/Cargo.toml",
+                "dependencies": [{dependencies}],
+                "targets": [{{"name": "{name}", "kind": ["{kind}"], "src_path": "/repo/{name}/src/lib.rs", "test": true}}],
+                "metadata": {metadata}
+            }}"#
+            )
+        };
+        let test_dependency = r#"{"name": "wasm-bindgen-test", "kind": "dev"}"#;
+        let packages = [
+            package("enso-prelude", test_dependency, "lib", "null"),
+            package("enso-shapely-macros", test_dependency, "proc-macro", "null"),
+            package("enso-data", "", "lib", "null"),
+            package("enso-integration-test", test_dependency, "lib", "null"),
+            package(
+                "enso-gpu",
+                test_dependency,
+                "lib",
+                r#"{"wasm-test": {"skip": true, "reason": "Needs a GPU."}}"#,
+            ),
+        ];
+        let ids = [
+            "enso-prelude",
+            "enso-shapely-macros",
+            "enso-data",
+            "enso-integration-test",
+            "enso-gpu",
+        ]
+        .map(|name| format!("\"{name} 0.1.0\""));
+        let json = format!(
+            r#"{{"packages": [{}], "workspace_members": [{}], "workspace_root": "/repo", "target_directory": "/repo/target"}}"#,
+            packages.join(","),
+            ids.join(",")
+        );
+        let metadata: Metadata = serde_json::from_str(&json)?;
+        let plan = TestPlan::from_metadata(&metadata)?;
+        let skip_reason =
+            |name: &str| plan.crates.iter().find(|c| c.name == name).unwrap().skip.clone();
+        assert_eq!(skip_reason("enso-prelude"), None);
+        assert_eq!(skip_reason("enso-shapely-macros"), Some(SkipReason::ProcMacro));
+        assert_eq!(skip_reason("enso-data"), Some(SkipReason::NoTestFramework));
+        assert_eq!(skip_reason("enso-integration-test"), Some(SkipReason::IntegrationTests));
+        assert_eq!(
+            skip_reason("enso-gpu"),
+            Some(SkipReason::OptedOut(Some("Needs a GPU.".into())))
+        );
+        assert_eq!(plan.tested().count(), 1);
+        assert!(plan.to_string().contains("skip  enso-gpu (opted out: Needs a GPU.)"));
+
+        let filtered = plan.clone().filter(&["enso-data".into()])?;
+        assert_eq!(filtered.crates.len(), 1);
+        assert!(plan.filter(&["enso-unknown".into()]).is_err());
+        Ok(())
+    }
+}
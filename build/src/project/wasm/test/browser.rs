@@ -0,0 +1,104 @@
+//! Selecting the environment that `wasm-bindgen-test-runner` runs the tests in.
+
+use crate::prelude::*;
+
+use crate::project::wasm::env;
+
+use ide_ci::cache::goodie::chrome::ChromeForTesting;
+use ide_ci::cache::goodie::chrome::Component;
+use ide_ci::cache::Cache;
+
+
+
+/// Version of Chrome for Testing that is downloaded if no chromedriver is installed.
+pub const CHROME_VERSION: &str = "121.0.6167.85";
+
+/// Name of the file with the WebDriver capabilities, read by `wasm-bindgen-test-runner` from its
+/// working directory.
+pub const WEBDRIVER_CONFIG: &str = "webdriver.json";
+
+/// Which environment was requested for the tests.
+#[derive(clap::ArgEnum, Clone, Copy, Debug, Default, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum BrowserChoice {
+    /// Use the installed chromedriver or download Chrome for Testing. Fails if neither works.
+    #[default]
+    Chrome,
+    /// Run only the tests that do not need a browser, in Node.
+    Node,
+}
+
+/// The environment that the tests are run in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Browser {
+    /// Chrome driven by chromedriver. The suites not configured to run in a browser still run in
+    /// Node.
+    Chrome {
+        chromedriver: PathBuf,
+        /// If not set, chromedriver looks for the Chrome installed in the system.
+        binary:       Option<PathBuf>,
+    },
+    /// Only the suites that do not need a browser are run, in Node.
+    Node,
+}
+
+impl Browser {
+    pub async fn discover(choice: BrowserChoice, cache: &Cache) -> Result<Self> {
+        match choice {
+            BrowserChoice::Node => Ok(Browser::Node),
+            BrowserChoice::Chrome => Self::chrome(cache).await.context(
+                "Chrome is not available. Pass `--browser node` to run only the tests not \
+                needing a browser.",
+            ),
+        }
+    }
+
+    /// Use the installed chromedriver (from `CHROMEDRIVER` or `PATH`) or download the pinned
+    /// Chrome for Testing with its chromedriver.
+    pub async fn chrome(cache: &Cache) -> Result<Self> {
+        let installed =
+            env::CHROMEDRIVER.get().or_else(|_| ide_ci::program::lookup("chromedriver"));
+        if let Ok(chromedriver) = installed {
+            debug!("Using the installed chromedriver: {}", chromedriver.display());
+            return Ok(Browser::Chrome { chromedriver, binary: None });
+        }
+        let get = |component| ChromeForTesting { version: CHROME_VERSION.into(), component };
+        let chromedriver = get(Component::Chromedriver).get(cache).await?;
+        let binary = get(Component::Chrome).get(cache).await?;
+        Ok(Browser::Chrome { chromedriver, binary: Some(binary) })
+    }
+
+    /// The WebDriver capabilities to be put in the [`WEBDRIVER_CONFIG`] file, if any.
+    pub fn webdriver_config(&self) -> Option<serde_json::Value> {
+        match self {
+            Browser::Chrome { binary: Some(binary), .. } =>
+                Some(serde_json::json!({ "goog:chromeOptions": { "binary": binary } })),
+            _ => None,
+        }
+    }
+
+    /// Set up the `wasm-bindgen-test-runner` invocation running in the given working directory.
+    pub fn configure(
+        &self,
+        command: &mut impl IsCommandWrapper,
+        working_dir: &Path,
+        headless: bool,
+    ) -> Result {
+        match self {
+            Browser::Chrome { chromedriver, .. } => {
+                command.set_env(env::CHROMEDRIVER, chromedriver)?;
+                if !headless {
+                    command.set_env(env::NO_HEADLESS, &true)?;
+                }
+            }
+            Browser::Node => {
+                command.set_env(env::WASM_BINDGEN_TEST_ONLY_NODE, &true)?;
+            }
+        }
+        if let Some(config) = self.webdriver_config() {
+            let path = working_dir.join(WEBDRIVER_CONFIG);
+            ide_ci::fs::write(path, serde_json::to_string_pretty(&config)?)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,344 @@
+//! Compiling the WASM test suites with Cargo and running them with `wasm-bindgen-test-runner`.
+//!
+//! Each test binary runs in its own process, in parallel with the others, with a time limit. The
+//! results are written as JUnit reports, one per crate.
+
+use crate::prelude::*;
+
+use crate::project::wasm::env;
+use crate::project::wasm::test::browser::Browser;
+use crate::project::wasm::test::browser::BrowserChoice;
+use crate::rust::lockfile::Lockfile;
+
+use ide_ci::cache::goodie::wasm_bindgen::WasmBindgenCli;
+use ide_ci::cache::Cache;
+use ide_ci::models::junit;
+use ide_ci::programs::cargo;
+use ide_ci::programs::wasm_bindgen::WASM_TARGET;
+use ide_ci::programs::Cargo;
+use ide_ci::programs::WasmBindgenTestRunner;
+use std::process::Output;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+
+
+/// How long the tests of a single binary may run before they are killed.
+pub const DEFAULT_BINARY_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How long the browser may run the tests of a binary, see [`Options::test_timeout`].
+pub const DEFAULT_TEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Printed by `wasm-bindgen-test-runner` when it skips a browser suite in the Node-only mode.
+const BROWSER_ONLY_SKIPPED: &str = "only configured to run in a browser";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    pub browser:        BrowserChoice,
+    pub headless:       bool,
+    /// Limit for running all the tests of a single test binary.
+    pub binary_timeout: Duration,
+    /// How long `wasm-bindgen-test-runner` waits for the headless browser to run all the tests of
+    /// a binary. It does not apply to the tests run in Node, only [`Self::binary_timeout`] does.
+    pub test_timeout:   Option<Duration>,
+    /// How many test binaries may run at once.
+    pub jobs:           usize,
+    /// Where the JUnit reports are written.
+    pub junit_dir:      PathBuf,
+    /// Additional options for `cargo test`, like `--profile`.
+    pub cargo_options:  Vec<String>,
+}
+
+impl Options {
+    pub fn new(junit_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            browser:        default(),
+            headless:       true,
+            binary_timeout: DEFAULT_BINARY_TIMEOUT,
+            test_timeout:   Some(DEFAULT_TEST_TIMEOUT),
+            jobs:           std::thread::available_parallelism().map_or(1, |n| n.get()),
+            junit_dir:      junit_dir.into(),
+            cargo_options:  default(),
+        }
+    }
+}
+
+/// A compiled test suite of a crate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestBinary {
+    pub crate_name: String,
+    /// The `.wasm` file.
+    pub path:       PathBuf,
+}
+
+impl TestBinary {
+    /// Name of the JUnit test suite, unique among the crate's test binaries.
+    pub fn suite_name(&self) -> String {
+        let stem = self.path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+        // Cargo appends the hash to the target name, e.g. `enso_prelude-0123456789abcdef`.
+        let target = stem.rsplit_once('-').map_or(&*stem, |(target, _hash)| target);
+        if target == self.crate_name.replace('-', "_") {
+            self.crate_name.clone()
+        } else {
+            format!("{}::{target}", self.crate_name)
+        }
+    }
+}
+
+/// Paths of the test executables from Cargo's JSON messages.
+pub fn test_executables(cargo_messages: &str) -> Vec<PathBuf> {
+    #[derive(Deserialize)]
+    struct Profile {
+        test: bool,
+    }
+    #[derive(Deserialize)]
+    struct Message {
+        reason:     String,
+        profile:    Option<Profile>,
+        executable: Option<PathBuf>,
+    }
+    cargo_messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Message>(line).ok())
+        .filter(|message| message.reason == "compiler-artifact")
+        .filter(|message| message.profile.as_ref().map_or(false, |profile| profile.test))
+        .filter_map(|message| message.executable)
+        .collect()
+}
+
+/// Compile the crate's tests to WASM, without running them.
+pub async fn compile(
+    repo_root: &Path,
+    crate_name: &str,
+    manifest: &Path,
+    cargo_options: &[String],
+) -> Result<Vec<TestBinary>> {
+    let stdout = Cargo
+        .cmd()?
+        .current_dir(repo_root)
+        .apply(&cargo::Command::Test)
+        .arg("--no-run")
+        .arg("--manifest-path")
+        .arg(manifest)
+        .args(["--target", WASM_TARGET, "--message-format", "json-render-diagnostics"])
+        .args(cargo_options)
+        .run_stdout()
+        .await?;
+    let binaries = test_executables(&stdout)
+        .into_iter()
+        .map(|path| TestBinary { crate_name: crate_name.into(), path })
+        .collect();
+    Ok(binaries)
+}
+
+/// Turn the `wasm-bindgen-test-runner` output into a JUnit test suite.
+///
+/// The failures' messages are taken from the `---- <test> output ----` sections.
+pub fn parse_output(suite_name: &str, output: &str) -> junit::TestSuite {
+    let mut failure_outputs = BTreeMap::<String, String>::new();
+    let mut current_failure = None;
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("---- ").and_then(|l| l.strip_suffix(" output ----"))
+        {
+            current_failure = Some(name.to_owned());
+        } else if line.trim() == "failures:" || line.starts_with("test result:") {
+            current_failure = None;
+        } else if let Some(name) = &current_failure {
+            let text = failure_outputs.entry(name.clone()).or_default();
+            text.push_str(line.trim());
+            text.push('\n');
+        }
+    }
+
+    let test_cases = output
+        .lines()
+        .filter_map(|line| line.strip_prefix("test ")?.rsplit_once(" ... "))
+        .map(|(name, result)| {
+            let outcome = match result.trim() {
+                "ok" => junit::Outcome::Passed,
+                "ignored" => junit::Outcome::Skipped,
+                _ => junit::Outcome::Failed {
+                    message: failure_outputs.get(name).cloned().unwrap_or_default(),
+                },
+            };
+            junit::TestCase { name: name.into(), class_name: suite_name.into(), time: 0.0, outcome }
+        })
+        .collect();
+    junit::TestSuite { name: suite_name.into(), time: 0.0, test_cases }
+}
+
+/// A test case standing for a problem with running the whole suite.
+fn suite_level_case(suite_name: &str, name: &str, outcome: junit::Outcome) -> junit::TestCase {
+    junit::TestCase { name: name.into(), class_name: suite_name.into(), time: 0.0, outcome }
+}
+
+/// Run the binary's tests, never failing: problems are reported in the returned suite.
+pub async fn run(binary: &TestBinary, browser: &Browser, options: &Options) -> junit::TestSuite {
+    let suite_name = binary.suite_name();
+    let runner_error =
+        |message| suite_level_case(&suite_name, "<runner>", junit::Outcome::Error { message });
+    let start = Instant::now();
+    let output = run_runner(binary, browser, options);
+    let mut suite = match tokio::time::timeout(options.binary_timeout, output).await {
+        Err(_) => {
+            let message = format!("Timed out after {:?}.", options.binary_timeout);
+            junit::TestSuite {
+                name: suite_name.clone(),
+                test_cases: vec![runner_error(message)],
+                ..default()
+            }
+        }
+        Ok(Err(e)) => junit::TestSuite {
+            name: suite_name.clone(),
+            test_cases: vec![runner_error(format!("{e:?}"))],
+            ..default()
+        },
+        Ok(Ok(output)) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let mut suite = parse_output(&suite_name, &stdout);
+            if stdout.contains(BROWSER_ONLY_SKIPPED) {
+                info!("Skipped {suite_name}, as it requires a browser.");
+                suite.test_cases.push(suite_level_case(
+                    &suite_name,
+                    "<browser>",
+                    junit::Outcome::Skipped,
+                ));
+            } else if !output.status.success() && suite.failures() == 0 {
+                // The runner itself failed, e.g. could not start the browser.
+                suite.test_cases.push(runner_error(format!("{}\n{stderr}", output.status)));
+            }
+            if !output.status.success() {
+                warn!("Tests of {suite_name} failed:\n{stdout}\n{stderr}");
+            }
+            suite
+        }
+    };
+    suite.time = start.elapsed().as_secs_f64();
+    suite
+}
+
+async fn run_runner(binary: &TestBinary, browser: &Browser, options: &Options) -> Result<Output> {
+    let working_dir = tempfile::tempdir()?;
+    let mut command = WasmBindgenTestRunner.cmd()?;
+    browser.configure(&mut command, working_dir.path(), options.headless)?;
+    command
+        .current_dir(working_dir.path())
+        .kill_on_drop(true)
+        .set_env_opt(
+            env::WASM_BINDGEN_TEST_TIMEOUT,
+            options.test_timeout.map(|t| t.as_secs()).as_ref(),
+        )?
+        .arg(&binary.path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let output = command.spawn()?.wait_with_output().await?;
+    Ok(output)
+}
+
+/// Compile the tests of the given crates (name and manifest path) and run them.
+///
+/// The JUnit report of each crate is written to [`Options::junit_dir`]. Fails if any test failed.
+pub async fn test_crates(
+    repo_root: &Path,
+    cache: &Cache,
+    crates: impl IntoIterator<Item = (String, PathBuf)>,
+    options: &Options,
+) -> Result<junit::Report> {
+    // The runner must match the `wasm-bindgen` the tests were compiled with.
+    let lockfile = Lockfile::read(repo_root.join("Cargo.lock"))?;
+    let version = lockfile.version_of("wasm-bindgen")?.clone();
+    WasmBindgenCli { version }.install_if_missing(cache).await?;
+    let browser = Browser::discover(options.browser, cache).await?;
+    info!("Running the WASM tests in {browser:?}.");
+
+    let mut binaries = Vec::new();
+    for (name, manifest) in crates {
+        info!("Compiling tests of {name}.");
+        binaries.extend(compile(repo_root, &name, &manifest, &options.cargo_options).await?);
+    }
+
+    // The futures are created upfront, as a closure returning them does not satisfy the
+    // higher-ranked bounds of the boxed CLI future.
+    let browser = &browser;
+    let runs = binaries.iter().map(|binary| async move {
+        let suite = run(binary, browser, options).await;
+        (binary, suite)
+    });
+    let results: Vec<_> = futures::stream::iter(runs.collect_vec())
+        .buffer_unordered(options.jobs.max(1))
+        .collect()
+        .await;
+
+    let mut reports = BTreeMap::<&str, junit::Report>::new();
+    for (binary, suite) in results {
+        reports.entry(&binary.crate_name).or_default().suites.push(suite);
+    }
+    let mut ret = junit::Report::default();
+    for (crate_name, report) in reports {
+        report.write(options.junit_dir.join(format!("{crate_name}.xml")))?;
+        ret.merge(report);
+    }
+
+    let failed: Vec<_> =
+        ret.failed_cases().map(|(suite, case)| (&suite.name, &case.name)).collect();
+    for (suite, case) in &failed {
+        error!("Failed: {suite}: {case}");
+    }
+    ensure!(failed.is_empty(), "{} WASM tests failed.", failed.len());
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_runner_output() {
+        let output = r#"
+running 3 tests
+test enso_prelude::tests::mapping ... ok
+test enso_prelude::tests::folding ... FAIL
+test enso_prelude::tests::sorting ... ignored
+
+failures:
+
+---- enso_prelude::tests::folding output ----
+    error output:
+        panicked at 'assertion failed: `(left == right)`'
+
+failures:
+
+    enso_prelude::tests::folding
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored
+"#;
+        let suite = parse_output("enso-prelude", output);
+        assert_eq!(suite.test_cases.len(), 3);
+        assert_eq!(suite.test_cases[0].outcome, junit::Outcome::Passed);
+        assert_eq!(suite.test_cases[2].outcome, junit::Outcome::Skipped);
+        let junit::Outcome::Failed { message } = &suite.test_cases[1].outcome else {
+            panic!("Expected a failure, got {:?}.", suite.test_cases[1].outcome);
+        };
+        assert!(message.contains("assertion failed"), "{message}");
+    }
+
+    #[test]
+    fn finding_test_executables() {
+        let messages = r#"{"reason":"compiler-artifact","profile":{"test":false},"executable":null}
+{"reason":"compiler-artifact","profile":{"test":true},"executable":"/repo/target/wasm32-unknown-unknown/debug/deps/enso_prelude-0123456789abcdef.wasm"}
+{"reason":"build-finished","success":true}
+"#;
+        let executables = test_executables(messages);
+        assert_eq!(executables.len(), 1);
+        let binary =
+            TestBinary { crate_name: "enso-prelude".into(), path: executables[0].clone() };
+        assert_eq!(binary.suite_name(), "enso-prelude");
+        let binary = TestBinary {
+            crate_name: "enso-prelude".into(),
+            path:       "/repo/target/deps/integration-0123456789abcdef.wasm".into(),
+        };
+        assert_eq!(binary.suite_name(), "enso-prelude::integration");
+    }
+}
@@ -0,0 +1,293 @@
+//! In-process watching of the target's sources, rebuilding it when they change.
+//!
+//! Unlike spawning `cargo watch`, this calls [`IsTarget::build`] directly, so any target can be
+//! watched without passing its inputs through the command line.
+
+use crate::prelude::*;
+
+use crate::project::Context;
+use crate::project::IsTarget;
+use crate::project::IsWatcher;
+use crate::source::BuildTargetJob;
+
+use derivative::Derivative;
+use ignore::gitignore::Gitignore;
+use notify::RecursiveMode;
+use notify::Watcher as _;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::task::JoinHandle;
+
+
+
+/// How long the sources must stay untouched before the rebuild starts.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchOptions {
+    pub debounce: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self { debounce: DEFAULT_DEBOUNCE }
+    }
+}
+
+/// Decides which changed paths should trigger a rebuild.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    /// Only changes under these directories are relevant.
+    pub roots:      Vec<PathBuf>,
+    /// Changes under these directories are ignored, e.g. so the build outputs don't retrigger it.
+    pub excluded:   Vec<PathBuf>,
+    /// Rules from all the `.gitignore` files under the roots.
+    pub gitignores: Vec<Gitignore>,
+}
+
+impl Filter {
+    /// Collect the `.gitignore` files from the roots.
+    ///
+    /// The walk itself respects the already found rules, so the ignored directories (like
+    /// `target`) are not traversed.
+    pub fn new(roots: Vec<PathBuf>, excluded: Vec<PathBuf>) -> Result<Self> {
+        let mut gitignores = Vec::new();
+        for root in &roots {
+            for entry in ignore::WalkBuilder::new(root).hidden(false).require_git(false).build() {
+                let entry = entry?;
+                if entry.file_name() == ".gitignore" {
+                    let (gitignore, error) = Gitignore::new(entry.path());
+                    if let Some(error) = error {
+                        warn!("Failed to parse {}: {error}", entry.path().display());
+                    }
+                    gitignores.push(gitignore);
+                }
+            }
+        }
+        Ok(Self { roots, excluded, gitignores })
+    }
+
+    /// The directories under `root` to watch, i.e. the ones that are neither ignored nor
+    /// excluded.
+    pub fn directories_to_watch(&self, root: &Path) -> Result<Vec<PathBuf>> {
+        let mut ret = Vec::new();
+        let walker = ignore::WalkBuilder::new(root).hidden(false).require_git(false).build();
+        for entry in walker {
+            let entry = entry?;
+            let is_dir = entry.file_type().map_or(false, |file_type| file_type.is_dir());
+            if is_dir && self.is_relevant(entry.path()) {
+                ret.push(entry.into_path());
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn is_relevant(&self, path: &Path) -> bool {
+        let is_in_roots = self.roots.iter().any(|root| path.starts_with(root));
+        let is_excluded = self.excluded.iter().any(|excluded| path.starts_with(excluded))
+            || path.components().any(|component| component.as_os_str() == ".git");
+        let is_ignored = self.gitignores.iter().any(|gitignore| {
+            path.starts_with(gitignore.path())
+                && gitignore.matched_path_or_any_parents(path, path.is_dir()).is_ignore()
+        });
+        is_in_roots && !is_excluded && !is_ignored
+    }
+}
+
+/// The watch process, rebuilding the target in the background.
+///
+/// Dropping the watcher stops the watch.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct NativeWatcher<Target: IsTarget> {
+    /// The artifact from the first build. It is updated in place by the subsequent ones.
+    #[derivative(Debug = "ignore")]
+    pub artifact: Target::Artifact,
+    pub task:     JoinHandle<Result>,
+}
+
+impl<Target: IsTarget> AsRef<Target::Artifact> for NativeWatcher<Target> {
+    fn as_ref(&self) -> &Target::Artifact {
+        &self.artifact
+    }
+}
+
+impl<Target: IsTarget> IsWatcher<Target> for NativeWatcher<Target> {
+    fn wait_for_finish(&mut self) -> BoxFuture<Result> {
+        async move { (&mut self.task).await? }.boxed()
+    }
+}
+
+impl<Target: IsTarget> Drop for NativeWatcher<Target> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Build the target and keep rebuilding it whenever its sources change.
+///
+/// The sources are looked for in [`IsTarget::source_roots`].
+///
+/// The returned future completes after the first build, so the artifacts are in place for anyone
+/// depending on them. If the first build fails, no watch is started. Later failures are only
+/// reported, as the next change may fix them.
+pub fn watch<Target: IsTarget>(
+    target: Target,
+    context: Context,
+    job: BuildTargetJob<Target>,
+    options: WatchOptions,
+) -> BoxFuture<'static, Result<NativeWatcher<Target>>>
+where
+    Target::BuildInput: Clone + Sync,
+{
+    async move {
+        let artifact = target.build(context.clone(), job.clone()).await?;
+        let roots = target.source_roots(&context, &job.inner);
+        let filter = Filter::new(roots, vec![job.destination.clone()])?;
+
+        let (sender, events) = tokio::sync::mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            // Fails only when the watch loop has finished, so nobody is interested anymore.
+            let _ = sender.send(event);
+        })?;
+        let mut changes = Changes { events, watcher, filter, debounce: options.debounce };
+        for root in changes.filter.roots.clone() {
+            changes.watch_directory(&root)?;
+        }
+        let roots = changes.filter.roots.iter().map(|r| r.display()).join(", ");
+        info!("Watching {roots} for changes.");
+
+        let task = tokio::spawn(async move {
+            loop {
+                changes.next().await?;
+                'build: loop {
+                    changes.settle().await?;
+                    // Restarting the loop drops the build future, cancelling the in-flight build.
+                    let build = target.build(context.clone(), job.clone());
+                    tokio::select! {
+                        result = build => {
+                            match result {
+                                Ok(_) => info!("Rebuilt {}.", target.artifact_name()),
+                                Err(e) =>
+                                    error!("Rebuilding {} failed: {e:?}", target.artifact_name()),
+                            }
+                            break 'build;
+                        }
+                        changed = changes.next() => {
+                            changed?;
+                            info!("Sources changed during the build, restarting it.");
+                        }
+                    }
+                }
+            }
+        });
+        Ok(NativeWatcher { artifact, task })
+    }
+    .boxed()
+}
+
+/// Stream of the relevant filesystem changes.
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct Changes {
+    events:   UnboundedReceiver<notify::Result<notify::Event>>,
+    /// Delivers the events only while alive.
+    #[derivative(Debug = "ignore")]
+    watcher:  notify::RecommendedWatcher,
+    filter:   Filter,
+    debounce: Duration,
+}
+
+impl Changes {
+    /// Watch the directory and its relevant subdirectories.
+    ///
+    /// Each directory is watched separately, so the ignored ones (like `target`) do not use up
+    /// the watches.
+    fn watch_directory(&mut self, directory: &Path) -> Result {
+        for directory in self.filter.directories_to_watch(directory)? {
+            self.watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+        }
+        Ok(())
+    }
+
+    /// Wait for any relevant change. Returns the changed paths.
+    async fn next(&mut self) -> Result<Vec<PathBuf>> {
+        loop {
+            let changed = self.receive().await?;
+            if !changed.is_empty() {
+                debug!("Relevant changes: {changed:?}");
+                return Ok(changed);
+            }
+        }
+    }
+
+    /// Wait until there were no relevant changes for the debounce period.
+    async fn settle(&mut self) -> Result {
+        let mut deadline = tokio::time::Instant::now() + self.debounce;
+        while let Ok(changed) = tokio::time::timeout_at(deadline, self.receive()).await {
+            if !changed?.is_empty() {
+                deadline = tokio::time::Instant::now() + self.debounce;
+            }
+        }
+        Ok(())
+    }
+
+    /// Receive the next event, returning its relevant paths (possibly none).
+    async fn receive(&mut self) -> Result<Vec<PathBuf>> {
+        match self.events.recv().await {
+            Some(Ok(event)) => {
+                let changed = event
+                    .paths
+                    .into_iter()
+                    .filter(|path| self.filter.is_relevant(path))
+                    .collect_vec();
+                if event.kind.is_create() {
+                    for directory in changed.iter().filter(|path| path.is_dir()) {
+                        if let Err(e) = self.watch_directory(directory) {
+                            warn!("Failed to watch {}: {e}", directory.display());
+                        }
+                    }
+                }
+                Ok(changed)
+            }
+            Some(Err(e)) => {
+                warn!("Error while watching the sources: {e}");
+                Ok(default())
+            }
+            None => bail!("The file watcher has stopped."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filtering_paths() -> Result {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        ide_ci::fs::write(root.join(".gitignore"), "target/\n*.log\n")?;
+        ide_ci::fs::write(root.join("lib/.gitignore"), "generated.rs\n")?;
+        ide_ci::fs::create_dir_if_missing(root.join("target"))?;
+        ide_ci::fs::create_dir_if_missing(root.join("dist"))?;
+
+        let filter = Filter::new(vec![root.into()], vec![root.join("dist")])?;
+        assert_eq!(filter.gitignores.len(), 2);
+        assert!(filter.is_relevant(&root.join("lib/src/lib.rs")));
+        assert!(filter.is_relevant(&root.join("generated.rs")));
+        assert!(!filter.is_relevant(&root.join("lib/generated.rs")));
+        assert!(!filter.is_relevant(&root.join("target/debug/foo")));
+        assert!(!filter.is_relevant(&root.join("build.log")));
+        assert!(!filter.is_relevant(&root.join("dist/ide.wasm")));
+        assert!(!filter.is_relevant(&root.join(".git/index")));
+        assert!(!filter.is_relevant(Path::new("/elsewhere/lib.rs")));
+
+        ide_ci::fs::create_dir_if_missing(root.join("lib/src"))?;
+        ide_ci::fs::create_dir_if_missing(root.join("target/debug"))?;
+        let mut directories = filter.directories_to_watch(root)?;
+        directories.sort();
+        assert_eq!(directories, [root.to_path_buf(), root.join("lib"), root.join("lib/src")]);
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+//! Container image registries that we publish the images to.
+
+use crate::prelude::*;
+
+use ide_ci::programs::docker::Credentials;
+use ide_ci::programs::docker::ImageId;
+use ide_ci::programs::Docker;
+use std::str::FromStr;
+
+
+
+pub mod env {
+    ide_ci::define_env_var! {
+        /// Client ID of the Azure service principal allowed to push to the Container Registry.
+        AZURE_CLIENT_ID, String;
+
+        /// Secret of the Azure service principal, see [`AZURE_CLIENT_ID`].
+        AZURE_CLIENT_SECRET, String;
+
+        /// User name for the generic registry. If not set, the registry is used anonymously.
+        ENSO_DOCKER_REGISTRY_USERNAME, String;
+
+        /// Password for the generic registry, see [`ENSO_DOCKER_REGISTRY_USERNAME`].
+        ENSO_DOCKER_REGISTRY_PASSWORD, String;
+    }
+}
+
+/// Server of the GitHub Container Registry.
+pub const GHCR_SERVER: &str = "ghcr.io";
+
+/// An image repository in a container registry.
+///
+/// The textual form (used e.g. on the command line) is one of:
+/// * `ecr:<repository>` — AWS Elastic Container Registry, using the AWS credentials from the
+///   environment;
+/// * `acr:<registry>/<repository>` — Azure Container Registry, using the service principal given by
+///   [`env::AZURE_CLIENT_ID`] and [`env::AZURE_CLIENT_SECRET`];
+/// * `ghcr:<owner>/<repository>` — GitHub Container Registry, using the GitHub token;
+/// * `<server>/<repository>` — any other registry, using [`env::ENSO_DOCKER_REGISTRY_USERNAME`] and
+///   [`env::ENSO_DOCKER_REGISTRY_PASSWORD`] if set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Registry {
+    Ecr { repository: String },
+    Acr { registry: String, repository: String },
+    Ghcr { owner: String, repository: String },
+    Generic { server: String, repository: String },
+}
+
+impl FromStr for Registry {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let split = |text: &str| -> Result<(String, String)> {
+            let (first, rest) =
+                text.split_once('/').with_context(|| format!("Missing repository in `{s}`."))?;
+            ensure!(!first.is_empty() && !rest.is_empty(), "Invalid registry `{s}`.");
+            Ok((first.into(), rest.into()))
+        };
+        Ok(match s.split_once(':') {
+            Some(("ecr", repository)) => Registry::Ecr { repository: repository.into() },
+            Some(("acr", rest)) => {
+                let (registry, repository) = split(rest)?;
+                Registry::Acr { registry, repository }
+            }
+            Some(("ghcr", rest)) => {
+                let (owner, repository) = split(rest)?;
+                Registry::Ghcr { owner, repository }
+            }
+            // Anything else is a server name, possibly with a port.
+            _ => {
+                let (server, repository) = split(s)?;
+                Registry::Generic { server, repository }
+            }
+        })
+    }
+}
+
+impl Display for Registry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Registry::Ecr { repository } => write!(f, "ecr:{repository}"),
+            Registry::Acr { registry, repository } => write!(f, "acr:{registry}/{repository}"),
+            Registry::Ghcr { owner, repository } => write!(f, "ghcr:{owner}/{repository}"),
+            Registry::Generic { server, repository } => write!(f, "{server}/{repository}"),
+        }
+    }
+}
+
+impl Registry {
+    /// Server hosting the Azure Container Registry of the given name.
+    pub fn acr_server(registry: &str) -> String {
+        format!("{registry}.azurecr.io")
+    }
+
+    /// Full name of the image repository, i.e. the image name without the tag.
+    pub async fn image_repository(&self) -> Result<String> {
+        Ok(match self {
+            Registry::Ecr { repository } => {
+                let client = aws_sdk_ecr::Client::new(&aws_config::load_from_env().await);
+                crate::aws::ecr::get_repository_uri(&client, repository).await?
+            }
+            Registry::Acr { registry, repository } =>
+                format!("{}/{repository}", Self::acr_server(registry)),
+            Registry::Ghcr { owner, repository } => format!("{GHCR_SERVER}/{owner}/{repository}"),
+            Registry::Generic { server, repository } => format!("{server}/{repository}"),
+        })
+    }
+
+    /// Credentials to log in before pushing. `None` if the registry can be used anonymously.
+    pub async fn credentials(&self) -> Result<Option<Credentials>> {
+        Ok(match self {
+            Registry::Ecr { .. } => {
+                let client = aws_sdk_ecr::Client::new(&aws_config::load_from_env().await);
+                Some(crate::aws::ecr::get_credentials(&client).await?)
+            }
+            Registry::Acr { registry, .. } => Some(Credentials::new(
+                env::AZURE_CLIENT_ID.get()?,
+                env::AZURE_CLIENT_SECRET.get()?,
+                Self::acr_server(registry),
+            )),
+            Registry::Ghcr { owner, .. } => {
+                let username =
+                    ide_ci::actions::env::GITHUB_ACTOR.get().unwrap_or_else(|_| owner.clone());
+                let token = crate::retrieve_github_access_token()?;
+                Some(Credentials::new(username, token, GHCR_SERVER))
+            }
+            Registry::Generic { server, .. } => match env::ENSO_DOCKER_REGISTRY_USERNAME.get() {
+                Ok(username) => {
+                    let password = env::ENSO_DOCKER_REGISTRY_PASSWORD.get()?;
+                    Some(Credentials::new(username, password, server))
+                }
+                Err(_) => None,
+            },
+        })
+    }
+
+    /// Tag the local image and push it to this registry.
+    ///
+    /// Returns the full name of the pushed image.
+    pub async fn push(&self, image: &ImageId, tag: &str) -> Result<String> {
+        let target = format!("{}:{tag}", self.image_repository().await?);
+        info!("Pushing {image} as {target}.");
+        Docker.tag(&image.0, &target).await?;
+        match self.credentials().await? {
+            Some(credentials) => Docker.while_logged_in(credentials, || Docker.push(&target)).await,
+            None => Docker.push(&target).await,
+        }
+        .with_context(|| format!("Failed to push {target} to {self}."))?;
+        Ok(target)
+    }
+}
+
+/// Log in to all the registries that require it, run the action and log out.
+pub async fn while_logged_in_all<F: Future<Output = Result<T>>, T>(
+    registries: &[Registry],
+    f: impl FnOnce() -> F,
+) -> Result<T> {
+    let mut servers = Vec::new();
+    let mut result = Ok(());
+    for registry in registries {
+        match registry.credentials().await {
+            Ok(Some(credentials)) => match Docker.login(&credentials).await {
+                Ok(()) => servers.push(credentials.server),
+                Err(e) => result = Err(e),
+            },
+            Ok(None) => {}
+            Err(e) => result = Err(e),
+        }
+        if result.is_err() {
+            break;
+        }
+    }
+    let ret = match result {
+        Ok(()) => f().await,
+        Err(e) => Err(e),
+    };
+    for server in servers {
+        if let Err(e) = Docker.logout(&server).await {
+            warn!("Failed to log out from {server}: {e}");
+        }
+    }
+    ret
+}
+
+/// Push the local image to all the given registries.
+///
+/// The registries are processed one by one, so the logins to the same server do not interfere.
+pub async fn push_to_all(
+    image: &ImageId,
+    tag: &str,
+    registries: &[Registry],
+) -> Result<Vec<String>> {
+    let mut ret = Vec::new();
+    for registry in registries {
+        ret.push(registry.push(image, tag).await?);
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_service::ContainerService;
+    use crate::test_service::RunningService;
+    use ide_ci::programs::docker::BuildOptions;
+
+    #[test]
+    fn parsing() -> Result {
+        let cases = [
+            ("ecr:runtime", Registry::Ecr { repository: "runtime".into() }),
+            ("acr:enso/runtime", Registry::Acr {
+                registry:   "enso".into(),
+                repository: "runtime".into(),
+            }),
+            ("ghcr:enso-org/runtime", Registry::Ghcr {
+                owner:      "enso-org".into(),
+                repository: "runtime".into(),
+            }),
+            ("localhost:5000/enso/runtime", Registry::Generic {
+                server:     "localhost:5000".into(),
+                repository: "enso/runtime".into(),
+            }),
+        ];
+        for (text, expected) in cases {
+            let parsed = text.parse::<Registry>()?;
+            assert_eq!(parsed, expected);
+            assert_eq!(parsed.to_string(), text);
+        }
+        assert!("ghcr:runtime".parse::<Registry>().is_err());
+        assert!("runtime".parse::<Registry>().is_err());
+        Ok(())
+    }
+
+    /// The reference registry implementation, used as a stand-in for the real registries.
+    fn local_registry(port: u16) -> ContainerService {
+        ContainerService::new("enso-test-registry", "registry:2", 5000, port, "v2/")
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn push_to_local_registries() -> Result {
+        let ports = ide_ci::get_free_ports(2)?;
+        let first = RunningService::start(local_registry(ports[0])).await?;
+        let second = RunningService::start(local_registry(ports[1])).await?;
+        let result = async {
+            let context = tempfile::tempdir()?;
+            ide_ci::fs::write(context.path().join("Dockerfile"), "FROM scratch\nCOPY data /\n")?;
+            ide_ci::fs::write(context.path().join("data"), "data")?;
+            let mut opts = BuildOptions::new(context.path());
+            opts.tags.push("enso-test-image:latest".into());
+            let image = Docker.build(opts).await?;
+
+            let registries = ports
+                .iter()
+                .map(|port| format!("localhost:{port}/enso/test").parse())
+                .collect::<Result<Vec<Registry>>>()?;
+            let pushed = push_to_all(&image, "v1", &registries).await?;
+            assert_eq!(
+                pushed,
+                ports.iter().map(|port| format!("localhost:{port}/enso/test:v1")).collect_vec()
+            );
+            Result::Ok(())
+        }
+        .await;
+        first.stop().await?;
+        second.stop().await?;
+        result
+    }
+}
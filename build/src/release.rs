@@ -1,15 +1,22 @@
 use crate::prelude::*;
 
+use crate::aws::ecr::runtime::runtime_image_options;
+use crate::blob_store::BlobStore;
 use crate::context::BuildContext;
 use crate::paths::generated;
 use crate::paths::TargetTriple;
 use crate::paths::EDITION_FILE_ARTIFACT_NAME;
 use crate::project;
+use crate::registry::Registry;
 use ide_ci::github;
+use ide_ci::programs::docker::ImageId;
+use ide_ci::programs::docker::Platform;
 use ide_ci::programs::Docker;
 use octocrab::models::repos::Release;
 use tempfile::tempdir;
 
+pub mod mirror;
+
 pub async fn create_release(context: &BuildContext) -> Result<Release> {
     let versions = &context.triple.versions;
     let commit = ide_ci::actions::env::GITHUB_SHA.get()?;
@@ -36,7 +43,16 @@ pub async fn create_release(context: &BuildContext) -> Result<Release> {
     Ok(release)
 }
 
-pub async fn publish_release(context: &BuildContext) -> Result {
+/// Publish the draft release and add its edition to the editions store.
+///
+/// The store is described by the URL (see [`crate::blob_store::open`]). If not given, the
+/// production editions bucket is used. If `dry_run` is set, neither the release nor the store is
+/// modified, the changes are only logged.
+pub async fn publish_release(
+    context: &BuildContext,
+    editions_store: Option<&Url>,
+    dry_run: bool,
+) -> Result {
     let BuildContext { inner: project::Context { octocrab, .. }, remote_repo, triple, .. } =
         context;
 
@@ -46,9 +62,13 @@ pub async fn publish_release(context: &BuildContext) -> Result {
     let release = remote_repo.repos(octocrab).releases().get_by_id(release_id).await?;
     ensure!(release.draft, "Release has been already published!");
 
-    debug!("Found the target release, will publish it.");
-    remote_repo.repos(octocrab).releases().update(release.id.0).draft(false).send().await?;
-    debug!("Done. Release URL: {}", release.url);
+    if dry_run {
+        info!("Dry run: would publish the release {}.", release.url);
+    } else {
+        debug!("Found the target release, will publish it.");
+        remote_repo.repos(octocrab).releases().update(release.id.0).draft(false).send().await?;
+        debug!("Done. Release URL: {}", release.url);
+    }
 
     let temp = tempdir()?;
     let edition_file_path = crate::paths::generated::RepoRootDistributionEditions::new_root(
@@ -64,17 +84,44 @@ pub async fn publish_release(context: &BuildContext) -> Result {
     )
     .await?;
 
-    debug!("Updating edition in the AWS S3.");
-    crate::aws::update_manifest(remote_repo, &edition_file_path).await?;
+    let store: Box<dyn BlobStore> = match editions_store {
+        Some(url) => crate::blob_store::open(url).await?,
+        None => Box::new(crate::aws::BucketContext::editions(remote_repo).await),
+    };
+    debug!("Updating edition in {store:?}.");
+    crate::aws::update_manifest(store.as_ref(), &edition_file_path, dry_run).await?;
 
     Ok(())
 }
 
-pub async fn deploy_to_ecr(context: &BuildContext, repository: String) -> Result {
-    let octocrab = &context.octocrab;
-    let release_id = crate::env::ReleaseId.fetch()?;
+/// Copy the assets of the release into the store described by the URL.
+///
+/// The release is looked up by the tag text, or by the [`crate::env::ReleaseId`] if not given.
+pub async fn mirror_release(
+    context: &BuildContext,
+    store: &Url,
+    tag: Option<&str>,
+) -> Result<mirror::Index> {
+    let BuildContext { inner: project::Context { octocrab, .. }, remote_repo, .. } = context;
+    let release = match tag {
+        Some(tag) => remote_repo.find_release_by_text(octocrab, tag).await?,
+        None => remote_repo.find_release_by_id(octocrab, crate::env::ReleaseId.fetch()?).await?,
+    };
+    let store = crate::blob_store::open(store).await?;
+    mirror::mirror_release(remote_repo, octocrab, &release, store.as_ref()).await
+}
 
-    let linux_triple = TargetTriple { os: OS::Linux, ..context.triple.clone() };
+/// Download the Linux engine package of the given architecture from the release.
+///
+/// The package is extracted into the given directory.
+pub async fn download_engine_package(
+    context: &BuildContext,
+    release: &Release,
+    arch: Arch,
+    output_dir: &Path,
+) -> Result<generated::EnginePackage> {
+    let octocrab = &context.octocrab;
+    let linux_triple = TargetTriple { os: OS::Linux, arch, ..context.triple.clone() };
     let package_name =
         generated::RepoRootBuiltDistribution::new_root(".", linux_triple.to_string())
             .enso_engine_triple
@@ -82,38 +129,137 @@ pub async fn deploy_to_ecr(context: &BuildContext, repository: String) -> Result
             .context("Failed to get Engine Package name.")?
             .as_str()
             .to_string();
-
-    let release = context.remote_repo.find_release_by_id(octocrab, release_id).await?;
-    let asset = github::find_asset_by_text(&release, &package_name)?;
-
+    let asset = github::find_asset_by_text(release, &package_name)?;
 
     let temp_for_archive = tempdir()?;
     let downloaded_asset = context
         .remote_repo
-        .download_asset_to(octocrab, &asset, temp_for_archive.path().to_owned())
+        .download_asset_to(octocrab, asset, temp_for_archive.path().to_owned())
         .await?;
 
-    let temp_for_extraction = tempdir()?;
-    ide_ci::archive::extract_to(&downloaded_asset, &temp_for_extraction).await?;
-
-    let engine_package = generated::EnginePackage::new_under(
-        &temp_for_extraction,
-        context.triple.versions.version.to_string(),
-    );
-
+    ide_ci::archive::extract_to(&downloaded_asset, output_dir).await?;
+    Ok(generated::EnginePackage::new_under(output_dir, context.triple.versions.version.to_string()))
+}
 
-    let config = &aws_config::load_from_env().await;
-    let client = aws_sdk_ecr::Client::new(config);
-    let repository_uri = crate::aws::ecr::get_repository_uri(&client, &repository).await?;
-    let tag = format!("{}:{}", repository_uri, context.triple.versions.version);
-    let _image = crate::aws::ecr::runtime::build_runtime_image(
+/// Build the runtime image from the Linux engine package of the release.
+pub async fn build_runtime_image(context: &BuildContext, arch: Arch) -> Result<ImageId> {
+    let release_id = crate::env::ReleaseId.fetch()?;
+    let release = context.remote_repo.find_release_by_id(&context.octocrab, release_id).await?;
+    let temp_for_extraction = tempdir()?;
+    let engine_package =
+        download_engine_package(context, &release, arch, temp_for_extraction.path()).await?;
+    let tag = format!("{}:{}", crate::aws::ecr::runtime::NAME, context.triple.versions.version);
+    crate::aws::ecr::runtime::build_runtime_image(
         context.repo_root.tools.ci.docker.clone(),
         engine_package,
-        tag.clone(),
+        tag,
     )
-    .await?;
+    .await
+}
+
+/// Name of the `buildx` builder used for the multi-platform runtime image builds.
+pub const BUILDX_BUILDER: &str = "enso-runtime-builder";
+
+/// Where and how to publish the runtime image.
+#[derive(Clone, Debug)]
+pub struct DeployImageOptions {
+    pub registries:    Vec<Registry>,
+    /// Architectures of the image. If there are multiple, the variants are published under a
+    /// single tag as a manifest list.
+    pub architectures: Vec<Arch>,
+    /// See [`ide_ci::programs::docker::BuildOptions::cache_from`].
+    pub cache_from:    Vec<String>,
+    /// See [`ide_ci::programs::docker::BuildOptions::cache_to`].
+    pub cache_to:      Vec<String>,
+}
+
+impl DeployImageOptions {
+    pub fn new(registries: Vec<Registry>) -> Self {
+        Self {
+            registries,
+            architectures: vec![Arch::X86_64],
+            cache_from: default(),
+            cache_to: default(),
+        }
+    }
+}
+
+/// Build the runtime image and push it to all the given registries.
+///
+/// A single-architecture image without the cache options is built locally and then pushed.
+/// Otherwise, each architecture variant is built and pushed with `buildx` (under the
+/// `<version>-<arch>` tag) and then combined into the manifest list. The variants are built by the
+/// [`BUILDX_BUILDER`], with the emulators of the foreign architectures registered beforehand.
+///
+/// Returns the full names of the pushed images.
+pub async fn deploy_image(
+    context: &BuildContext,
+    options: &DeployImageOptions,
+) -> Result<Vec<String>> {
+    let DeployImageOptions { registries, architectures, cache_from, cache_to } = options;
+    ensure!(!registries.is_empty(), "No registries to push the image to.");
+    let version = context.triple.versions.version.to_string();
+    if let [arch] = architectures.as_slice() && cache_from.is_empty() && cache_to.is_empty() {
+        let image = build_runtime_image(context, *arch).await?;
+        return crate::registry::push_to_all(&image, &version, registries).await;
+    }
+    ensure!(!architectures.is_empty(), "No architectures to build the image for.");
+
+    let release_id = crate::env::ReleaseId.fetch()?;
+    let release = context.remote_repo.find_release_by_id(&context.octocrab, release_id).await?;
+    let mut repositories = Vec::new();
+    for registry in registries {
+        repositories.push(registry.image_repository().await?);
+    }
+    let platforms = architectures.iter().map(|arch| Platform::linux(*arch)).collect_vec();
+    Docker.install_binfmt(&platforms).await?;
+    Docker.ensure_buildx_builder(BUILDX_BUILDER).await?;
+    crate::registry::while_logged_in_all(registries, || async {
+        for platform in &platforms {
+            let platform = *platform;
+            let temp_for_extraction = tempdir()?;
+            let engine_package = download_engine_package(
+                context,
+                &release,
+                platform.arch,
+                temp_for_extraction.path(),
+            )
+            .await?;
+            let dockerfile = &context.repo_root.tools.ci.docker;
+            let mut opts = runtime_image_options(dockerfile, &engine_package)?;
+            opts.platforms.push(platform);
+            opts.push = true;
+            opts.cache_from = cache_from.clone();
+            opts.cache_to = cache_to.clone();
+            opts.builder = Some(BUILDX_BUILDER.into());
+            let arch_name = platform.arch_name();
+            opts.tags =
+                repositories.iter().map(|repo| format!("{repo}:{version}-{arch_name}")).collect();
+            Docker.buildx_build(&opts).await?;
+        }
+        let mut ret = Vec::new();
+        for repository in &repositories {
+            let target = format!("{repository}:{version}");
+            let sources = architectures
+                .iter()
+                .map(|arch| format!("{target}-{}", Platform::linux(*arch).arch_name()));
+            Docker.create_manifest_list(&target, sources).await?;
+            ret.push(target);
+        }
+        Ok(ret)
+    })
+    .await
+}
 
-    let credentials = crate::aws::ecr::get_credentials(&client).await?;
-    Docker.while_logged_in(credentials, || async move { Docker.push(&tag).await }).await?;
+pub async fn deploy_to_ecr(
+    context: &BuildContext,
+    repository: String,
+    architectures: Vec<Arch>,
+) -> Result {
+    let options = DeployImageOptions {
+        architectures,
+        ..DeployImageOptions::new(vec![Registry::Ecr { repository }])
+    };
+    deploy_image(context, &options).await?;
     Ok(())
 }
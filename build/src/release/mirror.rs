@@ -0,0 +1,178 @@
+//! Mirroring the release assets from GitHub into a [`BlobStore`], so they can be served from our
+//! own CDN.
+//!
+//! Assets of each release are stored under `<version>/<asset name>`, along with the
+//! [`INDEX_FILENAME`] file describing them.
+
+use crate::prelude::*;
+
+use crate::blob_store::Acl;
+use crate::blob_store::BlobStore;
+use crate::blob_store::BlobStoreExt as _;
+use ide_ci::github::RepoPointer;
+use octocrab::models::repos::Release;
+use octocrab::Octocrab;
+use sha2::Digest;
+use tempfile::tempdir;
+
+
+
+/// Name of the file listing the mirrored assets of the release.
+pub const INDEX_FILENAME: &str = "index.json";
+
+/// Description of a single mirrored asset.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub name:   String,
+    /// Size in bytes.
+    pub size:   u64,
+    /// Hex-encoded SHA-256 digest of the contents.
+    pub sha256: String,
+}
+
+/// Contents of the [`INDEX_FILENAME`] file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Index {
+    pub version: String,
+    pub assets:  Vec<IndexEntry>,
+}
+
+impl Index {
+    pub fn get(&self, name: &str) -> Option<&IndexEntry> {
+        self.assets.iter().find(|entry| entry.name == name)
+    }
+
+    /// Add the entry, replacing the previous one with the same name.
+    pub fn insert(&mut self, entry: IndexEntry) {
+        self.assets.retain(|existing| existing.name != entry.name);
+        self.assets.push(entry);
+    }
+}
+
+pub fn sha256(data: &[u8]) -> String {
+    format!("{:x}", sha2::Sha256::digest(data))
+}
+
+/// Hex-encoded SHA-256 digest of the file, read in chunks.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = sha2::Sha256::new();
+    let mut file = ide_ci::fs::open(path)?;
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Upload of the assets of a single release version.
+#[derive(Debug)]
+pub struct Mirror<'a> {
+    pub store:      &'a dyn BlobStore,
+    pub index:      Index,
+    existing_files: BTreeSet<String>,
+}
+
+impl<'a> Mirror<'a> {
+    /// Prepare mirroring the given version, picking up the index left by the previous attempts.
+    pub async fn new(store: &'a dyn BlobStore, version: impl Into<String>) -> Result<Mirror<'a>> {
+        let version = version.into();
+        let existing_files = store.list_prefix(&format!("{version}/")).await?;
+        let index_path = format!("{version}/{INDEX_FILENAME}");
+        let index = if existing_files.contains(&index_path) {
+            store.get_json::<Index>(&index_path).await?
+        } else {
+            Index { version, assets: default() }
+        };
+        Ok(Self { store, index, existing_files })
+    }
+
+    pub fn path(&self, name: &str) -> String {
+        format!("{}/{}", self.index.version, name)
+    }
+
+    /// Store the asset from the local file, unless the identical one is already in the store.
+    ///
+    /// Returns whether the asset was uploaded.
+    pub async fn add_file(&mut self, name: &str, file: &Path) -> Result<bool> {
+        ensure!(name != INDEX_FILENAME, "Asset name `{name}` is reserved for the index.");
+        let path = self.path(name);
+        let entry = IndexEntry {
+            name:   name.into(),
+            size:   ide_ci::fs::metadata(file)?.len(),
+            sha256: sha256_file(file)?,
+        };
+        let is_up_to_date =
+            self.existing_files.contains(&path) && self.index.get(name) == Some(&entry);
+        if is_up_to_date {
+            info!("Skipping {path}, as it is already mirrored.");
+        } else {
+            info!("Uploading {path} ({} bytes).", entry.size);
+            self.store.put_file(&path, file, Acl::PublicRead).await?;
+            self.existing_files.insert(path);
+        }
+        self.index.insert(entry);
+        Ok(!is_up_to_date)
+    }
+
+    /// Write the index of the mirrored assets.
+    pub async fn finish(self) -> Result<Index> {
+        let path = self.path(INDEX_FILENAME);
+        self.store.put_json(&path, &self.index, Acl::PublicRead).await?;
+        Ok(self.index)
+    }
+}
+
+/// Copy all the assets of the GitHub release into the store.
+///
+/// The release's tag is used as the version. Every asset is downloaded, as GitHub does not provide
+/// its checksum, but the ones already mirrored with the same SHA-256 are not uploaded again.
+pub async fn mirror_release(
+    repo: &(impl RepoPointer + Send + Sync + 'static),
+    octocrab: &Octocrab,
+    release: &Release,
+    store: &dyn BlobStore,
+) -> Result<Index> {
+    let mut mirror = Mirror::new(store, &release.tag_name).await?;
+    let temp = tempdir()?;
+    for asset in &release.assets {
+        debug!("Downloading {}.", asset.name);
+        let path = repo.download_asset_to(octocrab, asset, temp.path().to_owned()).await?;
+        mirror.add_file(&asset.name, &path).await?;
+        ide_ci::fs::remove_file_if_exists(&path)?;
+    }
+    mirror.finish().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob_store::LocalDirectory;
+
+    #[tokio::test]
+    async fn mirroring_is_idempotent() -> Result {
+        let temp = tempdir()?;
+        let store = LocalDirectory { root: temp.path().join("store") };
+        let asset = |name: &str, contents: &str| -> Result<PathBuf> {
+            let path = temp.path().join(name);
+            ide_ci::fs::write(&path, contents)?;
+            Ok(path)
+        };
+
+        let mut mirror = Mirror::new(&store, "2022.1.1").await?;
+        assert!(mirror.add_file("engine.tar.gz", &asset("engine.tar.gz", "engine")?).await?);
+        assert!(mirror.add_file("ide.exe", &asset("ide.exe", "ide")?).await?);
+        let index = mirror.finish().await?;
+        assert_eq!(index.get("engine.tar.gz").map(|entry| entry.size), Some(6));
+
+        let mut mirror = Mirror::new(&store, "2022.1.1").await?;
+        assert_eq!(mirror.index, index);
+        assert!(!mirror.add_file("engine.tar.gz", &asset("engine.tar.gz", "engine")?).await?);
+        // Same size, different contents.
+        assert!(mirror.add_file("ide.exe", &asset("ide.exe", "IDE")?).await?);
+        let index = mirror.finish().await?;
+        assert_eq!(index.assets.len(), 2);
+        assert_eq!(index.get("ide.exe").map(|entry| entry.sha256.clone()), Some(sha256(b"IDE")));
+        assert_eq!(store.get("2022.1.1/ide.exe").await?, "IDE");
+
+        let mut mirror = Mirror::new(&store, "2022.1.2").await?;
+        assert!(mirror.add_file("engine.tar.gz", &asset("engine.tar.gz", "engine")?).await?);
+        Ok(())
+    }
+}
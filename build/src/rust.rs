@@ -1,3 +1,4 @@
 use crate::prelude::*;
 
+pub mod lockfile;
 pub mod parser;
@@ -0,0 +1,94 @@
+//! Reading the versions of the dependencies from the `Cargo.lock` file.
+
+use crate::prelude::*;
+
+use std::str::FromStr;
+
+
+
+/// A single package entry of the lockfile.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Package {
+    pub name:    String,
+    pub version: Version,
+    /// E.g. `registry+https://github.com/rust-lang/crates.io-index`. Missing for the workspace
+    /// members.
+    pub source:  Option<String>,
+}
+
+/// The parsed `Cargo.lock`. Only the package list is read.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub package: Vec<Package>,
+}
+
+impl FromStr for Lockfile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        toml::from_str(s).context("Failed to parse the Cargo lockfile.")
+    }
+}
+
+impl Lockfile {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        ide_ci::fs::read_to_string(&path)?
+            .parse2()
+            .with_context(|| format!("Failed to read {}.", path.as_ref().display()))
+    }
+
+    /// The version the package is locked at.
+    ///
+    /// Fails if the package is not present or is locked at multiple versions, as then it is
+    /// ambiguous which one is meant.
+    pub fn version_of(&self, name: &str) -> Result<&Version> {
+        let versions: BTreeSet<_> =
+            self.package.iter().filter(|p| p.name == name).map(|p| &p.version).collect();
+        match versions.len() {
+            0 => bail!("The package `{name}` is not present in the lockfile."),
+            1 => Ok(versions.into_iter().next().unwrap()),
+            _ => bail!(
+                "The package `{name}` is locked at multiple versions: {}.",
+                versions.iter().join(", ")
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locked_versions() -> Result {
+        let lockfile: Lockfile = r#"
+version = 3
+
+[[package]]
+name = "enso-gui"
+version = "0.1.0"
+dependencies = ["wasm-bindgen"]
+
+[[package]]
+name = "wasm-bindgen"
+version = "0.2.83"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "syn"
+version = "1.0.103"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "syn"
+version = "0.15.44"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#
+        .parse2()?;
+        assert_eq!(lockfile.version_of("wasm-bindgen")?, &Version::new(0, 2, 83));
+        assert!(lockfile.version_of("syn").is_err());
+        assert!(lockfile.version_of("serde").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+//! Services needed by the Standard Library tests, described as a Docker Compose project.
+//!
+//! This allows running the services once and then running the tests repeatedly, e.g. from an IDE,
+//! rather than spawning them for each `backend test` invocation (see
+//! [`crate::enso::BuiltEnso::shared_test_services`]).
+
+use crate::prelude::*;
+
+use crate::postgres;
+use crate::test_service::Environment;
+
+use ide_ci::env::Variable;
+use ide_ci::models::compose::Compose;
+use ide_ci::models::compose::Healthcheck;
+use ide_ci::models::compose::Service;
+use ide_ci::models::compose::Volume;
+use ide_ci::programs::DockerCompose;
+
+
+
+/// Name of the Compose project.
+pub const PROJECT: &str = "enso-test-env";
+
+/// Name of the generated compose file, placed in the `target` directory.
+pub const COMPOSE_FILE: &str = "test-env.docker-compose.yml";
+
+/// Image used to build and run httpbin, see [`crate::httpbin`].
+pub const GO_IMAGE: &str = "golang:1.19";
+
+/// Port on which httpbin listens inside its container.
+pub const HTTPBIN_CONTAINER_PORT: u16 = 8080;
+
+/// Host ports of the services.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TestEnvironment {
+    pub postgres_port:    u16,
+    pub httpbin_port:     u16,
+    /// Version (tag) of the official Postgres image.
+    pub postgres_version: &'static str,
+}
+
+impl Default for TestEnvironment {
+    fn default() -> Self {
+        Self {
+            postgres_port:    postgres::POSTGRES_CONTAINER_DEFAULT_PORT,
+            httpbin_port:     HTTPBIN_CONTAINER_PORT,
+            postgres_version: "latest",
+        }
+    }
+}
+
+impl TestEnvironment {
+    pub fn postgres(&self) -> Service {
+        use postgres::env::container::*;
+        let mut service = Service::image(format!("postgres:{}", self.postgres_version));
+        service
+            .publish_port(self.postgres_port, postgres::POSTGRES_CONTAINER_DEFAULT_PORT)
+            .env(POSTGRES_DB.name(), postgres::TEST_DATABASE_NAME)
+            .env(POSTGRES_USER.name(), postgres::TEST_USER)
+            .env(POSTGRES_PASSWORD.name(), postgres::TEST_PASSWORD);
+        service.healthcheck = Some(Healthcheck::cmd(
+            [
+                "pg_isready",
+                "--host=127.0.0.1",
+                &format!("--username={}", postgres::TEST_USER),
+                &format!("--dbname={}", postgres::TEST_DATABASE_NAME),
+            ],
+            "1s",
+        ));
+        service
+    }
+
+    /// The httpbin server, built from sources in the Go container.
+    ///
+    /// The Go module and build caches are kept in volumes, so only the first start compiles.
+    pub fn httpbin(&self) -> Service {
+        let mut service = Service::image(GO_IMAGE);
+        service.command = Some(format!(
+            "go run {}@{} -host :{HTTPBIN_CONTAINER_PORT}",
+            crate::httpbin::PACKAGE,
+            crate::httpbin::VERSION
+        ));
+        service.publish_port(self.httpbin_port, HTTPBIN_CONTAINER_PORT);
+        service.volumes.push("go-modules:/go/pkg/mod".into());
+        service.volumes.push("go-build-cache:/root/.cache/go-build".into());
+        let url = format!("http://localhost:{HTTPBIN_CONTAINER_PORT}/get");
+        let mut healthcheck = Healthcheck::cmd(["curl", "--fail", "--silent", &url], "1s");
+        // Give time for the compilation.
+        healthcheck.start_period = Some("5m".into());
+        service.healthcheck = Some(healthcheck);
+        service
+    }
+
+    pub fn compose(&self) -> Compose {
+        let mut compose = Compose::default();
+        compose.services.insert("postgres".into(), self.postgres());
+        compose.services.insert("httpbin".into(), self.httpbin());
+        for volume in ["go-modules", "go-build-cache"] {
+            compose.volumes.insert(volume.into(), Volume::default());
+        }
+        compose
+    }
+
+    /// Environment variables pointing the tests to the services.
+    pub fn env(&self) -> Environment {
+        let host = format!("localhost:{}", self.postgres_port);
+        let mut ret = postgres::enso_test_env(
+            &host,
+            postgres::TEST_DATABASE_NAME,
+            postgres::TEST_USER,
+            postgres::TEST_PASSWORD,
+        );
+        let httpbin_url = format!("http://localhost:{}", self.httpbin_port);
+        ret.push((crate::httpbin::env::Url::NAME.into(), httpbin_url));
+        ret
+    }
+
+    /// Write the compose file into the given directory and return the project handle.
+    pub fn project(&self, target_dir: impl AsRef<Path>) -> Result<DockerCompose> {
+        let file = target_dir.as_ref().join(COMPOSE_FILE);
+        self.compose().write(&file)?;
+        Ok(DockerCompose::new(file, PROJECT))
+    }
+
+    /// Start the services and wait until they are healthy.
+    pub async fn up(&self, target_dir: impl AsRef<Path>) -> Result<Environment> {
+        let project = self.project(target_dir)?;
+        project.up(true).await?;
+        Ok(self.env())
+    }
+}
+
+/// Stop the services started by [`TestEnvironment::up`], removing their data.
+pub async fn down(target_dir: impl AsRef<Path>) -> Result {
+    let file = target_dir.as_ref().join(COMPOSE_FILE);
+    if !file.exists() {
+        info!("No test environment has been started, as {} does not exist.", file.display());
+        return Ok(());
+    }
+    DockerCompose::new(file, PROJECT).down(true).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_compose_file() -> Result {
+        let env = TestEnvironment { postgres_port: 15432, ..default() };
+        let compose = env.compose();
+        assert_eq!(compose.services["postgres"].ports, vec!["15432:5432".to_string()]);
+        assert!(compose.services["httpbin"].command.as_ref().unwrap().contains("go-httpbin"));
+        let yaml = compose.to_yaml()?;
+        assert_eq!(serde_yaml::from_str::<Compose>(&yaml)?, compose);
+
+        let vars = env.env();
+        assert!(vars.contains(&("ENSO_DATABASE_TEST_HOST".into(), "localhost:15432".into())));
+        assert!(
+            vars.contains(&("ENSO_HTTP_TEST_HTTPBIN_URL".into(), "http://localhost:8080".into()))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,527 @@
+//! Services (like databases or mock HTTP servers) that need to be running for the tests.
+//!
+//! Each service implements [`TestService`], describing how to spawn it, how to tell that it is
+//! ready and how the tests can reach it. [`RunningService`] takes care of the rest: spawning,
+//! waiting for readiness and the teardown.
+
+use crate::prelude::*;
+
+use ide_ci::programs::docker::ContainerId;
+use ide_ci::programs::docker::HealthStatus;
+use ide_ci::programs::docker::ImageId;
+use ide_ci::programs::docker::RunOptions;
+use ide_ci::programs::Docker;
+use regex::Regex;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncRead;
+use tokio::io::BufReader;
+use tokio::net::TcpStream;
+use tokio::process::Child;
+
+
+
+/// Interval between the consecutive readiness checks.
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How to tell that a spawned service is ready to be used.
+#[derive(Clone, Debug)]
+pub enum Readiness {
+    /// A TCP connection to the local port can be established.
+    TcpConnect { port: u16 },
+    /// A GET request to the URL succeeds.
+    HttpGet { url: Url },
+    /// The service process printed a line matching the pattern to its standard output or error.
+    LogLine { pattern: Regex },
+    /// The Docker container reports being healthy. The container must have a health check.
+    ContainerHealthy { container: ContainerId },
+    /// The file (e.g. a Unix socket) exists.
+    PathExists { path: PathBuf },
+}
+
+/// Environment variables to be set for the tests, so they can reach the service.
+pub type Environment = Vec<(String, String)>;
+
+/// A service that the tests need to be running.
+#[async_trait]
+pub trait TestService: Debug + Send + Sync + 'static {
+    /// Name used in the logs.
+    fn name(&self) -> String;
+
+    /// Command running the service in the foreground. The process is killed on teardown.
+    fn command(&self) -> Result<Command>;
+
+    fn readiness(&self) -> Readiness;
+
+    /// How long to wait for the service to become ready.
+    fn startup_timeout(&self) -> Duration {
+        Duration::from_secs(120)
+    }
+
+    /// Environment variables shared by all the test suites using this service.
+    fn env(&self) -> Environment;
+
+    /// Prepare whatever the service needs before being spawned, e.g. its data directory.
+    async fn setup(&self) -> Result {
+        Ok(())
+    }
+
+    /// Prepare the service for the test suite with the given name and return its environment.
+    ///
+    /// Services that can isolate the suites (e.g. by giving each its own database) should
+    /// override this.
+    async fn prepare_suite(&self, _suite: &str) -> Result<Environment> {
+        Ok(self.env())
+    }
+
+    /// Release the resources that outlive the service process, e.g. a Docker container.
+    ///
+    /// Also called before the start, to clean up after a run that crashed.
+    async fn cleanup(&self) -> Result {
+        Ok(())
+    }
+}
+
+/// A service run from a Docker image, reachable over HTTP on a published port.
+///
+/// The container is named after the service and the host port, so the services on different
+/// ports do not clash, and a container left by a crashed run is removed before the start.
+#[derive(Clone, Debug)]
+pub struct ContainerService {
+    /// The container is named `{name}-{port}`.
+    pub name:           String,
+    /// Options of the run. The container name and the published port are filled in on spawning.
+    pub run_options:    RunOptions,
+    /// Port in the container on which the service listens.
+    pub container_port: u16,
+    /// Port on the host to which the [`container_port`](Self::container_port) is published.
+    pub port:           u16,
+    /// Path of the HTTP endpoint that responds successfully once the service is ready.
+    pub readiness_path: String,
+}
+
+impl ContainerService {
+    pub fn new(
+        name: impl Into<String>,
+        image: impl Into<String>,
+        container_port: u16,
+        port: u16,
+        readiness_path: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            run_options: RunOptions::new(ImageId(image.into())),
+            container_port,
+            port,
+            readiness_path: readiness_path.into(),
+        }
+    }
+
+    pub fn container(&self) -> ContainerId {
+        ContainerId(format!("{}-{}", self.name, self.port))
+    }
+
+    /// URL of the service, as seen from the host.
+    pub fn endpoint(&self) -> String {
+        format!("http://localhost:{}", self.port)
+    }
+}
+
+#[async_trait]
+impl TestService for ContainerService {
+    fn name(&self) -> String {
+        self.container().0
+    }
+
+    fn command(&self) -> Result<Command> {
+        let mut opts = self.run_options.clone();
+        opts.publish_port(self.port, self.container_port);
+        opts.name = Some(self.container().0);
+        Docker.run_cmd(&opts)
+    }
+
+    fn readiness(&self) -> Readiness {
+        let url = format!("{}/{}", self.endpoint(), self.readiness_path.trim_start_matches('/'));
+        Readiness::HttpGet { url: url.parse2().unwrap() }
+    }
+
+    fn env(&self) -> Environment {
+        default()
+    }
+
+    async fn cleanup(&self) -> Result {
+        Docker.remove_container(&self.container(), true).await
+    }
+}
+
+/// A started service.
+///
+/// Should be stopped with [`RunningService::stop`]. If it is just dropped, the process is killed
+/// but [`TestService::cleanup`] does not run.
+#[derive(Debug)]
+pub struct RunningService {
+    pub service: Box<dyn TestService>,
+    process:     Child,
+}
+
+impl RunningService {
+    /// Spawn the service and wait until it is ready.
+    pub async fn start(service: impl TestService) -> Result<Self> {
+        let name = service.name();
+        if let Err(e) = service.cleanup().await {
+            trace!("Cleanup before starting {name} failed: {e}");
+        }
+        service.setup().await?;
+
+        let mut command = service.command()?;
+        command.kill_on_drop(true);
+        let readiness = service.readiness();
+        let (process, log_lines) = match &readiness {
+            Readiness::LogLine { .. } => {
+                command.stdout(Stdio::piped()).stderr(Stdio::piped());
+                let mut process = command.spawn()?;
+                let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+                let stdout = process.stdout.take().context("Missing standard output.")?;
+                let stderr = process.stderr.take().context("Missing standard error.")?;
+                spawn_line_forwarder(format!("{name}ℹ️"), stdout, sender.clone());
+                spawn_line_forwarder(format!("{name}⚠️"), stderr, sender);
+                (process, Some(receiver))
+            }
+            _ => (command.spawn_intercepting()?, None),
+        };
+        let mut ret = Self { service: Box::new(service), process };
+
+        let timeout = ret.service.startup_timeout();
+        let result = tokio::select! {
+            result = tokio::time::timeout(timeout, wait_until_ready(readiness, log_lines)) => result
+                .with_context(|| format!("{name} did not become ready within {timeout:?}."))
+                .flatten(),
+            status = ret.process.wait() => Err(anyhow!("{name} exited before becoming ready: {status:?}")),
+        };
+        match result {
+            Ok(()) => {
+                info!("{name} is ready.");
+                Ok(ret)
+            }
+            Err(e) => {
+                if let Err(stop_error) = ret.stop().await {
+                    debug!("Failed to stop {name}: {stop_error}");
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Set the variables allowing the command (running the given test suite) to use the service.
+    pub async fn apply_env(&self, command: &mut Command, suite: &str) -> Result {
+        for (name, value) in self.service.prepare_suite(suite).await? {
+            command.env(name, value);
+        }
+        Ok(())
+    }
+
+    /// Kill the service and clean up after it.
+    pub async fn stop(mut self) -> Result {
+        let name = self.service.name();
+        debug!("Stopping {name}.");
+        if let Err(e) = self.process.kill().await {
+            // The process might have already finished on its own.
+            debug!("Failed to kill {name}: {e}");
+        }
+        self.service.cleanup().await
+    }
+}
+
+/// A group of running services, stopped together.
+#[derive(Debug, Default)]
+pub struct Services {
+    pub services: Vec<RunningService>,
+}
+
+impl Services {
+    /// Start all the services concurrently.
+    ///
+    /// If any of them fails to start, the ones already started are stopped.
+    pub async fn start(services: Vec<Box<dyn TestService>>) -> Result<Self> {
+        let started = futures::future::join_all(services.into_iter().map(RunningService::start));
+        let mut ret = Self::default();
+        let mut first_error = None;
+        for result in started.await {
+            match result {
+                Ok(service) => ret.services.push(service),
+                Err(e) => first_error = first_error.or(Some(e)),
+            }
+        }
+        if let Some(error) = first_error {
+            return Err(ret.stop_after(error).await);
+        }
+        Ok(ret)
+    }
+
+    pub async fn apply_env(&self, command: &mut Command, suite: &str) -> Result {
+        for service in &self.services {
+            service.apply_env(command, suite).await?;
+        }
+        Ok(())
+    }
+
+    /// Stop all the services, even if some of them fail to stop.
+    pub async fn stop(self) -> Result {
+        let results = futures::future::join_all(self.services.into_iter().map(|s| s.stop())).await;
+        results.into_iter().collect::<Result<Vec<_>>>()?;
+        Ok(())
+    }
+
+    /// Stop all the services after `error` interrupted their use.
+    ///
+    /// The returned error is `error`, with the failure to stop the services (if any) attached.
+    pub async fn stop_after(self, error: anyhow::Error) -> anyhow::Error {
+        match self.stop().await {
+            Ok(()) => error,
+            Err(stop_error) =>
+                error.context(format!("Failed to stop the started services: {stop_error:?}")),
+        }
+    }
+}
+
+#[async_trait]
+impl TestService for Box<dyn TestService> {
+    fn name(&self) -> String {
+        self.as_ref().name()
+    }
+
+    fn command(&self) -> Result<Command> {
+        self.as_ref().command()
+    }
+
+    fn readiness(&self) -> Readiness {
+        self.as_ref().readiness()
+    }
+
+    fn startup_timeout(&self) -> Duration {
+        self.as_ref().startup_timeout()
+    }
+
+    fn env(&self) -> Environment {
+        self.as_ref().env()
+    }
+
+    async fn setup(&self) -> Result {
+        self.as_ref().setup().await
+    }
+
+    async fn prepare_suite(&self, suite: &str) -> Result<Environment> {
+        self.as_ref().prepare_suite(suite).await
+    }
+
+    async fn cleanup(&self) -> Result {
+        self.as_ref().cleanup().await
+    }
+}
+
+async fn wait_until_ready(
+    readiness: Readiness,
+    log_lines: Option<tokio::sync::mpsc::UnboundedReceiver<String>>,
+) -> Result {
+    match readiness {
+        Readiness::TcpConnect { port } =>
+            while let Err(e) = TcpStream::connect(("localhost", port)).await {
+                trace!("Port {port} not ready yet: {e}");
+                tokio::time::sleep(READINESS_POLL_INTERVAL).await;
+            },
+        Readiness::HttpGet { url } => loop {
+            match reqwest::get(url.clone()).await.and_then(|r| r.error_for_status()) {
+                Ok(_) => break,
+                Err(e) => trace!("{url} not ready yet: {e}"),
+            }
+            tokio::time::sleep(READINESS_POLL_INTERVAL).await;
+        },
+        Readiness::ContainerHealthy { container } => loop {
+            match Docker.health_status(&container).await {
+                Ok(HealthStatus::Healthy) => break,
+                Ok(HealthStatus::Unhealthy) => bail!("Container {container} is unhealthy."),
+                Ok(HealthStatus::None) => bail!("Container {container} has no health check."),
+                Ok(HealthStatus::Starting) => trace!("Container {container} is starting."),
+                // The container might not have been created yet.
+                Err(e) => trace!("Container {container} not ready yet: {e}"),
+            }
+            tokio::time::sleep(READINESS_POLL_INTERVAL).await;
+        },
+        Readiness::PathExists { path } =>
+            while !path.exists() {
+                trace!("{} does not exist yet.", path.display());
+                tokio::time::sleep(READINESS_POLL_INTERVAL).await;
+            },
+        Readiness::LogLine { pattern } => {
+            let mut log_lines = log_lines.context("Service output is not being captured.")?;
+            loop {
+                let line = log_lines.recv().await.context("Service closed its output.")?;
+                if pattern.is_match(&line) {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Log the lines of the service output and send them to the readiness check.
+///
+/// The output is drained for the whole service lifetime, even after the readiness check is done,
+/// so the service never blocks on a full pipe.
+fn spawn_line_forwarder(
+    prefix: String,
+    reader: impl AsyncRead + Send + Unpin + 'static,
+    sender: tokio::sync::mpsc::UnboundedSender<String>,
+) {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            debug!("{prefix} {line}");
+            // The receiver is gone once the service is ready, that's fine.
+            let _ = sender.send(line);
+        }
+    });
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use tempfile::TempDir;
+
+    /// A shell script run as a service, recording its process ID and the cleanups.
+    ///
+    /// The scripts `exec` their long-running part, so killing the shell leaves nothing behind.
+    #[derive(Debug)]
+    struct Script {
+        script:    String,
+        readiness: Readiness,
+        timeout:   Duration,
+        pid_file:  PathBuf,
+        cleanups:  Arc<AtomicUsize>,
+    }
+
+    impl Script {
+        fn new(temp: &TempDir, name: &str, script: &str, readiness: Readiness) -> Self {
+            let pid_file = temp.path().join(format!("{name}.pid"));
+            Self {
+                script: format!("echo $$ > {}; {script}", pid_file.display()),
+                readiness,
+                timeout: Duration::from_secs(10),
+                pid_file,
+                cleanups: default(),
+            }
+        }
+
+        /// Whether the process is alive. A killed process that was not reaped yet is not.
+        fn is_running(pid: &str) -> bool {
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).unwrap_or_default();
+            !stat.is_empty() && !stat.contains(") Z ")
+        }
+    }
+
+    #[async_trait]
+    impl TestService for Script {
+        fn name(&self) -> String {
+            "script".into()
+        }
+
+        fn command(&self) -> Result<Command> {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(&self.script);
+            Ok(command)
+        }
+
+        fn readiness(&self) -> Readiness {
+            self.readiness.clone()
+        }
+
+        fn startup_timeout(&self) -> Duration {
+            self.timeout
+        }
+
+        fn env(&self) -> Environment {
+            default()
+        }
+
+        async fn cleanup(&self) -> Result {
+            self.cleanups.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn tcp_connect() -> Result {
+        let temp = tempfile::tempdir()?;
+        let listener = tokio::net::TcpListener::bind(("localhost", 0)).await?;
+        let port = listener.local_addr()?.port();
+        let service = Script::new(&temp, "tcp", "exec sleep 60", Readiness::TcpConnect { port });
+        let cleanups = service.cleanups.clone();
+        let running = RunningService::start(service).await?;
+        running.stop().await?;
+        // Once before the start and once on the stop.
+        assert_eq!(cleanups.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log_line() -> Result {
+        let temp = tempfile::tempdir()?;
+        let readiness = Readiness::LogLine { pattern: Regex::new("^ready$")? };
+        let service =
+            Script::new(&temp, "log", "echo starting; echo ready; exec sleep 60", readiness);
+        let running = RunningService::start(service).await?;
+        running.stop().await
+    }
+
+    #[tokio::test]
+    async fn path_exists() -> Result {
+        let temp = tempfile::tempdir()?;
+        let path = temp.path().join("socket");
+        let script = format!("sleep 0.5; touch {}; exec sleep 60", path.display());
+        let service = Script::new(&temp, "path", &script, Readiness::PathExists { path });
+        let running = RunningService::start(service).await?;
+        running.stop().await
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn startup_timeout_kills_process() -> Result {
+        let temp = tempfile::tempdir()?;
+        let path = temp.path().join("never");
+        let mut service =
+            Script::new(&temp, "timeout", "exec sleep 60", Readiness::PathExists { path });
+        service.timeout = Duration::from_secs(1);
+        let pid_file = service.pid_file.clone();
+        let error = RunningService::start(service).await.unwrap_err();
+        assert!(error.to_string().contains("did not become ready"), "{error:?}");
+        let pid = ide_ci::fs::read_to_string(pid_file)?;
+        assert!(!Script::is_running(pid.trim()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn failed_start_stops_started_services() -> Result {
+        let temp = tempfile::tempdir()?;
+        let readiness = Readiness::LogLine { pattern: Regex::new("^ready$")? };
+        let healthy = Script::new(&temp, "healthy", "echo ready; exec sleep 60", readiness);
+        let never = temp.path().join("never");
+        // Give the healthy service time to become ready before this one fails.
+        let failing =
+            Script::new(&temp, "failing", "sleep 1; exit 1", Readiness::PathExists { path: never });
+        let healthy_cleanups = healthy.cleanups.clone();
+        let healthy_pid_file = healthy.pid_file.clone();
+        let services: Vec<Box<dyn TestService>> = vec![Box::new(healthy), Box::new(failing)];
+        let error = Services::start(services).await.unwrap_err();
+        assert!(error.to_string().contains("exited before becoming ready"), "{error:?}");
+        assert_eq!(healthy_cleanups.load(Ordering::SeqCst), 2);
+        let pid = ide_ci::fs::read_to_string(healthy_pid_file)?;
+        assert!(!Script::is_running(pid.trim()));
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ pub mod artifact;
 pub mod asset;
 pub mod download;
 pub mod goodie;
+pub mod pinned;
 
 use crate::prelude::*;
 use anyhow::Context;
@@ -108,7 +109,9 @@ pub fn digest<S: Storable>(storable: &S) -> Result<String> {
 
 #[derive(Clone, Debug)]
 pub struct Cache {
-    root: PathBuf,
+    root:    PathBuf,
+    /// If set, the entries missing from the cache are not generated, see [`Cache::with_offline`].
+    offline: bool,
 }
 
 impl Cache {
@@ -125,7 +128,17 @@ impl Cache {
         let root = path.into();
         crate::fs::tokio::create_dir_if_missing(&root).await?;
         debug!("Prepared cache in {}", root.display());
-        Ok(Self { root })
+        Ok(Self { root, offline: false })
+    }
+
+    /// Fail on the cache miss instead of generating the entry (which typically means downloading
+    /// it).
+    pub fn with_offline(self, offline: bool) -> Self {
+        Self { offline, ..self }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
     }
 
     pub fn get<S>(&self, storable: S) -> BoxFuture<'static, Result<S::Output>>
@@ -148,6 +161,12 @@ impl Cache {
                     trace!("Found in cache, skipping generation.");
                     return Ok(out);
                 }
+                Err(e) if this.offline => {
+                    bail!(
+                        "{:?} is not in the cache ({e}) and cannot be generated in the offline mode.",
+                        storable.key()
+                    );
+                }
                 Err(e) => {
                     trace!("Value cannot be retrieved from cache because: {e}");
                     crate::fs::reset_dir(&entry_dir)?;
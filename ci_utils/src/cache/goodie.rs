@@ -4,8 +4,11 @@ use crate::cache;
 use crate::cache::Cache;
 
 pub mod binaryen;
+pub mod chrome;
 pub mod graalvm;
+pub mod postgresql;
 pub mod sbt;
+pub mod wasm_bindgen;
 
 /// Something that can be downloaded and, after that, enabled by modifying global state.
 pub trait Goodie: Debug + Clone + Send + Sync + 'static {
@@ -0,0 +1,91 @@
+//! Chrome for Testing, i.e. the Chrome builds meant for automation, released together with the
+//! matching chromedriver.
+//!
+//! See: <https://github.com/GoogleChromeLabs/chrome-for-testing>
+
+use crate::prelude::*;
+
+use crate::cache;
+use crate::extensions::os::OsExt;
+
+
+
+/// The downloadable part of a Chrome for Testing release.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum Component {
+    #[display(fmt = "chrome")]
+    Chrome,
+    #[display(fmt = "chromedriver")]
+    Chromedriver,
+}
+
+#[derive(Clone, Debug, Display)]
+#[display(fmt = "{component} {version}")]
+pub struct ChromeForTesting {
+    /// Full version, like `121.0.6167.85`.
+    pub version:   String,
+    pub component: Component,
+}
+
+impl ChromeForTesting {
+    /// Name of the platform used in the release packages.
+    pub fn platform() -> Result<&'static str> {
+        Ok(match (TARGET_OS, TARGET_ARCH) {
+            (OS::Windows, Arch::X86_64) => "win64",
+            (OS::Linux, Arch::X86_64) => "linux64",
+            (OS::MacOS, Arch::X86_64) => "mac-x64",
+            (OS::MacOS, Arch::AArch64) => "mac-arm64",
+            (os, arch) => bail!("Not supported arch/OS combination: {arch}-{os}."),
+        })
+    }
+
+    /// Name of the package, which is also the name of the directory it unpacks to.
+    pub fn package_name(&self) -> Result<String> {
+        Ok(format!("{}-{}", self.component, Self::platform()?))
+    }
+
+    /// Path to the executable in the unpacked package.
+    pub fn executable(&self, package_path: impl AsRef<Path>) -> Result<PathBuf> {
+        let root = package_path.as_ref().join(self.package_name()?);
+        Ok(match (self.component, TARGET_OS) {
+            (Component::Chrome, OS::MacOS) => root
+                .join("Google Chrome for Testing.app")
+                .join("Contents")
+                .join("MacOS")
+                .join("Google Chrome for Testing"),
+            (component, os) => root.join(format!("{component}{}", os.exe_suffix())),
+        })
+    }
+
+    /// Download the package (unless cached) and return the path to its executable.
+    ///
+    /// Nothing is activated, as the executables are passed explicitly to the tools that need them.
+    pub async fn get(&self, cache: &cache::Cache) -> Result<PathBuf> {
+        let package = self.download(cache).await?;
+        let executable = self.executable(package)?;
+        crate::fs::expect_file(&executable)?;
+        Ok(executable)
+    }
+}
+
+impl cache::Goodie for ChromeForTesting {
+    fn url(&self) -> BoxFuture<'static, Result<Url>> {
+        let version = self.version.clone();
+        let package_name = self.package_name();
+        async move {
+            let package_name = package_name?;
+            let platform = Self::platform()?;
+            let url = format!("https://storage.googleapis.com/chrome-for-testing-public/{version}/{platform}/{package_name}.zip");
+            url.parse2()
+        }
+        .boxed()
+    }
+
+    fn is_active(&self) -> BoxFuture<'static, Result<bool>> {
+        ready(Ok(false)).boxed()
+    }
+
+    fn activate(&self, _package_path: PathBuf) -> Result {
+        Ok(())
+    }
+}
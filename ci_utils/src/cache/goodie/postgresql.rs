@@ -0,0 +1,44 @@
+use crate::prelude::*;
+
+use crate::cache;
+use crate::env::prepend_to_path;
+use crate::programs::postgres::InitDb;
+
+
+
+/// Portable PostgreSQL binaries, as distributed by EnterpriseDB.
+///
+/// Only Windows and macOS builds are available. On Linux the distribution packages should be used.
+#[derive(Clone, Debug, Display)]
+pub struct Postgresql {
+    /// Full version of the package, e.g. `14.5-1`.
+    pub version: String,
+}
+
+impl cache::Goodie for Postgresql {
+    fn url(&self) -> BoxFuture<'static, Result<Url>> {
+        let version = self.version.clone();
+        async move {
+            let target = match (TARGET_OS, TARGET_ARCH) {
+                (OS::Windows, Arch::X86_64) => "windows-x64",
+                (OS::MacOS, _) => "osx",
+                (os, arch) => bail!("No portable PostgreSQL build for {arch}-{os}."),
+            };
+            let url = format!(
+                "https://get.enterprisedb.com/postgresql/postgresql-{version}-{target}-binaries.zip"
+            );
+            url.parse2()
+        }
+        .boxed()
+    }
+
+    fn is_active(&self) -> BoxFuture<'static, Result<bool>> {
+        ready(Ok(InitDb.lookup().is_ok())).boxed()
+    }
+
+    fn activate(&self, package_path: PathBuf) -> Result {
+        let bin_dir = package_path.join("pgsql").join("bin");
+        crate::fs::expect_dir(&bin_dir)?;
+        prepend_to_path(bin_dir)
+    }
+}
@@ -0,0 +1,89 @@
+use crate::prelude::*;
+
+use crate::cache;
+use crate::env::prepend_to_path;
+use crate::program::version::IsVersionPredicate;
+use crate::programs::WasmBindgen;
+
+
+
+/// The prebuilt `wasm-bindgen` CLI (together with `wasm-bindgen-test-runner`) from the GitHub
+/// releases.
+///
+/// The CLI must match the version of the `wasm-bindgen` crate exactly, as the generated module
+/// embeds the crate's schema version, so usually it should be read from the lockfile.
+#[derive(Clone, Debug, Display)]
+pub struct WasmBindgenCli {
+    pub version: Version,
+}
+
+impl IsVersionPredicate for WasmBindgenCli {
+    type Version = Version;
+    fn matches(&self, version: &Self::Version) -> bool {
+        version == &self.version
+    }
+}
+
+impl WasmBindgenCli {
+    /// The target triple of the release package for the current platform.
+    pub fn target() -> Result<&'static str> {
+        Ok(match (TARGET_OS, TARGET_ARCH) {
+            (OS::Windows, Arch::X86_64) => "x86_64-pc-windows-msvc",
+            (OS::Linux, Arch::X86_64) => "x86_64-unknown-linux-musl",
+            // There are no native Apple Silicon packages, the x86_64 one runs under Rosetta.
+            (OS::MacOS, Arch::X86_64 | Arch::AArch64) => "x86_64-apple-darwin",
+            (os, arch) => bail!("Not supported arch/OS combination: {arch}-{os}."),
+        })
+    }
+
+    pub fn package_name(&self) -> Result<String> {
+        Ok(format!("wasm-bindgen-{}-{}", self.version, Self::target()?))
+    }
+}
+
+impl cache::Goodie for WasmBindgenCli {
+    fn url(&self) -> BoxFuture<'static, Result<Url>> {
+        let version = self.version.clone();
+        let package_name = self.package_name();
+        async move {
+            let package_name = package_name?;
+            let url = format!("https://github.com/rustwasm/wasm-bindgen/releases/download/{version}/{package_name}.tar.gz");
+            url.parse2()
+        }
+        .boxed()
+    }
+
+    fn is_active(&self) -> BoxFuture<'static, Result<bool>> {
+        let this = self.clone();
+        async move {
+            WasmBindgen.require_present_that(this).await?;
+            Ok(true)
+        }
+        .boxed()
+    }
+
+    fn activate(&self, package_path: PathBuf) -> Result {
+        let bin_dir = package_path.join(self.package_name()?);
+        crate::fs::expect_dir(&bin_dir)?;
+        prepend_to_path(bin_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache;
+    use crate::cache::Goodie;
+    use crate::log::setup_logging;
+
+    #[tokio::test]
+    #[ignore]
+    async fn install_wasm_bindgen() -> Result {
+        setup_logging()?;
+        let cache = cache::Cache::new_default().await?;
+        let wasm_bindgen = WasmBindgenCli { version: Version::new(0, 2, 83) };
+        wasm_bindgen.install_if_missing(&cache).await?;
+        assert!(wasm_bindgen.is_active().await?);
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! Downloads of the files whose contents are known in advance.
+//!
+//! Unlike [`DownloadFile`], the entry is keyed by the version of the file and its expected
+//! SHA-256 digest, and the downloaded file is verified against the digest before it is stored.
+
+use crate::prelude::*;
+
+use crate::cache::download;
+use crate::cache::download::DownloadFile;
+use crate::cache::Cache;
+use crate::cache::Storable;
+
+use reqwest::IntoUrl;
+use sha2::Digest;
+
+
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Key {
+    /// Version of the file, like a release name or a commit hash. The URL typically contains it
+    /// as well, it is stored separately for diagnostics.
+    pub version: String,
+    pub url:     Url,
+    /// Hex-encoded SHA-256 digest of the file.
+    pub sha256:  String,
+}
+
+#[derive(Clone, Debug)]
+pub struct PinnedDownload {
+    pub key: Key,
+}
+
+impl PinnedDownload {
+    pub fn new(
+        version: impl Into<String>,
+        url: impl IntoUrl,
+        sha256: impl Into<String>,
+    ) -> Result<Self> {
+        let sha256 = sha256.into().to_lowercase();
+        Ok(Self { key: Key { version: version.into(), url: url.into_url()?, sha256 } })
+    }
+}
+
+/// Hex-encoded SHA-256 digest of the file.
+pub async fn sha256_of(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let data = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}.", path.display()))?;
+    Ok(format!("{:x}", sha2::Sha256::digest(&data)))
+}
+
+/// Fail if the file does not have the expected SHA-256 digest.
+pub async fn verify_sha256(path: impl AsRef<Path>, expected: &str) -> Result {
+    let path = path.as_ref();
+    let actual = sha256_of(path).await?;
+    ensure!(
+        actual.eq_ignore_ascii_case(expected),
+        "Checksum mismatch for {}: expected SHA-256 {expected}, got {actual}.",
+        path.display()
+    );
+    Ok(())
+}
+
+impl Storable for PinnedDownload {
+    type Metadata = PathBuf;
+    type Output = PathBuf;
+    type Key = Key;
+
+    fn generate(&self, cache: Cache, store: PathBuf) -> BoxFuture<'static, Result<Self::Metadata>> {
+        let key = download::Key {
+            url:                self.key.url.clone(),
+            additional_headers: default(),
+        };
+        let download = DownloadFile { key, client: default() };
+        let download = download.generate(cache, store.clone());
+        let Key { version, url, sha256 } = self.key.clone();
+        async move {
+            let filename = download.await?;
+            verify_sha256(store.join(&filename), &sha256)
+                .await
+                .with_context(|| format!("Failed to verify {url} in version {version}."))?;
+            Ok(filename)
+        }
+        .boxed()
+    }
+
+    fn adapt(
+        &self,
+        store: PathBuf,
+        metadata: Self::Metadata,
+    ) -> BoxFuture<'static, Result<Self::Output>> {
+        ready(Ok(store.join(metadata))).boxed()
+    }
+
+    fn key(&self) -> Self::Key {
+        self.key.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn verifying_checksum() -> Result {
+        let temp = tempfile::tempdir()?;
+        let path = temp.path().join("data");
+        crate::fs::write(&path, "enso")?;
+        let actual = sha256_of(&path).await?;
+        assert_eq!(actual.len(), 64);
+        assert!(verify_sha256(&path, &actual.to_uppercase()).await.is_ok());
+        assert!(verify_sha256(&path, &"0".repeat(64)).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn offline_cache_miss() -> Result {
+        let temp = tempfile::tempdir()?;
+        let cache = Cache::new(temp.path()).await?.with_offline(true);
+        let download = PinnedDownload::new("1.0.0", "https://example.com/file-1.0.0.zip", "00")?;
+        let error = cache.get(download).await.unwrap_err();
+        assert!(error.to_string().contains("offline"), "{error:?}");
+        Ok(())
+    }
+}
@@ -221,7 +221,11 @@ pub mod new {
         pub fn prepend(&self, value: impl Into<PathBuf>) -> Result {
             let mut paths = self.get()?;
             paths.insert(0, value.into());
-            self.set(&paths)
+            self.set(&paths)?;
+            if self.name() == PATH_ENVIRONMENT_NAME {
+                crate::program::resolver::invalidate_cache();
+            }
+            Ok(())
         }
     }
 
@@ -365,6 +369,7 @@ pub fn prepend_to_path(path: impl Into<PathBuf>) -> Result {
     let new_pieces = once(path).chain(old_pieces);
     let new_value = std::env::join_paths(new_pieces)?;
     std::env::set_var(PATH_ENVIRONMENT_NAME, new_value);
+    crate::program::resolver::invalidate_cache();
     Ok(())
 }
 
@@ -424,6 +429,9 @@ impl Modification {
                     std::env::set_var(&*self.variable_name, new_value);
                 },
         };
+        if self.variable_name == UniCase::new(PATH_ENVIRONMENT_NAME.to_string()) {
+            crate::program::resolver::invalidate_cache();
+        }
         Ok(())
     }
 }
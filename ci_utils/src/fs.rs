@@ -7,6 +7,7 @@ pub mod wrappers;
 
 pub use wrappers::*;
 
+use async_compression::tokio::bufread::BrotliEncoder;
 use async_compression::tokio::bufread::GzipEncoder;
 use async_compression::Level;
 use std::fs::File;
@@ -238,6 +239,13 @@ pub async fn compressed_size(path: impl AsRef<Path>) -> Result<byte_unit::Byte>
     crate::io::read_length(encoded_stream).await.map(into)
 }
 
+/// Get the size of a file after Brotli compression.
+pub async fn brotli_compressed_size(path: impl AsRef<Path>) -> Result<byte_unit::Byte> {
+    let file = ::tokio::io::BufReader::new(crate::fs::tokio::open(&path).await?);
+    let encoded_stream = BrotliEncoder::with_quality(file, Level::Best);
+    crate::io::read_length(encoded_stream).await.map(into)
+}
+
 pub fn check_if_identical(source: impl AsRef<Path>, target: impl AsRef<Path>) -> bool {
     (|| -> Result<bool> {
         if metadata(&source)?.len() == metadata(&target)?.len() {
@@ -216,6 +216,25 @@ pub fn get_free_port() -> Result<u16> {
         .context("Failed to find a free local port.")
 }
 
+/// Looks up the given number of distinct free ports in the IANA private or dynamic port range.
+///
+/// All the ports are kept bound until the lookup is complete, so they are guaranteed to differ.
+pub fn get_free_ports(count: usize) -> Result<Vec<u16>> {
+    let mut listeners = Vec::with_capacity(count);
+    for port in UNREGISTERED_PORTS {
+        if listeners.len() == count {
+            break;
+        }
+        // See `get_free_port` on why we use `Ipv4Addr::UNSPECIFIED`.
+        let ipv4 = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port);
+        if let Ok(listener) = TcpListener::bind(ipv4) {
+            listeners.push((port, listener));
+        }
+    }
+    ensure!(listeners.len() == count, "Failed to find {count} free local ports.");
+    Ok(listeners.into_iter().map(|(port, _)| port).collect())
+}
+
 pub fn ok_ready_boxed<'a, T: 'a + Send>(t: T) -> BoxFuture<'a, Result<T>> {
     ready(Ok(t)).boxed()
 }
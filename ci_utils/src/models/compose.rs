@@ -5,30 +5,184 @@
 use crate::prelude::*;
 
 /// A build section of the service.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Build {
     pub context: PathBuf,
-    pub target:  String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target:  Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub args:    Vec<String>,
 }
 
+/// Check run inside the container to determine whether the service is healthy.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Healthcheck {
+    /// Command in the exec form, e.g. `["CMD", "pg_isready"]`.
+    pub test:         Vec<String>,
+    /// Durations are given in the Compose format, e.g. `1s` or `1m30s`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval:     Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout:      Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries:      Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_period: Option<String>,
+}
+
+impl Healthcheck {
+    /// Run the command (without a shell) every `interval`.
+    pub fn cmd(
+        command: impl IntoIterator<Item: Into<String>>,
+        interval: impl Into<String>,
+    ) -> Self {
+        let test = once("CMD".into()).chain(command.into_iter().map(Into::into));
+        Self {
+            test:         test.collect(),
+            interval:     Some(interval.into()),
+            timeout:      None,
+            retries:      None,
+            start_period: None,
+        }
+    }
+}
+
+/// Condition of the dependency that must be met before the dependent service is started.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    ServiceStarted,
+    ServiceHealthy,
+    ServiceCompletedSuccessfully,
+}
+
+/// An entry of the long-form `depends_on` section.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Dependency {
+    pub condition: Condition,
+}
+
 /// A service entry.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Service {
-    pub build:   Build,
-    pub command: String,
-    pub volumes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image:          Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build:          Option<Build>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command:        Option<String>,
+    /// Port mappings in the `[host:]container[/protocol]` form.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports:          Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment:    BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes:        Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub healthcheck:    Option<Healthcheck>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub depends_on:     BTreeMap<String, Dependency>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks:       Vec<String>,
+}
+
+impl Service {
+    /// Service running the given image.
+    pub fn image(image: impl Into<String>) -> Self {
+        Self { image: Some(image.into()), ..default() }
+    }
+
+    /// Publish the container port on the given host port.
+    pub fn publish_port(&mut self, host: u16, container: u16) -> &mut Self {
+        self.ports.push(format!("{host}:{container}"));
+        self
+    }
+
+    pub fn env(&mut self, name: impl Into<String>, value: impl Display) -> &mut Self {
+        self.environment.insert(name.into(), value.to_string());
+        self
+    }
+
+    /// Start this service only after the other one reports being healthy.
+    pub fn depends_on_healthy(&mut self, service: impl Into<String>) -> &mut Self {
+        self.depends_on.insert(service.into(), Dependency { condition: Condition::ServiceHealthy });
+        self
+    }
 }
 
 /// A top-level volume entry.
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Volume {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub external: bool,
+}
+
+/// A top-level network entry.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Network {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub driver:   Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub external: bool,
 }
 
 /// File root.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Compose {
     pub services: BTreeMap<String, Service>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub volumes:  BTreeMap<String, Volume>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub networks: BTreeMap<String, Network>,
+}
+
+impl Compose {
+    pub fn to_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Write the file, so it can be used with [`crate::programs::DockerCompose`].
+    pub fn write(&self, path: impl AsRef<Path>) -> Result {
+        crate::fs::write(path, self.to_yaml()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() -> Result {
+        let mut db = Service::image("postgres:14");
+        db.publish_port(5432, 5432).env("POSTGRES_PASSWORD", "secret");
+        db.healthcheck = Some(Healthcheck::cmd(["pg_isready"], "1s"));
+        db.networks.push("backend".into());
+        let mut app = Service::image("app");
+        app.depends_on_healthy("db");
+
+        let mut compose = Compose::default();
+        compose.services.insert("db".into(), db);
+        compose.services.insert("app".into(), app);
+        compose.networks.insert("backend".into(), default());
+
+        let yaml = compose.to_yaml()?;
+        assert!(yaml.contains("condition: service_healthy"));
+        assert!(!yaml.contains("volumes"));
+        assert_eq!(serde_yaml::from_str::<Compose>(&yaml)?, compose);
+
+        let parsed: Compose = serde_yaml::from_str(
+            r#"
+services:
+  db:
+    image: postgres
+    healthcheck:
+      test: ["CMD", "pg_isready"]
+      retries: 5
+"#,
+        )?;
+        assert_eq!(parsed.services["db"].healthcheck.as_ref().and_then(|h| h.retries), Some(5));
+        Ok(())
+    }
 }
@@ -0,0 +1,306 @@
+//! Model for the JUnit XML test reports.
+//!
+//! Only the subset of the format that our test runners produce and that the GitHub test reporters
+//! consume is supported.
+//!
+//! See: <https://llg.cubic.org/docs/junit/>
+
+use crate::prelude::*;
+
+use quick_xml::events::BytesStart;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::fmt::Write;
+use std::io::BufRead;
+
+
+
+/// Result of a single test case.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Outcome {
+    #[default]
+    Passed,
+    /// The test's assertion did not hold.
+    Failed {
+        message: String,
+    },
+    /// The test could not be run to completion, e.g. because of an unexpected exception.
+    Error {
+        message: String,
+    },
+    Skipped,
+}
+
+impl Outcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Outcome::Failed { .. } | Outcome::Error { .. })
+    }
+}
+
+/// A single test case entry.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TestCase {
+    pub name:       String,
+    pub class_name: String,
+    /// Execution time in seconds.
+    pub time:       f64,
+    pub outcome:    Outcome,
+}
+
+/// A test suite, i.e. a group of test cases.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TestSuite {
+    pub name:       String,
+    /// Execution time in seconds.
+    pub time:       f64,
+    pub test_cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    pub fn count(&self, predicate: impl Fn(&Outcome) -> bool) -> usize {
+        self.test_cases.iter().filter(|case| predicate(&case.outcome)).count()
+    }
+
+    pub fn failures(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Failed { .. }))
+    }
+
+    pub fn errors(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Error { .. }))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Skipped))
+    }
+}
+
+/// Root of the report file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub suites: Vec<TestSuite>,
+}
+
+impl Report {
+    /// Parse the report from the XML text.
+    pub fn parse(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        reader.trim_text(true);
+        let mut ret = Report::default();
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event(&mut buf)? {
+                Event::Start(element) | Event::Empty(element) =>
+                    ret.handle_element(&reader, &element)?,
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(ret)
+    }
+
+    /// Read the report from the file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let text = crate::fs::read_to_string(&path)?;
+        Self::parse(&text).with_context(|| {
+            format!("Failed to parse JUnit report from {}.", path.as_ref().display())
+        })
+    }
+
+    /// Read all the `*.xml` reports in the directory tree and merge them into a single report.
+    pub fn read_dir(root: impl AsRef<Path>) -> Result<Self> {
+        let mut ret = Report::default();
+        for entry in walkdir::WalkDir::new(root.as_ref()).sort_by_file_name() {
+            let entry = entry?;
+            if entry.file_type().is_file() && entry.path().extension().contains(&OsStr::new("xml"))
+            {
+                ret.merge(Self::read(entry.path())?);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Append the suites of the other report to this one.
+    pub fn merge(&mut self, other: Report) {
+        self.suites.extend(other.suites);
+    }
+
+    /// Total execution time of all suites, in seconds.
+    pub fn time(&self) -> f64 {
+        self.suites.iter().map(|suite| suite.time).sum()
+    }
+
+    /// All the test cases that failed or errored, along with their suites.
+    pub fn failed_cases(&self) -> impl Iterator<Item = (&TestSuite, &TestCase)> {
+        self.suites.iter().flat_map(|suite| {
+            suite
+                .test_cases
+                .iter()
+                .filter(|case| case.outcome.is_failure())
+                .map(move |c| (suite, c))
+        })
+    }
+
+    /// Generate the XML text of the report.
+    pub fn to_xml(&self) -> Result<String> {
+        let mut ret = String::new();
+        let tests: usize = self.suites.iter().map(|suite| suite.test_cases.len()).sum();
+        let failures: usize = self.suites.iter().map(TestSuite::failures).sum();
+        let errors: usize = self.suites.iter().map(TestSuite::errors).sum();
+        writeln!(ret, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            ret,
+            r#"<testsuites tests="{tests}" failures="{failures}" errors="{errors}" time="{:.3}">"#,
+            self.time()
+        )?;
+        for suite in &self.suites {
+            writeln!(
+                ret,
+                r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+                escape(&suite.name),
+                suite.test_cases.len(),
+                suite.failures(),
+                suite.errors(),
+                suite.skipped(),
+                suite.time
+            )?;
+            for case in &suite.test_cases {
+                let header = format!(
+                    r#"<testcase name="{}" classname="{}" time="{:.3}""#,
+                    escape(&case.name),
+                    escape(&case.class_name),
+                    case.time
+                );
+                match &case.outcome {
+                    Outcome::Passed => writeln!(ret, "    {header}/>")?,
+                    Outcome::Failed { message } => writeln!(
+                        ret,
+                        r#"    {header}><failure message="{}"/></testcase>"#,
+                        escape(message)
+                    )?,
+                    Outcome::Error { message } => writeln!(
+                        ret,
+                        r#"    {header}><error message="{}"/></testcase>"#,
+                        escape(message)
+                    )?,
+                    Outcome::Skipped => writeln!(ret, "    {header}><skipped/></testcase>")?,
+                }
+            }
+            writeln!(ret, "  </testsuite>")?;
+        }
+        writeln!(ret, "</testsuites>")?;
+        Ok(ret)
+    }
+
+    /// Write the report to the file.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result {
+        crate::fs::write(path, self.to_xml()?)
+    }
+
+    fn handle_element<B: BufRead>(&mut self, reader: &Reader<B>, element: &BytesStart) -> Result {
+        let attribute = |name: &[u8]| -> Result<Option<String>> {
+            for attribute in element.attributes() {
+                let attribute = attribute?;
+                if attribute.key == name {
+                    return Ok(Some(attribute.unescape_and_decode_value(reader)?));
+                }
+            }
+            Ok(None)
+        };
+        let time = || -> Result<f64> {
+            // Some reporters put thousand separators in the time.
+            Ok(attribute(b"time")?.map(|t| t.replace(',', "").parse()).transpose()?.unwrap_or(0.0))
+        };
+        let message = || -> Result<String> { Ok(attribute(b"message")?.unwrap_or_default()) };
+
+        match element.name() {
+            b"testsuite" => self.suites.push(TestSuite {
+                name:       attribute(b"name")?.unwrap_or_default(),
+                time:       time()?,
+                test_cases: default(),
+            }),
+            b"testcase" => {
+                let case = TestCase {
+                    name:       attribute(b"name")?.unwrap_or_default(),
+                    class_name: attribute(b"classname")?.unwrap_or_default(),
+                    time:       time()?,
+                    outcome:    default(),
+                };
+                self.current_suite().test_cases.push(case);
+            }
+            b"failure" => self.set_current_outcome(Outcome::Failed { message: message()? }),
+            b"error" => self.set_current_outcome(Outcome::Error { message: message()? }),
+            b"skipped" => self.set_current_outcome(Outcome::Skipped),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn current_suite(&mut self) -> &mut TestSuite {
+        if self.suites.is_empty() {
+            // Test case outside of any suite. Not really valid but let's be lenient.
+            self.suites.push(default());
+        }
+        // Unwrap is safe, as we just made sure that there is at least one suite.
+        self.suites.last_mut().unwrap()
+    }
+
+    fn set_current_outcome(&mut self, outcome: Outcome) {
+        if let Some(case) = self.current_suite().test_cases.last_mut() {
+            case.outcome = outcome;
+        }
+    }
+}
+
+/// Escape the text, so it can be used as an XML attribute value.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="Vector" tests="3" time="1.5">
+    <testcase name="should map" classname="Vector" time="0.5"/>
+    <testcase name="should fold &amp; reduce" classname="Vector" time="1.0">
+      <failure message="Expected 1 but got 2">details</failure>
+    </testcase>
+    <testcase name="should sort" classname="Vector"><skipped/></testcase>
+  </testsuite>
+</testsuites>
+"#;
+
+    #[test]
+    fn parse_report() -> Result {
+        let report = Report::parse(SAMPLE)?;
+        assert_eq!(report.suites.len(), 1);
+        let suite = &report.suites[0];
+        assert_eq!(suite.name, "Vector");
+        assert_eq!(suite.time, 1.5);
+        assert_eq!(suite.test_cases.len(), 3);
+        assert_eq!(suite.test_cases[1].name, "should fold & reduce");
+        assert_eq!(suite.test_cases[1].outcome, Outcome::Failed {
+            message: "Expected 1 but got 2".into(),
+        });
+        assert_eq!(suite.skipped(), 1);
+        assert_eq!(report.failed_cases().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip() -> Result {
+        let mut report = Report::parse(SAMPLE)?;
+        report.merge(Report::parse(SAMPLE)?);
+        let reparsed = Report::parse(&report.to_xml()?)?;
+        assert_eq!(report, reparsed);
+        assert_eq!(reparsed.time(), 3.0);
+        Ok(())
+    }
+}
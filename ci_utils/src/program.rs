@@ -59,12 +59,22 @@ pub trait Program: Sized + 'static {
     ///
     /// The lookup locations are program-defined, they typically include Path environment variable
     /// and program-specific default locations.
+    ///
+    /// The result is memoized process-wide, until the `PATH` environment variable is modified.
     fn lookup(&self) -> anyhow::Result<Location<Self>> {
-        Resolver::<Self>::new(self.executable_names(), self.default_locations())?
-            .lookup()
+        resolver::lookup_cached(self.executable_names(), self.default_locations())
             .map(Location::new)
     }
 
+    /// All the executables that could be used for the program, in the order of preference. The
+    /// first one is the one that [`Program::lookup`] picks.
+    ///
+    /// Unlike [`Program::lookup`], this is never cached, as it is meant for diagnostics.
+    fn lookup_all(&self) -> Result<Vec<PathBuf>> {
+        let resolver = Resolver::<()>::new(self.executable_names(), self.default_locations())?;
+        Ok(resolver.lookup_all().collect())
+    }
+
     fn require_present(&self) -> BoxFuture<'static, Result<String>> {
         let executable_name = self.executable_name().to_owned();
         let get_version_string = self.version_string();
@@ -198,8 +208,19 @@ impl Program for Unknown {
     fn executable_name(&self) -> &str {
         &self.0
     }
+    /// The default locations of the known program with the same executable name.
+    fn default_locations(&self) -> Vec<PathBuf> {
+        crate::programs::default_locations(&self.0)
+    }
 }
 
 pub fn lookup(executable_name: impl AsRef<str>) -> Result<PathBuf> {
-    Resolver::<()>::new(vec![executable_name.as_ref()], vec![])?.lookup()
+    Ok(Unknown(executable_name.as_ref().into()).lookup()?.executable_path)
+}
+
+/// All executables that could be used for the given program name, in the order of preference.
+///
+/// Unlike [`lookup`], this is never cached, as it is meant for diagnostics.
+pub fn lookup_all(executable_name: impl AsRef<str>) -> Result<Vec<PathBuf>> {
+    Unknown(executable_name.as_ref().into()).lookup_all()
 }
@@ -1,5 +1,39 @@
 use crate::prelude::*;
 
+use std::lazy::SyncLazy;
+use std::sync::Mutex;
+
+
+
+/// Key identifying a single lookup request: the working directory (relative `PATH` entries are
+/// resolved against it), the executable names and the fallback directories.
+type CacheKey = (PathBuf, Vec<OsString>, Vec<PathBuf>);
+
+/// Process-wide memo of the successful program lookups.
+///
+/// The lookup result depends on the `PATH` environment variable, so the cache must be cleared
+/// (see [`invalidate_cache`]) whenever it is modified. The working directory is a part of the key.
+static CACHE: SyncLazy<Mutex<HashMap<CacheKey, PathBuf>>> = SyncLazy::new(default);
+
+/// Drop all memoized lookups. Should be called after each `PATH` modification.
+pub fn invalidate_cache() {
+    trace!("Invalidating program lookup cache.");
+    CACHE.lock().unwrap().clear();
+}
+
+/// Locate the program, reusing the previous result for the same working directory, names and
+/// fallback directories.
+pub fn lookup_cached(names: Vec<&str>, fallback_dirs: Vec<PathBuf>) -> Result<PathBuf> {
+    let resolver = Resolver::<()>::new(names, fallback_dirs.clone())?;
+    let key = (resolver.cwd.clone(), resolver.names.clone(), fallback_dirs);
+    if let Some(path) = CACHE.lock().unwrap().get(&key) {
+        return Ok(path.clone());
+    }
+    let path = resolver.lookup()?;
+    CACHE.lock().unwrap().insert(key, path.clone());
+    Ok(path)
+}
+
 #[derive(Debug)]
 pub struct Resolver<P> {
     pub cwd:          PathBuf,
@@ -18,6 +52,9 @@ impl<P> Resolver<P> {
         let phantom_data = default();
         Ok(Resolver { cwd, names, lookup_dirs, phantom_data })
     }
+
+    /// All the matching executables, in the order of preference. The first one is the one that
+    /// [`Resolver::lookup`] picks.
     pub fn lookup_all(self) -> impl Iterator<Item = PathBuf> {
         let Self { names, lookup_dirs, cwd, phantom_data: _phantom_data } = self;
         names
@@ -41,3 +78,30 @@ impl<P> Resolver<P> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_is_invalidated() -> Result {
+        let dir = tempfile::tempdir()?;
+        let cwd = std::env::current_dir()?;
+        let key = (cwd, vec![OsString::from("enso-fake-program")], vec![dir.path().to_owned()]);
+        CACHE.lock().unwrap().insert(key.clone(), dir.path().join("enso-fake-program"));
+        assert!(lookup_cached(vec!["enso-fake-program"], vec![dir.path().to_owned()]).is_ok());
+        invalidate_cache();
+        assert!(!CACHE.lock().unwrap().contains_key(&key));
+        Ok(())
+    }
+
+    #[test]
+    fn cache_key_contains_cwd() -> Result {
+        let dir = tempfile::tempdir()?;
+        let other_cwd = dir.path().join("other");
+        let key = (other_cwd, vec![OsString::from("enso-other-program")], vec![]);
+        CACHE.lock().unwrap().insert(key, dir.path().join("enso-other-program"));
+        assert!(lookup_cached(vec!["enso-other-program"], vec![]).is_err());
+        Ok(())
+    }
+}
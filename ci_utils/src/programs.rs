@@ -4,6 +4,7 @@ pub mod cargo;
 pub mod cmd;
 pub mod conda;
 pub mod docker;
+pub mod docker_compose;
 pub mod flatc;
 pub mod git;
 pub mod go;
@@ -12,6 +13,7 @@ pub mod java;
 pub mod javac;
 pub mod node;
 pub mod npx;
+pub mod postgres;
 pub mod pwsh;
 pub mod robocopy;
 pub mod rsync;
@@ -23,13 +25,16 @@ pub mod sh;
 pub mod tar;
 pub mod vs;
 pub mod vswhere;
+pub mod wasm_bindgen;
 pub mod wasm_opt;
 pub mod wasm_pack;
+pub mod xvfb;
 
 pub use cargo::Cargo;
 pub use cmd::Cmd;
 pub use conda::Conda;
 pub use docker::Docker;
+pub use docker_compose::DockerCompose;
 pub use flatc::Flatc;
 pub use git::Git;
 pub use go::Go;
@@ -38,7 +43,29 @@ pub use javac::Javac;
 pub use node::Node;
 pub use node::Npm;
 pub use pwsh::PwSh;
+pub use rustup::Rustup;
 pub use sbt::Sbt;
 pub use seven_zip::SevenZip;
 pub use sh::Bash;
+pub use wasm_bindgen::WasmBindgen;
+pub use wasm_bindgen::WasmBindgenTestRunner;
 pub use wasm_pack::WasmPack;
+pub use xvfb::Xvfb;
+
+/// Default locations of the known program that can be invoked under the given executable name.
+///
+/// Used when the program is looked up just by its name, like in [`crate::program::Unknown`].
+pub fn default_locations(executable_name: &str) -> Vec<PathBuf> {
+    fn of(program: impl Program, executable_name: &str) -> Option<Vec<PathBuf>> {
+        let is_known = program.executable_names().contains(&executable_name);
+        is_known.then(|| program.default_locations())
+    }
+    of(SevenZip, executable_name)
+        .or_else(|| of(Go, executable_name))
+        .or_else(|| of(Conda, executable_name))
+        .or_else(|| of(vswhere::VsWhere, executable_name))
+        .or_else(|| of(postgres::Postgres, executable_name))
+        .or_else(|| of(postgres::InitDb, executable_name))
+        .or_else(|| of(postgres::CreateDb, executable_name))
+        .unwrap_or_default()
+}
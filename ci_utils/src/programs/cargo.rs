@@ -4,6 +4,7 @@ use crate::prelude::*;
 use crate::program::command::Manipulator;
 
 pub mod clippy;
+pub mod metadata;
 
 /// Extra flags that Cargo invokes rustc with.
 ///
@@ -0,0 +1,100 @@
+//! Model of the `cargo metadata` output.
+//!
+//! Only the parts that we use are described. See:
+//! <https://doc.rust-lang.org/cargo/commands/cargo-metadata.html#output-format>
+
+use crate::prelude::*;
+
+use crate::programs::Cargo;
+
+
+
+/// Kind of the dependency. Normal dependencies have no kind in the output.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Dev,
+    Build,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    pub kind: Option<DependencyKind>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Target {
+    pub name:     String,
+    /// E.g. `lib`, `bin`, `test` or `proc-macro`.
+    pub kind:     Vec<String>,
+    pub src_path: PathBuf,
+    /// Whether the target is tested by default by `cargo test`. Missing in older Cargo versions.
+    #[serde(default = "default_true")]
+    pub test:     bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Package {
+    pub name:          String,
+    pub version:       Version,
+    pub id:            String,
+    pub manifest_path: PathBuf,
+    pub dependencies:  Vec<Dependency>,
+    pub targets:       Vec<Target>,
+    /// The `[package.metadata]` table, `null` if not present.
+    #[serde(default)]
+    pub metadata:      serde_json::Value,
+}
+
+impl Package {
+    pub fn is_proc_macro(&self) -> bool {
+        self.targets.iter().any(|target| target.kind.iter().any(|kind| kind == "proc-macro"))
+    }
+
+    /// Whether the package depends on the given crate, in any way but as a build dependency.
+    pub fn depends_on(&self, name: &str) -> bool {
+        self.dependencies.iter().any(|dependency| {
+            dependency.name == name && dependency.kind != Some(DependencyKind::Build)
+        })
+    }
+
+    /// Value under the given path in `[package.metadata]`, e.g. `["wasm-test", "skip"]`.
+    pub fn metadata_entry<'a>(
+        &self,
+        path: impl IntoIterator<Item = &'a str>,
+    ) -> Option<&serde_json::Value> {
+        path.into_iter().try_fold(&self.metadata, |value, key| value.get(key))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Metadata {
+    pub packages:          Vec<Package>,
+    /// IDs of the workspace member packages.
+    pub workspace_members: Vec<String>,
+    pub workspace_root:    PathBuf,
+    pub target_directory:  PathBuf,
+}
+
+impl Metadata {
+    /// Describe the workspace (or package) in the given directory. The dependencies are not
+    /// resolved, so the `packages` are only the workspace members.
+    pub async fn of_workspace(root: impl AsRef<Path>) -> Result<Self> {
+        let stdout = Cargo
+            .cmd()?
+            .current_dir(root.as_ref())
+            .args(["metadata", "--format-version", "1", "--no-deps"])
+            .run_stdout()
+            .await?;
+        serde_json::from_str(&stdout).context("Failed to parse the `cargo metadata` output.")
+    }
+
+    pub fn workspace_packages(&self) -> impl Iterator<Item = &Package> {
+        self.packages.iter().filter(|package| self.workspace_members.contains(&package.id))
+    }
+}
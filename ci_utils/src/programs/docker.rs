@@ -7,6 +7,18 @@ use std::collections::HashMap;
 use std::fmt::Formatter;
 use std::process::Stdio;
 use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncRead;
+use tokio::io::BufReader;
+
+pub mod engine;
+
+pub use engine::ContainerInspect;
+pub use engine::Event;
+pub use engine::HealthStatus;
+pub use engine::LogLine;
+pub use engine::LogStream;
 
 #[derive(Clone, Debug, PartialEq, Ord, PartialOrd, Eq, Hash)]
 pub enum NetworkDriver {
@@ -46,6 +58,25 @@ impl AsRef<str> for NetworkDriver {
     }
 }
 
+impl From<&str> for NetworkDriver {
+    fn from(name: &str) -> Self {
+        match name {
+            "bridge" => NetworkDriver::Bridge,
+            "host" => NetworkDriver::Host,
+            "overlay" => NetworkDriver::Overlay,
+            "ipvlan" => NetworkDriver::Ipvlan,
+            "macvlan" => NetworkDriver::Macvlan,
+            "none" => NetworkDriver::None,
+            "ics" => NetworkDriver::Ics,
+            "nat" => NetworkDriver::Nat,
+            "transparent" => NetworkDriver::Transparent,
+            "l2bridge" => NetworkDriver::L2bridge,
+            "null" => NetworkDriver::Null,
+            name => NetworkDriver::Other(name.to_string()),
+        }
+    }
+}
+
 impl Default for NetworkDriver {
     fn default() -> Self {
         if TARGET_OS == OS::Windows {
@@ -82,6 +113,10 @@ impl Credentials {
     }
 }
 
+/// Image registering the QEMU emulators for the foreign architectures, see
+/// [`Docker::install_binfmt`].
+pub const BINFMT_IMAGE: &str = "tonistiigi/binfmt";
+
 #[derive(Clone, Copy, Debug)]
 pub struct Docker;
 
@@ -92,7 +127,15 @@ impl Program for Docker {
 }
 
 impl Docker {
+    /// Build the image and return its ID.
+    ///
+    /// Always uses the CLI, so the daemon's default builder (usually BuildKit) is used. See
+    /// [`engine::EngineClient::build`] for the API alternative.
     pub async fn build(&self, options: BuildOptions) -> Result<ImageId> {
+        ensure!(
+            !options.push && options.cache_to.is_empty() && options.builder.is_none(),
+            "Pushing, cache export and builders require buildx, see `Docker::buildx_build`."
+        );
         let mut command = self.cmd()?;
         command.arg("build").args(options.args());
         debug!("{:?}", command);
@@ -111,6 +154,71 @@ impl Docker {
         Ok(ImageId(built_image_id.into()))
     }
 
+    /// Build the image using the `buildx` plugin.
+    ///
+    /// Unlike [`Docker::build`], this can build images for multiple platforms at once, push them
+    /// (as a manifest list if there are multiple platforms) and export the build cache. Note that
+    /// the images are not loaded into the local image store.
+    ///
+    /// Returns the digest of the built image (or manifest list), if reported by the builder.
+    pub async fn buildx_build(&self, options: &BuildOptions) -> Result<Option<String>> {
+        let temp = tempfile::tempdir()?;
+        let metadata_file = temp.path().join("metadata.json");
+        let mut command = self.cmd()?;
+        command.args(["buildx", "build"]).args(options.args());
+        command.arg("--metadata-file").arg(&metadata_file);
+        command.run_ok().await?;
+        let metadata: serde_json::Value =
+            serde_json::from_str(&crate::fs::read_to_string(&metadata_file)?)?;
+        let digest = metadata.get("containerimage.digest").and_then(|d| d.as_str());
+        Ok(digest.map(ToString::to_string))
+    }
+
+    /// Create (and push) a manifest list combining the given images, e.g. built for different
+    /// platforms.
+    pub async fn create_manifest_list(
+        &self,
+        target: &str,
+        sources: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    ) -> Result {
+        let mut cmd = self.cmd()?;
+        cmd.args(["buildx", "imagetools", "create", "--tag", target]).args(sources);
+        cmd.run_ok().await
+    }
+
+    /// Make sure that the `buildx` builder of the given name exists, creating it if needed.
+    ///
+    /// The builder uses the `docker-container` driver, as the default `docker` driver can neither
+    /// build for multiple platforms at once nor export the build cache.
+    pub async fn ensure_buildx_builder(&self, name: &str) -> Result {
+        let inspect = self.cmd()?.args(["buildx", "inspect", name]).run_ok().await;
+        if inspect.is_err() {
+            info!("Creating the buildx builder {name}.");
+            let mut command = self.cmd()?;
+            command.args(["buildx", "create", "--name", name, "--driver", "docker-container"]);
+            command.run_ok().await?;
+        }
+        self.cmd()?.args(["buildx", "inspect", "--bootstrap", name]).run_ok().await
+    }
+
+    /// Register the QEMU emulators, so the images for the given platforms can be built even if
+    /// they do not match the host's architecture.
+    pub async fn install_binfmt(&self, platforms: &[Platform]) -> Result {
+        let foreign = platforms
+            .iter()
+            .filter(|platform| platform.arch != TARGET_ARCH)
+            .map(|platform| platform.arch_name())
+            .unique()
+            .join(",");
+        if foreign.is_empty() {
+            return Ok(());
+        }
+        info!("Registering the emulators for {foreign}.");
+        let mut command = self.cmd()?;
+        command.args(["run", "--privileged", "--rm", BINFMT_IMAGE, "--install", &foreign]);
+        command.run_ok().await
+    }
+
     pub fn run_cmd(&self, options: &RunOptions) -> Result<Command> {
         let mut cmd = self.cmd()?;
         cmd.arg("run").args(options.args());
@@ -122,6 +230,9 @@ impl Docker {
     }
 
     pub async fn create(&self, options: &RunOptions) -> Result<ContainerId> {
+        if let Some(engine) = engine::EngineClient::detect() {
+            return engine.create_container(options).await;
+        }
         let output = self.cmd()?.arg("create").args(options.args()).output_ok().await?;
         Ok(ContainerId(output.single_line_stdout()?))
     }
@@ -158,6 +269,15 @@ impl Docker {
             .await
     }
 
+    /// Run a command in the running container.
+    pub async fn exec<S: AsRef<OsStr>>(
+        &self,
+        container: &ContainerId,
+        command: impl IntoIterator<Item = S>,
+    ) -> Result {
+        self.cmd()?.arg("exec").arg(container.as_str()).args(command).run_ok().await
+    }
+
     pub async fn start(&self, container: &ContainerId) -> Result {
         self.cmd()?.arg("start").arg(container.as_str()).run_ok().await
     }
@@ -187,6 +307,9 @@ impl Docker {
     }
 
     pub async fn list_networks(&self) -> Result<Vec<NetworkInfo>> {
+        if let Some(engine) = engine::EngineClient::detect() {
+            return engine.list_networks().await;
+        }
         let mut cmd = Docker.cmd()?;
         cmd.args(["network", "ls", "--no-trunc"]);
         cmd.stdout(Stdio::piped());
@@ -200,20 +323,7 @@ impl Docker {
             match line.split("   ").filter(|word| !word.is_empty()).collect_vec().as_slice() {
                 [id, name, driver, scope] => ret.push(NetworkInfo {
                     id:     id.to_string(),
-                    driver: match *driver {
-                        "bridge" => NetworkDriver::Bridge,
-                        "host" => NetworkDriver::Host,
-                        "overlay" => NetworkDriver::Overlay,
-                        "ipvlan" => NetworkDriver::Ipvlan,
-                        "macvlan" => NetworkDriver::Macvlan,
-                        "none" => NetworkDriver::None,
-                        "ics" => NetworkDriver::Ics,
-                        "nat" => NetworkDriver::Nat,
-                        "transparent" => NetworkDriver::Transparent,
-                        "l2bridge" => NetworkDriver::L2bridge,
-                        "null" => NetworkDriver::Null,
-                        name => NetworkDriver::Other(name.to_string()),
-                    },
+                    driver: NetworkDriver::from(*driver),
                     name:   name.to_string(),
                     scope:  scope.to_string(),
                 }),
@@ -223,6 +333,64 @@ impl Docker {
         Ok(ret)
     }
 
+    /// Get the container information, including its state and health.
+    pub async fn inspect_container(&self, container: &ContainerId) -> Result<ContainerInspect> {
+        if let Some(engine) = engine::EngineClient::detect() {
+            return engine.inspect_container(container).await;
+        }
+        let mut cmd = self.cmd()?;
+        cmd.args(["inspect", "--type", "container", container.as_str()]);
+        let output = cmd.output_ok().await?;
+        let mut ret = serde_json::from_slice::<Vec<ContainerInspect>>(&output.stdout)?;
+        ret.pop().with_context(|| format!("No information about the container {container}."))
+    }
+
+    pub async fn health_status(&self, container: &ContainerId) -> Result<HealthStatus> {
+        Ok(self.inspect_container(container).await?.health_status())
+    }
+
+    /// Stream the container output, starting with its whole history, until the container stops.
+    pub async fn logs(
+        &self,
+        container: &ContainerId,
+    ) -> Result<BoxStream<'static, Result<LogLine>>> {
+        if let Some(engine) = engine::EngineClient::detect() {
+            return engine.logs(container).await;
+        }
+        let mut cmd = self.cmd()?;
+        cmd.args(["logs", "--follow", container.as_str()]);
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+        let mut child = cmd.spawn()?;
+        let stdout = child.stdout.take().context("Missing standard output.")?;
+        let stderr = child.stderr.take().context("Missing standard error.")?;
+        let stdout =
+            lines_stream(stdout).map_ok(|text| LogLine { stream: LogStream::Stdout, text });
+        let stderr =
+            lines_stream(stderr).map_ok(|text| LogLine { stream: LogStream::Stderr, text });
+        Ok(owning_process(futures::stream::select(stdout, stderr), child))
+    }
+
+    /// Stream the daemon events, optionally limited to the given container.
+    pub async fn events(
+        &self,
+        container: Option<&ContainerId>,
+    ) -> Result<BoxStream<'static, Result<Event>>> {
+        if let Some(engine) = engine::EngineClient::detect() {
+            return engine.events(container).await;
+        }
+        let mut cmd = self.cmd()?;
+        cmd.args(["events", "--format", "{{json .}}"]);
+        if let Some(container) = container {
+            cmd.arg("--filter").arg(format!("container={container}"));
+        }
+        cmd.stdout(Stdio::piped()).kill_on_drop(true);
+        let mut child = cmd.spawn()?;
+        let stdout = child.stdout.take().context("Missing standard output.")?;
+        let events = lines_stream(stdout)
+            .and_then(|line| ready(serde_json::from_str::<Event>(&line).anyhow_err()));
+        Ok(owning_process(events, child))
+    }
+
     pub async fn while_logged_in<F: Future<Output = Result<T>>, T>(
         &self,
         credentials: Credentials,
@@ -252,6 +420,13 @@ impl Docker {
         cmd.run_ok().await
     }
 
+    /// Create the `target` tag referring to the `source` image.
+    pub async fn tag(&self, source: &str, target: &str) -> Result {
+        let mut cmd = self.cmd()?;
+        cmd.args(["tag", source, target]);
+        cmd.run_ok().await
+    }
+
     pub async fn push(&self, image: &str) -> Result {
         let mut cmd = self.cmd()?;
         cmd.args(["push", image]);
@@ -259,6 +434,60 @@ impl Docker {
     }
 }
 
+/// Platform of the image, like `linux/amd64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Platform {
+    pub os:   OS,
+    pub arch: Arch,
+}
+
+impl Platform {
+    pub fn new(os: OS, arch: Arch) -> Self {
+        Self { os, arch }
+    }
+
+    pub fn linux(arch: Arch) -> Self {
+        Self::new(OS::Linux, arch)
+    }
+
+    /// Architecture name, as used by Docker.
+    pub fn arch_name(&self) -> &'static str {
+        match self.arch {
+            Arch::X86_64 => "amd64",
+            Arch::AArch64 => "arm64",
+            arch => arch.as_str(),
+        }
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.os.as_str(), self.arch_name())
+    }
+}
+
+/// Stream of the lines read from the process output.
+fn lines_stream(
+    reader: impl AsyncRead + Send + Unpin + 'static,
+) -> impl Stream<Item = Result<String>> + Send + 'static {
+    futures::stream::unfold(BufReader::new(reader).lines(), |mut lines| async move {
+        lines.next_line().await.transpose().map(|line| (line.anyhow_err(), lines))
+    })
+}
+
+/// Keep the process alive as long as the stream of its output. As the process should be spawned
+/// with `kill_on_drop`, dropping the stream kills it.
+fn owning_process<T: Send + 'static>(
+    stream: impl Stream<Item = T> + Send + 'static,
+    child: tokio::process::Child,
+) -> BoxStream<'static, T> {
+    futures::stream::unfold((stream.boxed(), child), |(mut stream, child)| async move {
+        let item = stream.next().await?;
+        Some((item, (stream, child)))
+    })
+    .boxed()
+}
+
 #[derive(Clone, Debug)]
 pub struct BuildOptions {
     pub context:    PathBuf,
@@ -266,6 +495,18 @@ pub struct BuildOptions {
     pub tags:       Vec<String>,
     pub build_args: HashMap<String, Option<String>>,
     pub file:       Option<PathBuf>,
+    /// Platforms to build the image for. If empty, the platform of the Docker daemon is used.
+    /// Multiple platforms require [`Docker::buildx_build`].
+    pub platforms:  Vec<Platform>,
+    /// Push the built image. Requires [`Docker::buildx_build`].
+    pub push:       bool,
+    /// External cache sources, e.g. `type=registry,ref=<image>`.
+    pub cache_from: Vec<String>,
+    /// Cache export destinations, e.g. `type=registry,ref=<image>,mode=max`. Requires
+    /// [`Docker::buildx_build`].
+    pub cache_to:   Vec<String>,
+    /// The `buildx` builder to use, see [`Docker::ensure_buildx_builder`].
+    pub builder:    Option<String>,
 }
 
 impl BuildOptions {
@@ -276,6 +517,11 @@ impl BuildOptions {
             tags:       default(),
             build_args: default(),
             file:       default(),
+            platforms:  default(),
+            push:       default(),
+            cache_from: default(),
+            cache_to:   default(),
+            builder:    default(),
         }
     }
 
@@ -322,6 +568,25 @@ impl BuildOptions {
             // C:\Users\mwu\AppData\Local\Temp\2\.tmpOykTop`
             ret.push(file.without_verbatim_prefix().into());
         }
+        if !self.platforms.is_empty() {
+            ret.push("--platform".into());
+            ret.push(self.platforms.iter().join(",").into());
+        }
+        if self.push {
+            ret.push("--push".into());
+        }
+        for cache_from in &self.cache_from {
+            ret.push("--cache-from".into());
+            ret.push(cache_from.into());
+        }
+        for cache_to in &self.cache_to {
+            ret.push("--cache-to".into());
+            ret.push(cache_to.into());
+        }
+        if let Some(builder) = &self.builder {
+            ret.push("--builder".into());
+            ret.push(builder.into());
+        }
         ret
     }
 }
@@ -400,6 +665,10 @@ pub struct RunOptions {
     pub storage_size_gb:   Option<usize>,
     /// Proxy all received signals to the process (non-TTY mode only).
     pub sig_proxy:         Option<bool>,
+    /// Command run in the container to check its health, see [`Docker::health_status`].
+    pub health_cmd:        Option<String>,
+    /// Time between the health checks.
+    pub health_interval:   Option<Duration>,
 }
 
 impl RunOptions {
@@ -416,6 +685,8 @@ impl RunOptions {
             network: default(),
             storage_size_gb: default(),
             sig_proxy: default(),
+            health_cmd: default(),
+            health_interval: default(),
         }
     }
 
@@ -507,6 +778,15 @@ impl RunOptions {
             ret.push(arg.into());
         }
 
+        if let Some(health_cmd) = self.health_cmd.as_ref() {
+            ret.push("--health-cmd".into());
+            ret.push(health_cmd.into());
+        }
+
+        if let Some(health_interval) = self.health_interval {
+            ret.push(format!("--health-interval={}ms", health_interval.as_millis()).into());
+        }
+
         ret.push(OsString::from(&self.image.0));
 
         ret.extend(self.command.clone());
@@ -532,6 +812,31 @@ impl FromStr for ContainerId {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn dropping_stream_kills_process() -> Result {
+        let mut command = tokio::process::Command::new("sh");
+        command.args(["-c", "echo ready; sleep 60"]).stdout(Stdio::piped()).kill_on_drop(true);
+        let mut child = command.spawn()?;
+        let pid = child.id().context("Missing process ID.")?;
+        let stdout = child.stdout.take().context("Missing standard output.")?;
+        let mut lines = owning_process(lines_stream(stdout), child);
+        assert_eq!(lines.next().await.transpose()?.as_deref(), Some("ready"));
+        drop(lines);
+        // The killed process may linger as a zombie until it is reaped.
+        let is_running = || {
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).unwrap_or_default();
+            !stat.is_empty() && !stat.contains(") Z ")
+        };
+        for _ in 0..50 {
+            if !is_running() {
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        bail!("The process {pid} is still running.")
+    }
+
     #[tokio::test]
     #[ignore]
     async fn network() -> Result {
@@ -541,6 +846,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn buildx_args() {
+        let mut opts = BuildOptions::new("context");
+        opts.platforms = vec![Platform::linux(Arch::X86_64), Platform::linux(Arch::AArch64)];
+        opts.push = true;
+        opts.cache_to.push("type=inline".into());
+        opts.builder = Some("enso-builder".into());
+        let args = opts.args();
+        let args = args.iter().map(|arg| arg.as_str()).collect_vec();
+        assert_eq!(args, [
+            "context",
+            "--platform",
+            "linux/amd64,linux/arm64",
+            "--push",
+            "--cache-to",
+            "type=inline",
+            "--builder",
+            "enso-builder"
+        ]);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn build() -> Result {
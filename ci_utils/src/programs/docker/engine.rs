@@ -0,0 +1,727 @@
+//! Client of the Docker Engine HTTP API.
+//!
+//! The API is used through the local Unix socket, where available. The [`Docker`] methods fall
+//! back to the CLI otherwise, so the callers do not need to care which is used.
+//!
+//! See: <https://docs.docker.com/engine/api/v1.41/>
+//!
+//! [`Docker`]: crate::programs::Docker
+
+use crate::prelude::*;
+
+use crate::programs::docker::BuildOptions;
+use crate::programs::docker::ContainerId;
+use crate::programs::docker::ImageId;
+use crate::programs::docker::NetworkDriver;
+use crate::programs::docker::NetworkInfo;
+use crate::programs::docker::RestartPolicy;
+use crate::programs::docker::RunOptions;
+use bytes::Buf;
+use bytes::BytesMut;
+use hyper::body::HttpBody;
+use hyper::Body;
+use hyper::Method;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
+use serde::de::DeserializeOwned;
+
+
+
+/// Version of the API that we speak. Supported by Docker Engine 20.10 and newer.
+pub const API_VERSION: &str = "v1.41";
+
+/// Name under which the Dockerfile from outside the build context is added to it.
+pub const DOCKERFILE_IN_CONTEXT: &str = ".dockerfile.enso";
+
+/// Default location of the Docker daemon socket.
+pub const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+crate::define_env_var! {
+    /// Address of the Docker daemon, e.g. `unix:///var/run/docker.sock`.
+    DOCKER_HOST, String;
+
+    /// If set to true, the Docker CLI is used even if the Engine API is available.
+    ENSO_DOCKER_DISABLE_ENGINE_API, bool;
+}
+
+/// Health of the container, as reported by its health check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// The container has no health check.
+    None,
+    Starting,
+    Healthy,
+    Unhealthy,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct HealthCheckResult {
+    pub exit_code: i64,
+    pub output:    String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Health {
+    pub status:         HealthStatus,
+    pub failing_streak: u32,
+    #[serde(default)]
+    pub log:            Vec<HealthCheckResult>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerState {
+    /// E.g. `created`, `running` or `exited`.
+    pub status:    String,
+    pub running:   bool,
+    pub exit_code: i64,
+    /// Present only if the container has a health check.
+    pub health:    Option<Health>,
+}
+
+/// The subset of the container information returned by `docker inspect`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerInspect {
+    pub id:    String,
+    pub name:  String,
+    pub image: String,
+    pub state: ContainerState,
+}
+
+impl ContainerInspect {
+    pub fn health_status(&self) -> HealthStatus {
+        self.state.health.as_ref().map_or(HealthStatus::None, |health| health.status)
+    }
+}
+
+/// Object that an event is about.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EventActor {
+    #[serde(rename = "ID")]
+    pub id:         String,
+    #[serde(rename = "Attributes", default)]
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// Event reported by the daemon, like a container start or a health status change.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Event {
+    /// Type of the object, e.g. `container` or `network`.
+    #[serde(rename = "Type")]
+    pub r#type: String,
+    /// E.g. `start`, `die` or `health_status: healthy`.
+    #[serde(rename = "Action")]
+    pub action: String,
+    #[serde(rename = "Actor")]
+    pub actor:  EventActor,
+    /// Unix timestamp.
+    pub time:   i64,
+}
+
+/// Output stream of the container process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogLine {
+    pub stream: LogStream,
+    pub text:   String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct NetworkListEntry {
+    id:     String,
+    name:   String,
+    driver: String,
+    scope:  String,
+}
+
+/// Client connecting to the Docker daemon through its Unix socket.
+#[derive(Clone, Debug)]
+pub struct EngineClient {
+    pub socket: PathBuf,
+}
+
+impl EngineClient {
+    /// Find the daemon socket, as described by the environment.
+    ///
+    /// Returns `None` if the API should not or cannot be used, e.g. because the daemon is remote
+    /// or not on a Unix system.
+    pub fn detect() -> Option<Self> {
+        if ENSO_DOCKER_DISABLE_ENGINE_API.get().contains(&true) || !cfg!(unix) {
+            return None;
+        }
+        let socket = match DOCKER_HOST.get() {
+            Ok(host) => PathBuf::from(host.strip_prefix("unix://")?),
+            Err(_) => PathBuf::from(DEFAULT_SOCKET),
+        };
+        socket.exists().then_some(Self { socket })
+    }
+
+    #[cfg(unix)]
+    async fn connect(&self) -> Result<hyper::client::conn::SendRequest<Body>> {
+        let stream = tokio::net::UnixStream::connect(&self.socket)
+            .await
+            .with_context(|| format!("Failed to connect to {}.", self.socket.display()))?;
+        let (sender, connection) = hyper::client::conn::handshake(stream).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("Docker Engine API connection failed: {e}");
+            }
+        });
+        Ok(sender)
+    }
+
+    #[cfg(not(unix))]
+    async fn connect(&self) -> Result<hyper::client::conn::SendRequest<Body>> {
+        bail!("Docker Engine API is supported only through the Unix socket.")
+    }
+
+    /// Send the request with the JSON body to the given path (without the API version prefix).
+    pub async fn request(&self, method: Method, path: &str, body: Body) -> Result<Response<Body>> {
+        self.request_with_content_type(method, path, "application/json", body).await
+    }
+
+    pub async fn request_with_content_type(
+        &self,
+        method: Method,
+        path: &str,
+        content_type: &str,
+        body: Body,
+    ) -> Result<Response<Body>> {
+        let request = Request::builder()
+            .method(method)
+            .uri(format!("/{API_VERSION}{path}"))
+            .header(hyper::header::HOST, "docker")
+            .header(hyper::header::CONTENT_TYPE, content_type)
+            .body(body)?;
+        trace!("Docker Engine API request: {request:?}");
+        let response = self.connect().await?.send_request(request).await?;
+        let status = response.status();
+        if status.is_client_error() || status.is_server_error() {
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            let message = serde_json::from_slice::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|error| error.get("message")?.as_str().map(ToString::to_string))
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).into());
+            bail!("Docker Engine API request to {path} failed with {status}: {message}");
+        }
+        Ok(response)
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self.request(Method::GET, path, Body::empty()).await?;
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        serde_json::from_slice(&body).with_context(|| format!("Failed to parse {path} response."))
+    }
+
+    /// Check that the daemon is responding.
+    pub async fn ping(&self) -> Result {
+        let response = self.request(Method::GET, "/_ping", Body::empty()).await?;
+        ensure!(response.status() == StatusCode::OK, "Unexpected ping response: {response:?}");
+        Ok(())
+    }
+
+    /// Create the container, without starting it.
+    pub async fn create_container(&self, options: &RunOptions) -> Result<ContainerId> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct CreateResponse {
+            id:       String,
+            #[serde(default)]
+            warnings: Vec<String>,
+        }
+
+        let path = match &options.name {
+            Some(name) => format!("/containers/create?{}", query([("name", name.as_str())])),
+            None => "/containers/create".into(),
+        };
+        let body = create_container_body(options).to_string();
+        let response = self.request(Method::POST, &path, body.into()).await?;
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let response = serde_json::from_slice::<CreateResponse>(&body)?;
+        for warning in response.warnings {
+            warn!("{warning}");
+        }
+        Ok(ContainerId(response.id))
+    }
+
+    /// Build the image, sending the context as a tarball.
+    ///
+    /// Unlike [`Docker::build`], this uses the legacy builder rather than BuildKit, so the
+    /// Dockerfile cannot use the BuildKit-only features (like `RUN --mount`). Also, the
+    /// `.dockerignore` file is not supported, the whole context is sent.
+    ///
+    /// [`Docker::build`]: crate::programs::Docker::build
+    pub async fn build(&self, options: &BuildOptions) -> Result<ImageId> {
+        ensure!(options.platforms.len() <= 1, "Multiple platforms require buildx.");
+        let context = options.context.clone();
+        let dockerfile = options.file.clone();
+        let (tarball, dockerfile) =
+            tokio::task::spawn_blocking(move || pack_context(&context, dockerfile.as_deref()))
+                .await??;
+        let path = format!("/build?{}", build_query(options, dockerfile.as_deref())?);
+        let file = tokio::fs::File::open(tarball.path()).await?;
+        let body = Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+        let response =
+            self.request_with_content_type(Method::POST, &path, "application/x-tar", body).await?;
+
+        let mut body = response.into_body();
+        let mut buffer = BytesMut::new();
+        let mut image_id = None;
+        let mut handle_line = |line: &[u8]| -> Result {
+            let message = serde_json::from_slice::<BuildMessage>(line)?;
+            if let Some(error) = message.error {
+                bail!("Docker build failed: {error}");
+            }
+            if let Some(stream) = message.stream {
+                debug!("{}", stream.trim_end());
+            }
+            if let Some(aux) = message.aux {
+                image_id = Some(aux.id);
+            }
+            Ok(())
+        };
+        while let Some(chunk) = body.data().await {
+            buffer.extend_from_slice(&chunk?);
+            while let Some(position) = buffer.iter().position(|byte| *byte == b'\n') {
+                let line = buffer.split_to(position + 1);
+                handle_line(&line)?;
+            }
+        }
+        if !buffer.iter().all(u8::is_ascii_whitespace) {
+            handle_line(&buffer)?;
+        }
+        let image_id = image_id.context("Docker did not report the built image ID.")?;
+        debug!("Image {image_id} successfully built!");
+        Ok(ImageId(image_id))
+    }
+
+    pub async fn inspect_container(&self, container: &ContainerId) -> Result<ContainerInspect> {
+        self.get_json(&format!("/containers/{container}/json")).await
+    }
+
+    pub async fn list_networks(&self) -> Result<Vec<NetworkInfo>> {
+        let networks = self.get_json::<Vec<NetworkListEntry>>("/networks").await?;
+        Ok(networks
+            .into_iter()
+            .map(|network| NetworkInfo {
+                id:     network.id,
+                name:   network.name,
+                driver: NetworkDriver::from(network.driver.as_str()),
+                scope:  network.scope,
+            })
+            .collect())
+    }
+
+    /// Stream the container output, starting with its whole history.
+    ///
+    /// The stream ends when the container stops. Containers with a TTY are not supported, as
+    /// their output is not multiplexed.
+    pub async fn logs(
+        &self,
+        container: &ContainerId,
+    ) -> Result<BoxStream<'static, Result<LogLine>>> {
+        let path = format!("/containers/{container}/logs?follow=true&stdout=true&stderr=true");
+        let response = self.request(Method::GET, &path, Body::empty()).await?;
+        let decoder = LogDecoder::default();
+        let stream = futures::stream::unfold(
+            (response.into_body(), decoder),
+            |(mut body, mut decoder)| async move {
+                loop {
+                    if let Some(line) = decoder.next_line() {
+                        return Some((line, (body, decoder)));
+                    }
+                    match body.data().await {
+                        Some(Ok(chunk)) => decoder.push(&chunk),
+                        Some(Err(e)) => return Some((Err(e.into()), (body, decoder))),
+                        None => return decoder.finish().map(|line| (line, (body, decoder))),
+                    }
+                }
+            },
+        );
+        Ok(stream.boxed())
+    }
+
+    /// Stream the daemon events, optionally limited to the given container.
+    pub async fn events(
+        &self,
+        container: Option<&ContainerId>,
+    ) -> Result<BoxStream<'static, Result<Event>>> {
+        let path = match container {
+            Some(container) => {
+                let filters = serde_json::json!({ "container": [container.as_str()] });
+                let filters = url::form_urlencoded::byte_serialize(filters.to_string().as_bytes())
+                    .collect::<String>();
+                format!("/events?filters={filters}")
+            }
+            None => "/events".into(),
+        };
+        let response = self.request(Method::GET, &path, Body::empty()).await?;
+        let stream = futures::stream::unfold(
+            (response.into_body(), BytesMut::new()),
+            |(mut body, mut buffer)| async move {
+                loop {
+                    if let Some(position) = buffer.iter().position(|byte| *byte == b'\n') {
+                        let line = buffer.split_to(position + 1);
+                        let event = serde_json::from_slice::<Event>(&line).anyhow_err();
+                        return Some((event, (body, buffer)));
+                    }
+                    match body.data().await {
+                        Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                        Some(Err(e)) => return Some((Err(e.into()), (body, buffer))),
+                        None => return None,
+                    }
+                }
+            },
+        );
+        Ok(stream.boxed())
+    }
+}
+
+/// Progress message of the image build.
+#[derive(Clone, Debug, Deserialize)]
+struct BuildMessage {
+    stream: Option<String>,
+    error:  Option<String>,
+    aux:    Option<BuildAux>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct BuildAux {
+    #[serde(rename = "ID")]
+    id: String,
+}
+
+/// URL-encoded query string.
+fn query<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    url::form_urlencoded::Serializer::new(String::new()).extend_pairs(pairs).finish()
+}
+
+/// Query string of the image build request.
+///
+/// The `dockerfile` is the path of the Dockerfile within the context, if not the default one.
+pub fn build_query(options: &BuildOptions, dockerfile: Option<&str>) -> Result<String> {
+    let mut pairs = Vec::new();
+    for tag in &options.tags {
+        pairs.push(("t", tag.clone()));
+    }
+    if let Some(target) = &options.target {
+        pairs.push(("target", target.as_str().to_string()));
+    }
+    if let Some(dockerfile) = dockerfile {
+        pairs.push(("dockerfile", dockerfile.to_string()));
+    }
+    if let [platform] = options.platforms.as_slice() {
+        pairs.push(("platform", platform.to_string()));
+    }
+    if !options.build_args.is_empty() {
+        // As with the CLI, the arguments without a value are taken from the environment.
+        let build_args = options
+            .build_args
+            .iter()
+            .filter_map(|(name, value)| {
+                let value = value.clone().or_else(|| std::env::var(name).ok())?;
+                Some((name.clone(), value))
+            })
+            .collect::<BTreeMap<_, _>>();
+        pairs.push(("buildargs", serde_json::to_string(&build_args)?));
+    }
+    if !options.cache_from.is_empty() {
+        pairs.push(("cachefrom", serde_json::to_string(&options.cache_from)?));
+    }
+    Ok(query(pairs.iter().map(|(name, value)| (*name, value.as_str()))))
+}
+
+/// Pack the build context into a temporary tarball.
+///
+/// Returns the tarball and the path of the Dockerfile within it, if not the default one. The
+/// Dockerfile from outside the context is added to it as [`DOCKERFILE_IN_CONTEXT`].
+pub fn pack_context(
+    context: &Path,
+    dockerfile: Option<&Path>,
+) -> Result<(tempfile::NamedTempFile, Option<String>)> {
+    let tarball = tempfile::NamedTempFile::new()?;
+    let mut builder = tar::Builder::new(tarball.reopen()?);
+    builder.follow_symlinks(false);
+    builder.append_dir_all(".", context)?;
+    let dockerfile = match dockerfile {
+        Some(file) => match file.strip_prefix(context) {
+            Ok(relative) => Some(relative.iter().map(|part| part.to_string_lossy()).join("/")),
+            Err(_) => {
+                builder.append_path_with_name(file, DOCKERFILE_IN_CONTEXT)?;
+                Some(DOCKERFILE_IN_CONTEXT.to_string())
+            }
+        },
+        None => None,
+    };
+    builder.into_inner()?;
+    Ok((tarball, dockerfile))
+}
+
+/// Body of the container creation request, equivalent to the `docker create` arguments.
+///
+/// See: <https://docs.docker.com/engine/api/v1.41/#operation/ContainerCreate>
+pub fn create_container_body(options: &RunOptions) -> serde_json::Value {
+    let port_key = |port: &u16| format!("{port}/tcp");
+    let exposed_ports = options
+        .ports
+        .values()
+        .map(|guest| (port_key(guest), serde_json::json!({})))
+        .collect::<serde_json::Map<_, _>>();
+    let port_bindings = options
+        .ports
+        .iter()
+        .map(|(host, guest)| {
+            (port_key(guest), serde_json::json!([{ "HostPort": host.to_string() }]))
+        })
+        .collect::<serde_json::Map<_, _>>();
+    let binds = options
+        .volume
+        .iter()
+        .map(|(host, guest)| format!("{}:{}", host.display(), guest.display()))
+        .collect_vec();
+    let env = options
+        .env
+        .iter()
+        .map(|(name, value)| format!("{}={}", name.to_string_lossy(), value.to_string_lossy()))
+        .collect_vec();
+
+    let mut host_config = serde_json::json!({
+        "Binds": binds,
+        "PortBindings": port_bindings,
+    });
+    if let Some(network) = &options.network {
+        host_config["NetworkMode"] = network.to_string().into();
+    }
+    if let Some(restart) = options.restart {
+        let (name, retries) = match restart {
+            RestartPolicy::No => ("no", None),
+            RestartPolicy::OnFailure { max_retries } => ("on-failure", max_retries),
+            RestartPolicy::Always => ("always", None),
+            RestartPolicy::UnlessStopped => ("unless-stopped", None),
+        };
+        host_config["RestartPolicy"] =
+            serde_json::json!({ "Name": name, "MaximumRetryCount": retries.unwrap_or(0) });
+    }
+    if let Some(storage_size_gb) = options.storage_size_gb {
+        host_config["StorageOpt"] = serde_json::json!({ "size": format!("{storage_size_gb}G") });
+    }
+
+    let mut ret = serde_json::json!({
+        "Image": options.image.0,
+        "Env": env,
+        "ExposedPorts": exposed_ports,
+        "HostConfig": host_config,
+    });
+    if !options.command.is_empty() {
+        let command = options.command.iter().map(|arg| arg.to_string_lossy()).collect_vec();
+        ret["Cmd"] = serde_json::json!(command);
+    }
+    if let Some(working_directory) = &options.working_directory {
+        ret["WorkingDir"] = working_directory.display().to_string().into();
+    }
+    if let Some(health_cmd) = &options.health_cmd {
+        let mut healthcheck = serde_json::json!({ "Test": ["CMD-SHELL", health_cmd] });
+        if let Some(interval) = options.health_interval {
+            healthcheck["Interval"] = (interval.as_nanos() as u64).into();
+        }
+        ret["Healthcheck"] = healthcheck;
+    }
+    ret
+}
+
+/// Decoder of the multiplexed container output.
+///
+/// Each frame has an 8-byte header: the stream type, three zero bytes and the big-endian 32-bit
+/// payload length. Frames are not aligned with lines, so the partial lines are buffered.
+#[derive(Debug, Default)]
+pub struct LogDecoder {
+    input:   BytesMut,
+    stdout:  Vec<u8>,
+    stderr:  Vec<u8>,
+    decoded: std::collections::VecDeque<LogLine>,
+}
+
+impl LogDecoder {
+    const HEADER_LENGTH: usize = 8;
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.input.extend_from_slice(data);
+        while self.input.len() >= Self::HEADER_LENGTH {
+            let mut header = &self.input[..Self::HEADER_LENGTH];
+            let stream = if header.get_u8() == 2 { LogStream::Stderr } else { LogStream::Stdout };
+            header.advance(3);
+            let length = header.get_u32() as usize;
+            if self.input.len() < Self::HEADER_LENGTH + length {
+                break;
+            }
+            self.input.advance(Self::HEADER_LENGTH);
+            let payload = self.input.split_to(length);
+            let buffer = match stream {
+                LogStream::Stdout => &mut self.stdout,
+                LogStream::Stderr => &mut self.stderr,
+            };
+            buffer.extend_from_slice(&payload);
+            while let Some(position) = buffer.iter().position(|byte| *byte == b'\n') {
+                let line = buffer.drain(..=position).collect_vec();
+                let text = String::from_utf8_lossy(&line).trim_end().to_string();
+                self.decoded.push_back(LogLine { stream, text });
+            }
+        }
+    }
+
+    pub fn next_line(&mut self) -> Option<Result<LogLine>> {
+        self.decoded.pop_front().map(Ok)
+    }
+
+    /// Flush the unterminated lines at the end of the output.
+    pub fn finish(&mut self) -> Option<Result<LogLine>> {
+        for (stream, buffer) in
+            [(LogStream::Stdout, &mut self.stdout), (LogStream::Stderr, &mut self.stderr)]
+        {
+            if !buffer.is_empty() {
+                let text = String::from_utf8_lossy(buffer).trim_end().to_string();
+                buffer.clear();
+                self.decoded.push_back(LogLine { stream, text });
+            }
+        }
+        self.next_line()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::docker::Network;
+    use std::time::Duration;
+
+    fn frame(stream: u8, payload: &str) -> Vec<u8> {
+        let mut ret = vec![stream, 0, 0, 0];
+        ret.extend((payload.len() as u32).to_be_bytes());
+        ret.extend(payload.as_bytes());
+        ret
+    }
+
+    #[test]
+    fn decoding_logs() {
+        let mut decoder = LogDecoder::default();
+        let data = [frame(1, "hello\nwor"), frame(2, "oops\n"), frame(1, "ld\nbye")].concat();
+        // Feed in small pieces, to check that the frames split across chunks are handled.
+        for chunk in data.chunks(5) {
+            decoder.push(chunk);
+        }
+        let mut lines = Vec::new();
+        while let Some(line) = decoder.next_line() {
+            lines.push(line.unwrap());
+        }
+        lines.extend(decoder.finish().map(Result::unwrap));
+        let stdout = |text: &str| LogLine { stream: LogStream::Stdout, text: text.into() };
+        let stderr = |text: &str| LogLine { stream: LogStream::Stderr, text: text.into() };
+        assert_eq!(lines, vec![stdout("hello"), stderr("oops"), stdout("world"), stdout("bye")]);
+    }
+
+    #[test]
+    fn container_creation_body() {
+        let mut options = RunOptions::new(ImageId("postgres:latest".into()));
+        options.name = Some("db".into());
+        options.env_raw("POSTGRES_USER", "enso");
+        options.publish_port(5433, 5432);
+        options.volume("/data", "/var/lib/postgresql/data");
+        options.network = Some(Network::Host);
+        options.restart = Some(RestartPolicy::OnFailure { max_retries: Some(3) });
+        options.health_cmd = Some("pg_isready".into());
+        options.health_interval = Some(Duration::from_secs(1));
+        options.command = vec!["postgres".into(), "-N".into(), "100".into()];
+        let body = create_container_body(&options);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "Image": "postgres:latest",
+                "Env": ["POSTGRES_USER=enso"],
+                "Cmd": ["postgres", "-N", "100"],
+                "ExposedPorts": { "5432/tcp": {} },
+                "Healthcheck": { "Test": ["CMD-SHELL", "pg_isready"], "Interval": 1_000_000_000u64 },
+                "HostConfig": {
+                    "Binds": ["/data:/var/lib/postgresql/data"],
+                    "PortBindings": { "5432/tcp": [{ "HostPort": "5433" }] },
+                    "NetworkMode": "host",
+                    "RestartPolicy": { "Name": "on-failure", "MaximumRetryCount": 3 },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn packing_build_context() -> Result {
+        let temp = tempfile::tempdir()?;
+        let context = temp.path().join("context");
+        crate::fs::write(context.join("Dockerfile"), "FROM scratch")?;
+        crate::fs::write(context.join("bin/app"), "app")?;
+        crate::fs::write(temp.path().join("other.Dockerfile"), "FROM busybox")?;
+
+        let (tarball, dockerfile) = pack_context(&context, None)?;
+        assert_eq!(dockerfile, None);
+        let (_, dockerfile) = pack_context(&context, Some(&context.join("Dockerfile")))?;
+        assert_eq!(dockerfile.as_deref(), Some("Dockerfile"));
+        let entries = |tarball: &tempfile::NamedTempFile| -> Result<Vec<String>> {
+            let mut archive = tar::Archive::new(tarball.reopen()?);
+            let mut ret = Vec::new();
+            for entry in archive.entries()? {
+                let entry = entry?;
+                if entry.header().entry_type().is_file() {
+                    ret.push(entry.path()?.to_string_lossy().trim_start_matches("./").to_string());
+                }
+            }
+            ret.sort();
+            Ok(ret)
+        };
+        assert_eq!(entries(&tarball)?, ["Dockerfile", "bin/app"]);
+
+        let (tarball, dockerfile) =
+            pack_context(&context, Some(&temp.path().join("other.Dockerfile")))?;
+        assert_eq!(dockerfile.as_deref(), Some(DOCKERFILE_IN_CONTEXT));
+        assert_eq!(entries(&tarball)?, [DOCKERFILE_IN_CONTEXT, "Dockerfile", "bin/app"]);
+        Ok(())
+    }
+
+    #[test]
+    fn build_request_query() -> Result {
+        let mut options = BuildOptions::new("context");
+        options.tags = vec!["enso:1".into(), "enso:latest".into()];
+        options.build_args.insert("VERSION".into(), Some("1.0".into()));
+        options.cache_from.push("enso:0".into());
+        let query = build_query(&options, Some("tools/Dockerfile"))?;
+        assert_eq!(
+            query,
+            "t=enso%3A1&t=enso%3Alatest&dockerfile=tools%2FDockerfile&\
+             buildargs=%7B%22VERSION%22%3A%221.0%22%7D&cachefrom=%5B%22enso%3A0%22%5D"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parsing_inspect() -> Result {
+        let json = r#"{
+            "Id": "abc", "Name": "/postgres", "Image": "sha256:def",
+            "State": {
+                "Status": "running", "Running": true, "ExitCode": 0,
+                "Health": {"Status": "healthy", "FailingStreak": 0, "Log": [
+                    {"Start": "", "End": "", "ExitCode": 0, "Output": "accepting connections"}
+                ]}
+            }
+        }"#;
+        let inspect = serde_json::from_str::<ContainerInspect>(json)?;
+        assert_eq!(inspect.health_status(), HealthStatus::Healthy);
+        Ok(())
+    }
+}
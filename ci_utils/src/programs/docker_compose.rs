@@ -0,0 +1,125 @@
+//! Wrapper over the `docker compose` plugin, managing a project described by a compose file.
+//!
+//! See [`crate::models::compose`] for the file model.
+
+use crate::prelude::*;
+
+use crate::programs::Docker;
+
+
+
+/// State of a single container of the project, as reported by `docker compose ps`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ServiceStatus {
+    pub name:    String,
+    pub service: String,
+    /// E.g. `running` or `exited`.
+    pub state:   String,
+    /// Empty if the service defines no healthcheck.
+    #[serde(default)]
+    pub health:  String,
+}
+
+impl ServiceStatus {
+    pub fn is_running(&self) -> bool {
+        self.state == "running"
+    }
+}
+
+/// Parse the `docker compose ps --format json` output.
+///
+/// Older Compose versions print a single JSON array, newer print one object per line.
+pub fn parse_ps_output(text: &str) -> Result<Vec<ServiceStatus>> {
+    let text = text.trim();
+    if text.starts_with('[') {
+        Ok(serde_json::from_str(text)?)
+    } else {
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str::<ServiceStatus>(line).anyhow_err())
+            .collect()
+    }
+}
+
+/// The Compose project, i.e. the services described by the file, running under the given name.
+#[derive(Clone, Debug)]
+pub struct DockerCompose {
+    pub file:    PathBuf,
+    pub project: String,
+}
+
+impl DockerCompose {
+    pub fn new(file: impl Into<PathBuf>, project: impl Into<String>) -> Self {
+        Self { file: file.into(), project: project.into() }
+    }
+
+    pub fn cmd(&self) -> Result<Command> {
+        let mut command = Docker.cmd()?;
+        command
+            .arg("compose")
+            .arg("--file")
+            .arg(&self.file)
+            .arg("--project-name")
+            .arg(&self.project);
+        Ok(command)
+    }
+
+    /// Create and start all the services in the background.
+    ///
+    /// If `wait` is set, returns only after the services are running and healthy.
+    pub async fn up(&self, wait: bool) -> Result {
+        let mut command = self.cmd()?;
+        command.args(["up", "--detach"]);
+        if wait {
+            command.arg("--wait");
+        }
+        command.run_ok().await
+    }
+
+    /// Stop and remove the containers. If `volumes` is set, the named volumes are removed as well.
+    pub async fn down(&self, volumes: bool) -> Result {
+        let mut command = self.cmd()?;
+        command.args(["down", "--remove-orphans"]);
+        if volumes {
+            command.arg("--volumes");
+        }
+        command.run_ok().await
+    }
+
+    pub async fn ps(&self) -> Result<Vec<ServiceStatus>> {
+        let output = self.cmd()?.args(["ps", "--all", "--format", "json"]).output_ok().await?;
+        parse_ps_output(&String::from_utf8(output.stdout)?)
+    }
+
+    /// Print the logs of the given services (all if empty) to the standard output.
+    pub async fn logs(&self, services: &[String], follow: bool) -> Result {
+        let mut command = self.cmd()?;
+        command.arg("logs");
+        if follow {
+            command.arg("--follow");
+        }
+        command.args(services).run_ok().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_ps() -> Result {
+        let array = r#"[{"Name":"enso-postgres-1","Service":"postgres","State":"running","Health":"healthy"}]"#;
+        let lines = r#"{"Name":"enso-postgres-1","Service":"postgres","State":"running","Health":"healthy"}
+{"Name":"enso-httpbin-1","Service":"httpbin","State":"exited"}
+"#;
+        let from_array = parse_ps_output(array)?;
+        let from_lines = parse_ps_output(lines)?;
+        assert_eq!(from_array[0], from_lines[0]);
+        assert!(from_lines[0].is_running());
+        assert_eq!(from_lines[1].health, "");
+        assert!(!from_lines[1].is_running());
+        assert!(parse_ps_output("")?.is_empty());
+        Ok(())
+    }
+}
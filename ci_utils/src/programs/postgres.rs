@@ -0,0 +1,130 @@
+//! Programs from the PostgreSQL server distribution.
+//!
+//! Distributions often do not put the server binaries on `PATH`, so the usual installation
+//! directories are also searched.
+
+use crate::prelude::*;
+
+use crate::programs::Program;
+
+
+
+/// Glob patterns of the directories where the PostgreSQL binaries are typically installed.
+pub fn install_location_patterns() -> &'static [&'static str] {
+    match TARGET_OS {
+        // Debian and Ubuntu packages, Fedora packages.
+        OS::Linux => &["/usr/lib/postgresql/*/bin", "/usr/pgsql-*/bin"],
+        // Homebrew on Apple Silicon and Intel, Postgres.app.
+        OS::MacOS => &[
+            "/opt/homebrew/opt/postgresql*/bin",
+            "/usr/local/opt/postgresql*/bin",
+            "/Applications/Postgres.app/Contents/Versions/*/bin",
+        ],
+        OS::Windows => &["C:\\Program Files\\PostgreSQL\\*\\bin"],
+        _ => &[],
+    }
+}
+
+/// Directories matching [`install_location_patterns`], the newest version first.
+pub fn default_locations() -> Vec<PathBuf> {
+    let mut ret = install_location_patterns()
+        .iter()
+        .filter_map(|pattern| glob::glob(pattern).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .collect_vec();
+    // Sorting the paths themselves would put e.g. `9.6` before `15`. The unversioned locations,
+    // like Homebrew's `postgresql` alias, come last.
+    ret.sort_by_key(|path| std::cmp::Reverse(version_in_path(path)));
+    ret
+}
+
+/// The `(major, minor)` version of the installation in the given directory, as encoded in its
+/// path, e.g. `/usr/lib/postgresql/9.6/bin` or `/opt/homebrew/opt/postgresql@15/bin`.
+///
+/// The minor version is zero if not present in the path.
+pub fn version_in_path(path: impl AsRef<Path>) -> Option<(u32, u32)> {
+    path.as_ref().components().rev().find_map(|component| {
+        let name = component.as_os_str().to_str()?;
+        let version = name.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let mut parts = version.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+        Some((major, minor))
+    })
+}
+
+/// The database server.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Postgres;
+
+impl Program for Postgres {
+    fn executable_name(&self) -> &'static str {
+        "postgres"
+    }
+    fn default_locations(&self) -> Vec<PathBuf> {
+        default_locations()
+    }
+}
+
+/// Creates a new database cluster, i.e. the data directory of the server.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InitDb;
+
+impl Program for InitDb {
+    fn executable_name(&self) -> &'static str {
+        "initdb"
+    }
+    fn default_locations(&self) -> Vec<PathBuf> {
+        default_locations()
+    }
+}
+
+impl InitDb {
+    /// Initialize a data directory for a cluster that trusts all local connections.
+    pub fn init_trusting(&self, data_dir: impl AsRef<Path>, superuser: &str) -> Result<Command> {
+        let mut command = self.cmd()?;
+        command.arg("--pgdata").arg(data_dir.as_ref()).args([
+            "--username",
+            superuser,
+            "--auth",
+            "trust",
+            "--encoding",
+            "UTF8",
+        ]);
+        Ok(command)
+    }
+}
+
+/// Creates a new database in a running server.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CreateDb;
+
+impl Program for CreateDb {
+    fn executable_name(&self) -> &'static str {
+        "createdb"
+    }
+    fn default_locations(&self) -> Vec<PathBuf> {
+        default_locations()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_from_paths() {
+        assert_eq!(version_in_path("/usr/lib/postgresql/9.6/bin"), Some((9, 6)));
+        assert_eq!(version_in_path("/usr/lib/postgresql/15/bin"), Some((15, 0)));
+        assert_eq!(version_in_path("/usr/pgsql-14/bin"), Some((14, 0)));
+        assert_eq!(version_in_path("/opt/homebrew/opt/postgresql@15/bin"), Some((15, 0)));
+        assert_eq!(
+            version_in_path("/Applications/Postgres.app/Contents/Versions/14.5/bin"),
+            Some((14, 5))
+        );
+        assert_eq!(version_in_path("/usr/local/opt/postgresql/bin"), None);
+        assert!(version_in_path("/usr/lib/postgresql/15/bin") > version_in_path("/a/9.6/bin"));
+    }
+}
@@ -23,3 +23,15 @@ impl Program for Rustup {
         "rustup"
     }
 }
+
+impl Rustup {
+    /// Install the target for the toolchain used in the given directory, unless already installed.
+    pub async fn add_target(&self, directory: impl AsRef<Path>, target: &str) -> Result {
+        self.cmd()?
+            .current_dir(directory.as_ref())
+            .env_remove(env::Toolchain::NAME)
+            .args(["target", "add", target])
+            .run_ok()
+            .await
+    }
+}
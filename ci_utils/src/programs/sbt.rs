@@ -20,6 +20,13 @@ macro_rules! strong_string {
 
 strong_string!(Task(str));
 
+/// Makes `sbt` act as a thin client, sending the command to the sbt server running for the
+/// current project. The server is started in the background if it is not already running.
+pub const CLIENT_FLAG: &str = "--client";
+
+/// Command that stops the sbt server, when sent through the thin client.
+pub const SHUTDOWN_COMMAND: &str = "shutdown";
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Sbt;
 
@@ -0,0 +1,69 @@
+//! The `wasm-bindgen` CLI, generating the JS bindings for a WASM module compiled by Cargo.
+//!
+//! This is what `wasm-pack build` runs under the hood. The CLI must be in exactly the same version
+//! as the `wasm-bindgen` crate the module was compiled with, see
+//! [`crate::cache::goodie::wasm_bindgen`].
+
+use crate::prelude::*;
+
+use crate::new_command_type;
+use crate::programs::wasm_pack::Target;
+
+
+
+/// Target triple that the WASM modules are compiled for.
+pub const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WasmBindgen;
+
+impl Program for WasmBindgen {
+    type Command = WasmBindgenCommand;
+    fn executable_name(&self) -> &'static str {
+        "wasm-bindgen"
+    }
+}
+
+new_command_type! {WasmBindgen, WasmBindgenCommand}
+
+impl WasmBindgenCommand {
+    /// The WASM module compiled by Cargo.
+    pub fn input(&mut self, wasm: impl AsRef<Path>) -> &mut Self {
+        self.arg(wasm.as_ref())
+    }
+
+    pub fn target(&mut self, target: Target) -> &mut Self {
+        self.arg("--target").arg(target)
+    }
+
+    pub fn output_directory(&mut self, output_path: impl AsRef<Path>) -> &mut Self {
+        self.arg("--out-dir").arg(output_path.as_ref())
+    }
+
+    /// Sets the output file names. Defaults to the input's name.
+    pub fn output_name(&mut self, output_name: impl AsRef<Path>) -> &mut Self {
+        self.arg("--out-name").arg(output_name.as_ref())
+    }
+
+    /// Include otherwise-extraneous debug checks in the output.
+    pub fn debug(&mut self) -> &mut Self {
+        self.arg("--debug")
+    }
+
+    pub fn typescript(&mut self) -> &mut Self {
+        self.arg("--typescript")
+    }
+}
+
+/// Runs the test suite compiled to WASM, in Node or in a browser (driven by WebDriver), depending
+/// on how the suite was configured.
+///
+/// Comes in the same package as [`WasmBindgen`] and must match it in version.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WasmBindgenTestRunner;
+
+impl Program for WasmBindgenTestRunner {
+    fn executable_name(&self) -> &'static str {
+        "wasm-bindgen-test-runner"
+    }
+}
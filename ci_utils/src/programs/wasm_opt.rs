@@ -2,7 +2,9 @@ use crate::prelude::*;
 use crate::program::command::Manipulator;
 use crate::program::version::IsVersion;
 
-#[derive(Clone, Copy, Debug, strum::Display, strum::EnumString)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, strum::Display, strum::EnumString, Serialize, Deserialize,
+)]
 pub enum OptimizationLevel {
     /// execute default optimization passes (equivalent to -Os)
     O,
@@ -23,6 +25,13 @@ pub enum OptimizationLevel {
     Oz,
 }
 
+impl OptimizationLevel {
+    /// Parse the command line flag, like `-O3`.
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        arg.strip_prefix('-').and_then(|level| level.parse().ok())
+    }
+}
+
 impl Manipulator for OptimizationLevel {
     fn apply<C: IsCommandWrapper + ?Sized>(&self, command: &mut C) {
         let flag = format!("-{self}");
@@ -39,6 +48,69 @@ impl Manipulator for Output<'_> {
     }
 }
 
+/// Typed wasm-opt invocation options, not including the input and output.
+///
+/// Passes run in the order of the arguments: first the [`OptimizationLevel`]'s pipeline (if set),
+/// then the explicitly listed passes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Options {
+    pub optimization_level: Option<OptimizationLevel>,
+    /// How much to focus on the code size in the optimization pipeline, from 0 to 2.
+    pub shrink_level:       Option<u32>,
+    /// Additional passes to run, e.g. `dce` or `inlining-optimizing`.
+    pub passes:             Vec<String>,
+    /// WebAssembly features to enable, e.g. `bulk-memory`.
+    pub enable_features:    Vec<String>,
+    /// WebAssembly features to disable, e.g. `sign-ext`.
+    pub disable_features:   Vec<String>,
+    /// Run the passes repeatedly, as long as the module keeps shrinking.
+    pub converge:           bool,
+    /// Keep the debug info (like the names section) in the output.
+    pub debuginfo:          bool,
+    /// Remove the DWARF debug info from the output.
+    pub strip_dwarf:        bool,
+    /// Raw arguments, passed after all the others.
+    pub extra_args:         Vec<String>,
+}
+
+impl Options {
+    pub fn with_level(optimization_level: OptimizationLevel) -> Self {
+        Self { optimization_level: Some(optimization_level), ..default() }
+    }
+
+    pub fn args(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        ret.extend(self.enable_features.iter().map(|feature| format!("--enable-{feature}")));
+        ret.extend(self.disable_features.iter().map(|feature| format!("--disable-{feature}")));
+        if let Some(level) = self.optimization_level {
+            ret.push(format!("-{level}"));
+        }
+        if let Some(shrink_level) = self.shrink_level {
+            ret.push("--shrink-level".into());
+            ret.push(shrink_level.to_string());
+        }
+        ret.extend(self.passes.iter().map(|pass| format!("--{pass}")));
+        if self.converge {
+            ret.push("--converge".into());
+        }
+        if self.debuginfo {
+            ret.push("--debuginfo".into());
+        }
+        if self.strip_dwarf {
+            ret.push("--strip-dwarf".into());
+        }
+        ret.extend(self.extra_args.iter().cloned());
+        ret
+    }
+}
+
+impl Manipulator for Options {
+    fn apply<C: IsCommandWrapper + ?Sized>(&self, command: &mut C) {
+        command.args(self.args());
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct WasmOpt;
 
@@ -73,6 +145,42 @@ impl IsVersion for Version {
 mod tests {
     use super::*;
 
+    #[test]
+    fn options_args() -> Result {
+        let options: Options = serde_yaml::from_str(
+            r#"
+optimization-level: Oz
+shrink-level: 2
+passes: [dce, inlining-optimizing]
+enable-features: [bulk-memory]
+converge: true
+strip-dwarf: true
+extra-args: ["--zero-filled-memory"]
+"#,
+        )?;
+        assert_eq!(options.args(), vec![
+            "--enable-bulk-memory",
+            "-Oz",
+            "--shrink-level",
+            "2",
+            "--dce",
+            "--inlining-optimizing",
+            "--converge",
+            "--strip-dwarf",
+            "--zero-filled-memory",
+        ]);
+        assert!(Options::default().args().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn optimization_level_from_arg() {
+        assert_eq!(OptimizationLevel::from_arg("-O3"), Some(OptimizationLevel::O3));
+        assert_eq!(OptimizationLevel::from_arg("-Oz"), Some(OptimizationLevel::Oz));
+        assert_eq!(OptimizationLevel::from_arg("O3"), None);
+        assert_eq!(OptimizationLevel::from_arg("--converge"), None);
+    }
+
     #[test]
     fn version_parsing() -> Result {
         let sample_version_string = "wasm-opt version 108 (version_108)";
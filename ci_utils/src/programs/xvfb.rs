@@ -0,0 +1,12 @@
+use crate::prelude::*;
+
+/// X virtual framebuffer, an X server that renders to memory instead of a screen. Allows running
+/// GUI applications on machines without a display.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Xvfb;
+
+impl Program for Xvfb {
+    fn executable_name(&self) -> &'static str {
+        "Xvfb"
+    }
+}
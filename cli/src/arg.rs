@@ -10,6 +10,7 @@ pub mod project_manager;
 pub mod release;
 pub mod runtime;
 pub mod wasm;
+pub mod which;
 
 use clap::Arg;
 use clap::ArgEnum;
@@ -126,6 +127,9 @@ pub enum Target {
     CiGen,
     /// Regenerate `syntax2` library (new parser).
     JavaGen(java_gen::Target),
+    /// List all the executables that could be used for the given program, together with their
+    /// versions. The first one is the one that the build script will use.
+    Which(which::Options),
 }
 
 /// Build, test and package Enso Engine.
@@ -141,6 +145,10 @@ pub struct Cli {
     #[clap(long, global = true, maybe_default_os = default_cache_path(), enso_env())]
     pub cache_path: PathBuf,
 
+    /// Do not download anything that is not in the cache yet, fail instead.
+    #[clap(long, global = true, enso_env())]
+    pub offline: bool,
+
     /// The GitHub repository with the project. This is mainly used to manage releases (checking
     /// released versions to generate a new one, or uploading release assets).
     /// The argument should follow the format `owner/repo_name`.
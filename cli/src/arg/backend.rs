@@ -42,7 +42,29 @@ pub enum Command {
     /// Run the tests.
     Test {
         #[clap(arg_enum, required = true)]
-        which: Vec<enso_build::engine::Tests>,
+        which:        Vec<enso_build::engine::Tests>,
+        /// Run only the given shard of the Standard Library test suites, written as `i/n`.
+        #[clap(long, enso_env())]
+        shard:        Option<enso_build::enso::Shard>,
+        /// Run the Standard Library test suites concurrently.
+        #[clap(long, enso_env())]
+        parallel:     bool,
+        /// Run only the test suites of the given Standard Library test projects, e.g.
+        /// `Table_Tests`. Can be given multiple times.
+        #[clap(long = "library", enso_env())]
+        libraries:    Vec<String>,
+        /// Run only the tests matching the pattern. For Scala it is passed to sbt's `testOnly`,
+        /// for the Standard Library it is a regular expression forwarded to the test runner.
+        #[clap(long, enso_env())]
+        filter:       Option<String>,
+        /// Rerun only the tests that failed in the previous run, as recorded in the JUnit reports.
+        /// The tests that pass on the retry are reported as flaky.
+        #[clap(long, enso_env())]
+        rerun_failed: bool,
+        /// How to run the database for the Standard Library tests. By default, Docker is used on
+        /// Linux and the locally installed Postgres binaries elsewhere.
+        #[clap(long, arg_enum, enso_env())]
+        postgres:     Option<enso_build::postgres::Backend>,
     },
     /// Run an SBT command.
     Sbt {
@@ -51,6 +73,26 @@ pub enum Command {
     },
     /// Perform the CI check routine for the backend.
     CiCheck {},
+    /// Manage the services used by the Standard Library tests, run with Docker Compose.
+    TestEnv {
+        #[clap(subcommand)]
+        command: TestEnvCommand,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq, Eq)]
+pub enum TestEnvCommand {
+    /// Start the services and print the environment variables pointing the tests to them.
+    Up {
+        /// Host port of the Postgres server.
+        #[clap(long, default_value_t = enso_build::postgres::POSTGRES_CONTAINER_DEFAULT_PORT)]
+        postgres_port: u16,
+        /// Host port of the httpbin server.
+        #[clap(long, default_value_t = enso_build::test_env::HTTPBIN_CONTAINER_PORT)]
+        httpbin_port:  u16,
+    },
+    /// Stop the services and remove their data.
+    Down,
 }
 
 #[derive(Args, Clone, Debug, PartialEq)]
@@ -58,4 +100,8 @@ pub struct Target {
     /// Command for backend package.
     #[clap(subcommand)]
     pub command: Command,
+    /// Print the build plan (steps, their inputs and outputs) instead of running it. The `sbt`
+    /// command just prints the sbt invocation. Commands that cannot be described this way fail.
+    #[clap(long, global = true, enso_env())]
+    pub dry_run: bool,
 }
@@ -1,18 +1,32 @@
 use enso_build::prelude::*;
 
+use crate::arg::ArgExt;
 use crate::arg::BuildJob;
 use crate::arg::Source;
 use crate::arg::WatchJob;
 use crate::source_args_hlp;
 use crate::IsWatchableSource;
+use enso_build::ide::web::AssetPins;
 use enso_build::project::gui::Gui;
 use enso_build::project::wasm::Wasm;
 
 use clap::Args;
 use clap::Subcommand;
+use std::lazy::SyncOnceCell;
 
 source_args_hlp!(Gui, "gui", BuildInput);
 
+static ASSET_PINS: SyncOnceCell<AssetPins> = SyncOnceCell::new();
+
+pub fn initialize_asset_pins(pins: AssetPins) -> Result {
+    ASSET_PINS.set(pins).map_err(|_| anyhow!("GUI asset pins were already set."))
+}
+
+/// The pinned GUI inputs from the build configuration.
+pub fn asset_pins() -> &'static AssetPins {
+    ASSET_PINS.get_or_init(default)
+}
+
 impl IsWatchableSource for Gui {
     type WatchInput = WatchInput;
 }
@@ -33,6 +47,19 @@ pub struct WatchInput {
     pub gui_shell: bool,
 }
 
+#[derive(Args, Clone, Debug, PartialEq)]
+pub struct Deploy {
+    #[clap(flatten)]
+    pub source: Source<Gui>,
+    /// Where to upload the files, e.g. `s3://<bucket>/<prefix>` or `file:///<path>`.
+    #[clap(long, enso_env())]
+    pub store:  Url,
+    /// Path in the store to deploy to, ending with a slash, e.g. `gui/`. Empty means the store's
+    /// root.
+    #[clap(long, enso_env(), default_value = "")]
+    pub prefix: String,
+}
+
 #[derive(Subcommand, Clone, Debug, PartialEq)]
 pub enum Command {
     /// Builds the GUI from the local sources.
@@ -41,6 +68,9 @@ pub enum Command {
     Get(Source<Gui>),
     /// Continuously rebuilds GUI when its sources are changed and serves it using dev-server.
     Watch(WatchJob<Gui>),
+    /// Uploads the GUI files under their content-hashed and original names, skipping the ones
+    /// already in the store.
+    Deploy(Deploy),
 }
 
 #[derive(Args, Clone, Debug)]
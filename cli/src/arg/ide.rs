@@ -6,6 +6,8 @@ use crate::arg::WatchJob;
 use crate::source_args_hlp;
 use enso_build::project::backend::Backend;
 use enso_build::project::gui::Gui;
+use enso_build::project::ide::smoke_test;
+use enso_build::project::ide::smoke_test::DisplayChoice;
 use enso_build::project::wasm::DEFAULT_INTEGRATION_TESTS_WASM_TIMEOUT;
 
 use clap::Args;
@@ -24,6 +26,35 @@ pub struct BuildInput {
     pub output_path:     OutputPath<Target>,
 }
 
+#[derive(Args, Clone, Debug, PartialEq)]
+pub struct SmokeTestOptions {
+    /// Where the IDE window is displayed.
+    #[clap(long, arg_enum, default_value_t = DisplayChoice::Auto, enso_env())]
+    pub display:                  DisplayChoice,
+    /// Time limit for the IDE to start and for its Project Manager to answer. Supports formats
+    /// like "300secs" or "5min".
+    #[clap(long, default_value_t = smoke_test::DEFAULT_TIMEOUT.into(), enso_env())]
+    pub smoke_test_timeout:       humantime::Duration,
+    /// The JSON-RPC endpoint of the Project Manager spawned by the IDE.
+    #[clap(long, default_value = smoke_test::DEFAULT_PROJECT_MANAGER_ENDPOINT, enso_env())]
+    pub project_manager_endpoint: Url,
+    /// Additional option to be passed to Enso IDE. Can be used multiple times to pass many
+    /// arguments.
+    #[clap(long, allow_hyphen_values = true, enso_env())]
+    pub ide_option:               Vec<String>,
+}
+
+impl From<SmokeTestOptions> for smoke_test::Options {
+    fn from(options: SmokeTestOptions) -> Self {
+        Self {
+            display:                  options.display,
+            timeout:                  options.smoke_test_timeout.into(),
+            project_manager_endpoint: options.project_manager_endpoint,
+            ide_options:              options.ide_option,
+        }
+    }
+}
+
 #[derive(Subcommand, Clone, Debug)]
 pub enum Command {
     /// Builds both Project Manager and GUI, puts them together into a single, client Electron
@@ -47,6 +78,17 @@ pub enum Command {
         #[clap(long, allow_hyphen_values = true, enso_env())]
         ide_option: Vec<String>,
     },
+    /// Like `Build` but then starts the unpacked Linux package and checks that its Project
+    /// Manager answers, shutting everything down afterwards.
+    SmokeTest {
+        #[clap(flatten)]
+        params:     BuildInput,
+        /// Do not build the IDE, test the one already in the output directory.
+        #[clap(long)]
+        skip_build: bool,
+        #[clap(flatten)]
+        options:    SmokeTestOptions,
+    },
     /// Builds Project Manager and runs it in the background. Builds GUI and runs it using
     /// webpack's dev server.
     Watch {
@@ -60,19 +102,18 @@ pub enum Command {
     IntegrationTest {
         /// If set, the project manager won't be spawned.
         #[clap(long)]
-        external_backend:  bool,
+        external_backend: bool,
         #[clap(flatten)]
-        project_manager:   Source<Backend>,
+        project_manager:  Source<Backend>,
         /// Run WASM tests in the headless mode
         #[clap(long, parse(try_from_str), default_value_t = true)]
-        headless:          bool,
+        headless:         bool,
         /// Custom timeout for wasm-bindgen test runner. Supports formats like "300secs" or "5min".
         #[clap(long, default_value_t = DEFAULT_INTEGRATION_TESTS_WASM_TIMEOUT.into())]
-        wasm_timeout:      humantime::Duration,
-        /// Additional options to be appended to the wasm-pack invocation. Note that wasm-pack will
-        /// further redirect any unrecognized option to the underlying cargo call.
+        wasm_timeout:     humantime::Duration,
+        /// Additional options to be appended to the `cargo test` invocation compiling the tests.
         #[clap(last = true)]
-        wasm_pack_options: Vec<String>,
+        cargo_options:    Vec<String>,
     },
 }
 
@@ -7,6 +7,49 @@ use clap::Subcommand;
 pub struct DeployToEcr {
     #[clap(long, default_value = enso_build::aws::ecr::runtime::NAME, enso_env())]
     pub ecr_repository: String,
+    /// Architecture of the image. If given multiple times, all the variants are published under
+    /// a single tag.
+    #[clap(long = "arch", default_value = "x86_64")]
+    pub architectures:  Vec<Arch>,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct DeployImage {
+    /// Registry to push the image to, e.g. `ecr:runtime`, `acr:<registry>/<repository>`,
+    /// `ghcr:<owner>/<repository>` or `<server>/<repository>`. Can be given multiple times.
+    #[clap(long = "registry", required = true)]
+    pub registries:    Vec<enso_build::registry::Registry>,
+    /// Architecture of the image. If given multiple times, all the variants are published under
+    /// a single tag.
+    #[clap(long = "arch", default_value = "x86_64")]
+    pub architectures: Vec<Arch>,
+    /// Build cache source, passed to `docker buildx build --cache-from`.
+    #[clap(long)]
+    pub cache_from:    Vec<String>,
+    /// Build cache destination, passed to `docker buildx build --cache-to`.
+    #[clap(long)]
+    pub cache_to:      Vec<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Publish {
+    /// Where to publish the edition, e.g. `s3://<bucket>/<prefix>` or `file:///<path>`.
+    /// Defaults to the production editions bucket.
+    #[clap(long, enso_env())]
+    pub editions_store: Option<Url>,
+    /// Do not modify the release nor the editions store, only log what would be done.
+    #[clap(long, enso_env())]
+    pub dry_run:        bool,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Mirror {
+    /// Where to copy the assets, e.g. `s3://<bucket>/<prefix>` or `file:///<path>`.
+    #[clap(long, enso_env())]
+    pub store: Url,
+    /// Text of the release's tag. If not given, the release with `ENSO_RELEASE_ID` is mirrored.
+    #[clap(long, enso_env())]
+    pub tag:   Option<String>,
 }
 
 #[derive(Subcommand, Clone, Debug)]
@@ -14,7 +57,12 @@ pub enum Action {
     CreateDraft,
     /// Build the runtime image and push it to ECR.
     DeployToEcr(DeployToEcr),
-    Publish,
+    /// Build the runtime image and push it to the given registries.
+    DeployImage(DeployImage),
+    /// Publish the draft release and update the editions bucket, pruning old nightly editions.
+    Publish(Publish),
+    /// Copy the release assets into a bucket, to be served from our own CDN.
+    Mirror(Mirror),
 }
 
 #[derive(Args, Clone, Debug)]
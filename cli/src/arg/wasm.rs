@@ -10,9 +10,13 @@ use crate::IsWatchableSource;
 use clap::ArgEnum;
 use clap::Args;
 use clap::Subcommand;
+use enso_build::project::wasm::test::browser::BrowserChoice;
+use enso_build::project::wasm::test::runner::DEFAULT_BINARY_TIMEOUT;
 use enso_build::project::wasm::Wasm;
+use enso_build::project::wasm::WasmOptPresets;
 use std::lazy::SyncOnceCell;
 
+pub use enso_build::project::wasm::Builder;
 pub use enso_build::project::wasm::Profile;
 
 source_args_hlp!(Wasm, "wasm", BuildInput);
@@ -29,6 +33,17 @@ pub fn initialize_default_wasm_size_limit(limit: byte_unit::Byte) -> Result {
         .map_err(|e| anyhow!("WASM size limit was already set to {e}."))
 }
 
+static WASM_OPT_PRESETS: SyncOnceCell<WasmOptPresets> = SyncOnceCell::new();
+
+pub fn initialize_wasm_opt_presets(presets: WasmOptPresets) -> Result {
+    WASM_OPT_PRESETS.set(presets).map_err(|_| anyhow!("wasm-opt presets were already set."))
+}
+
+/// The wasm-opt presets from the build configuration.
+pub fn wasm_opt_presets() -> &'static WasmOptPresets {
+    WASM_OPT_PRESETS.get_or_init(default)
+}
+
 // Follows hierarchy defined in  lib/rust/profiler/src/lib.rs
 #[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
 pub enum ProfilingLevel {
@@ -55,17 +70,26 @@ pub struct BuildInput {
     #[clap(default_value = enso_build::project::wasm::DEFAULT_TARGET_CRATE, long, enso_env())]
     pub crate_path: PathBuf,
 
+    /// How to compile the crate: with wasm-pack, or directly with Cargo and then wasm-bindgen (in
+    /// the version from the lockfile, downloaded if needed).
+    #[clap(long, arg_enum, default_value_t = Builder::WasmPack, enso_env())]
+    pub wasm_builder: Builder,
+
     /// Profile that is passed to wasm-pack.
     #[clap(long, arg_enum, default_value_t = Profile::Release, enso_env())]
     pub wasm_profile: Profile,
 
-    /// Additional options to be passed to wasm-opt. Might overwrite the optimization flag
-    /// resulting from 'wasm_profile' setting.
+    /// Named wasm-opt options defined in the build configuration. If not set, the preset named
+    /// after the 'wasm_profile' is used if defined, otherwise the profile's optimization level.
+    #[clap(long, enso_env())]
+    pub wasm_opt_preset: Option<String>,
+
+    /// Additional options to be passed to wasm-opt, after the ones from the preset.
     #[clap(long, allow_hyphen_values = true, enso_env())]
     pub wasm_opt_option: Vec<String>,
 
     /// Do not invoke wasm-opt, even if it is part of current profile.
-    #[clap(long, conflicts_with = "wasm-opt-option", enso_env())]
+    #[clap(long, conflicts_with_all = &["wasm-opt-option", "wasm-opt-preset"], enso_env())]
     pub skip_wasm_opt: bool,
 
     /// Additional options to be passed to Cargo.
@@ -80,13 +104,41 @@ pub struct BuildInput {
     /// "4.06MiB". Pass "0" to disable check.
     #[clap(long, enso_env(), default_value_if("skip_wasm_opt", Some("true"), Some("0")), maybe_default = DEFAULT_WASM_SIZE_LIMIT.get())]
     pub wasm_size_limit: Option<byte_unit::Byte>,
+
+    /// Size report of a previous build to compare with, e.g. the `size-report.json` from the
+    /// `gui_wasm` CI artifact. The build fails if the compressed WASM grew too much since then.
+    #[clap(long, enso_env())]
+    pub wasm_size_baseline: Option<PathBuf>,
+
+    /// How much the compressed WASM may grow since the `wasm-size-baseline`. Supports format like
+    /// "16KiB".
+    #[clap(long, enso_env(), default_value = "16KiB")]
+    pub wasm_size_max_growth: byte_unit::Byte,
 }
 
 #[derive(Args, Clone, Debug, PartialEq)]
 pub struct WatchInput {
-    /// Additional option to be passed to Cargo. Can be used multiple times to pass many arguments.
-    #[clap(long, allow_hyphen_values = true, enso_env())]
-    pub cargo_watch_option: Vec<String>,
+    /// How long the sources must stay untouched before the rebuild starts. Supports formats like
+    /// "300ms" or "1s".
+    #[clap(long, enso_env(), default_value_t = enso_build::project::watch::DEFAULT_DEBOUNCE.into())]
+    pub watch_debounce: humantime::Duration,
+}
+
+#[derive(Args, Clone, Debug, PartialEq)]
+pub struct TestOptions {
+    /// Where to run the tests. With "chrome", the installed chromedriver or the downloaded Chrome
+    /// for Testing is used. With "node", only the tests not needing a browser are run.
+    #[clap(long, arg_enum, default_value_t = BrowserChoice::Chrome, enso_env())]
+    pub browser: BrowserChoice,
+
+    /// Time limit for running a single test binary, usually all the tests of a crate. Supports
+    /// formats like "300secs" or "10min".
+    #[clap(long, default_value_t = DEFAULT_BINARY_TIMEOUT.into(), enso_env())]
+    pub crate_timeout: humantime::Duration,
+
+    /// How many test binaries may run at once. Defaults to the number of CPUs.
+    #[clap(long, enso_env())]
+    pub jobs: Option<usize>,
 }
 
 #[derive(Subcommand, Clone, Debug, PartialEq)]
@@ -107,6 +159,14 @@ pub enum Command {
         /// Skip the WASM Rust tests.
         #[clap(long)]
         no_wasm:   bool,
+        /// Run the WASM tests only of the given crates (by the package name).
+        #[clap(long = "crate")]
+        crates:    Vec<String>,
+        /// Only print which crates' WASM tests would be run, and why the others are skipped.
+        #[clap(long)]
+        list:      bool,
+        #[clap(flatten)]
+        options:   TestOptions,
     },
 }
 
@@ -0,0 +1,7 @@
+use crate::prelude::*;
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct Options {
+    /// Name of the program to look up, e.g. `cargo` or `sbt`.
+    pub program: String,
+}
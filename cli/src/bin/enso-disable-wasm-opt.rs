@@ -1,6 +1,8 @@
 //! This script is used to disable the `wasm-opt` optimization in the crates that can be used as
 //! WASM entry points. Unfortunately, wasm-opt does not allow for disabling wasm-opt through a
 //! command line flag, so we have to disable it by setting an appropriate flag in each Cargo.toml.
+//!
+//! This is not needed when building with `--wasm-builder cargo`, as wasm-pack is not used then.
 
 #![feature(option_result_contains)]
 #![feature(associated_type_bounds)]
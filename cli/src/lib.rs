@@ -59,6 +59,7 @@ use enso_build::project::runtime;
 use enso_build::project::runtime::Runtime;
 use enso_build::project::wasm;
 use enso_build::project::wasm::Wasm;
+use enso_build::project::watch::WatchOptions;
 use enso_build::project::IsTarget;
 use enso_build::project::IsWatchable;
 use enso_build::project::IsWatcher;
@@ -132,7 +133,7 @@ impl Processor {
         triple.versions.publish()?;
         let context = BuildContext {
             inner: project::Context {
-                cache: Cache::new(&cli.cache_path).await?,
+                cache: Cache::new(&cli.cache_path).await?.with_offline(cli.offline),
                 octocrab,
                 upload_artifacts: cli.upload_artifacts,
                 repo_root: enso_build::paths::new_repo_root(absolute_repo_path, &triple),
@@ -316,13 +317,41 @@ impl Processor {
         .boxed()
     }
 
+    /// Default options of the WASM test runner, with the JUnit reports written next to the
+    /// Standard Library ones.
+    pub fn wasm_test_options(&self) -> wasm::test::Options {
+        let junit_dir = enso_build::paths::ENSO_TEST_JUNIT_DIR
+            .get()
+            .unwrap_or_else(|_| self.repo_root.target.join("test-results"));
+        wasm::test::Options::new(junit_dir.join("wasm"))
+    }
+
     pub fn handle_wasm(&self, wasm: arg::wasm::Target) -> BoxFuture<'static, Result> {
         match wasm.command {
             arg::wasm::Command::Watch(job) => self.watch_and_wait(job),
             arg::wasm::Command::Build(job) => self.build(job).void_ok().boxed(),
             arg::wasm::Command::Check => Wasm.check().boxed(),
-            arg::wasm::Command::Test { no_wasm, no_native } =>
-                Wasm.test(self.repo_root.to_path_buf(), !no_wasm, !no_native).boxed(),
+            arg::wasm::Command::Test { list: true, crates, .. } => {
+                let repo_root = self.repo_root.to_path_buf();
+                async move {
+                    let plan = wasm::test::TestPlan::discover(&repo_root).await?.filter(&crates)?;
+                    print!("{plan}");
+                    Ok(())
+                }
+                .boxed()
+            }
+            arg::wasm::Command::Test { no_wasm, no_native, crates, list: false, options } => {
+                let arg::wasm::TestOptions { browser, crate_timeout, jobs } = options;
+                let mut options = self.wasm_test_options();
+                options.browser = browser;
+                options.binary_timeout = crate_timeout.into();
+                if let Some(jobs) = jobs {
+                    options.jobs = jobs;
+                }
+                let repo_root = self.repo_root.to_path_buf();
+                let cache = self.cache.clone();
+                Wasm.test(repo_root, cache, !no_wasm, !no_native, crates, options).boxed()
+            }
             arg::wasm::Command::Get(source) => self.get(source).void_ok().boxed(),
         }
     }
@@ -343,6 +372,17 @@ impl Processor {
             arg::gui::Command::Build(job) => self.build(job),
             arg::gui::Command::Get(source) => self.get(source).void_ok().boxed(),
             arg::gui::Command::Watch(job) => self.watch_and_wait(job),
+            arg::gui::Command::Deploy(deploy) => {
+                let artifact = self.get(deploy.source);
+                async move {
+                    let artifact = artifact.await?;
+                    let store = enso_build::blob_store::open(&deploy.store).await?;
+                    let uploaded = artifact.deploy(&*store, &deploy.prefix).await?;
+                    info!("Uploaded {uploaded} GUI files to {}.", deploy.store);
+                    Ok(())
+                }
+                .boxed()
+            }
         }
     }
 
@@ -356,7 +396,11 @@ impl Processor {
     }
 
     pub fn handle_backend(&self, backend: arg::backend::Target) -> BoxFuture<'static, Result> {
+        let dry_run = backend.dry_run;
         match backend.command {
+            arg::backend::Command::Build { .. } | arg::backend::Command::TestEnv { .. }
+                if dry_run =>
+                ready(Err(anyhow!("This command does not support the dry run."))).boxed(),
             arg::backend::Command::Build { source } => self.get(source).void_ok().boxed(),
             arg::backend::Command::Upload { input } => {
                 let input = enso_build::project::Backend::resolve(self, input);
@@ -366,7 +410,7 @@ impl Processor {
                     let input = input.await?;
                     let operation = enso_build::engine::Operation::Release(
                         enso_build::engine::ReleaseOperation {
-                            repo,
+                            repo:    repo.clone(),
                             command: enso_build::engine::ReleaseCommand::Upload,
                         },
                     );
@@ -378,7 +422,12 @@ impl Processor {
                         ..default()
                     };
                     let context = input.prepare_context(context, config)?;
-                    context.execute(operation).await?;
+                    if dry_run {
+                        print!("{}", context.describe_plan()?);
+                        println!("The built packages would be uploaded to the release in {repo}.");
+                    } else {
+                        context.execute(operation).await?;
+                    }
                     Ok(())
                 }
                 .boxed()
@@ -390,15 +439,28 @@ impl Processor {
                     ..default()
                 };
                 let context = self.prepare_backend_context(config);
-                async move {
-                    let context = context.await?;
-                    context.build().await?;
-                    Ok(())
-                }
-                .boxed()
+                build_backend(context, dry_run)
             }
-            arg::backend::Command::Test { which } => {
+            arg::backend::Command::Test {
+                which,
+                shard,
+                parallel,
+                libraries,
+                filter,
+                rerun_failed,
+                postgres,
+            } => {
                 let mut config = enso_build::engine::BuildConfigurationFlags::default();
+                config.scala_test_filter = filter.clone();
+                config.standard_library_tests = enso_build::enso::StandardLibraryTestOptions {
+                    shard,
+                    parallel,
+                    libraries,
+                    filter,
+                    rerun: default(),
+                    postgres,
+                };
+                config.rerun_failed_tests = rerun_failed;
                 for arg in which {
                     match arg {
                         Tests::Scala => config.test_scala = true,
@@ -407,7 +469,7 @@ impl Processor {
                 }
                 config.test_java_generated_from_rust = true;
                 let context = self.prepare_backend_context(config);
-                async move { context.await?.build().void_ok().await }.boxed()
+                build_backend(context, dry_run)
             }
             arg::backend::Command::Sbt { command } => {
                 let context = self.prepare_backend_context(default());
@@ -415,13 +477,19 @@ impl Processor {
                     let mut command_pieces = vec![OsString::from("sbt")];
                     command_pieces.extend(command.into_iter().map(into));
 
-                    let operation =
-                        enso_build::engine::Operation::Run(enso_build::engine::RunOperation {
-                            command_pieces,
-                        });
+                    let operation = enso_build::engine::RunOperation { command_pieces };
 
                     let context = context.await?;
-                    context.execute(operation).await
+                    if dry_run {
+                        let command_line = operation
+                            .command_pieces
+                            .iter()
+                            .map(|piece| piece.to_string_lossy())
+                            .join(" ");
+                        println!("Would run: {command_line}");
+                        return Ok(());
+                    }
+                    context.execute(enso_build::engine::Operation::Run(operation)).await
                 }
                 .boxed()
             }
@@ -438,14 +506,32 @@ impl Processor {
                     generate_documentation: true,
                     ..default()
                 };
-                let context = self.prepare_backend_context(config);
-                async move {
-                    let mut context = context.await?;
+                let context = self.prepare_backend_context(config).map_ok(|mut context| {
                     context.upload_artifacts = true;
-                    context.build().await
+                    context
+                });
+                build_backend(context.boxed(), dry_run)
+            }
+            arg::backend::Command::TestEnv { command } => {
+                let target_dir = self.repo_root.target.to_path_buf();
+                match command {
+                    arg::backend::TestEnvCommand::Up { postgres_port, httpbin_port } => {
+                        let env = enso_build::test_env::TestEnvironment {
+                            postgres_port,
+                            httpbin_port,
+                            ..default()
+                        };
+                        async move {
+                            for (name, value) in env.up(&target_dir).await? {
+                                println!("{name}={value}");
+                            }
+                            Ok(())
+                        }
+                        .boxed()
+                    }
+                    arg::backend::TestEnvCommand::Down =>
+                        async move { enso_build::test_env::down(&target_dir).await }.boxed(),
                 }
-                .void_ok()
-                .boxed()
             }
         }
     }
@@ -496,6 +582,27 @@ impl Processor {
                 }
                 .boxed()
             }
+            arg::ide::Command::SmokeTest { params, skip_build, options } => {
+                let build_job = if skip_build {
+                    let artifact = ide::Artifact::new(
+                        self.triple.os,
+                        self.triple.arch,
+                        &self.triple.versions.version,
+                        &params.output_path.output_path,
+                    );
+                    ok_ready_boxed(artifact)
+                } else {
+                    self.build_ide(params)
+                };
+                let options: ide::smoke_test::Options = options.into();
+                async move {
+                    let artifact = build_job.await?;
+                    let outcome = ide::smoke_test::run(&artifact, &options).await?;
+                    info!("IDE smoke test passed. {outcome}");
+                    Ok(())
+                }
+                .boxed()
+            }
             arg::ide::Command::Watch { project_manager, gui } => {
                 let gui_watcher = self.watch(gui);
                 let project_manager = self.spawn_project_manager(project_manager, None);
@@ -514,10 +621,15 @@ impl Processor {
             arg::ide::Command::IntegrationTest {
                 external_backend,
                 project_manager,
-                wasm_pack_options,
+                cargo_options,
                 headless,
                 wasm_timeout,
             } => {
+                let mut options = self.wasm_test_options();
+                options.headless = headless;
+                options.test_timeout = Some(wasm_timeout.into());
+                options.cargo_options = cargo_options;
+                let cache = self.cache.clone();
                 let custom_root = tempdir();
                 let (custom_root, project_manager) = match custom_root {
                     Ok(tempdir) => {
@@ -533,14 +645,7 @@ impl Processor {
                 async move {
                     let project_manager =
                         if !external_backend { Some(project_manager?.await?) } else { None };
-                    Wasm.integration_test(
-                        source_root,
-                        project_manager,
-                        headless,
-                        wasm_pack_options,
-                        Some(wasm_timeout.into()),
-                    )
-                    .await?;
+                    Wasm.integration_test(source_root, cache, project_manager, options).await?;
                     // Custom root must live while the tests are being run.
                     drop(custom_root);
                     Ok(())
@@ -597,6 +702,23 @@ impl Processor {
     }
 }
 
+/// Run the backend build in the given context. If `dry_run` is set, just print the build plan.
+pub fn build_backend(
+    context: BoxFuture<'static, Result<enso_build::engine::RunContext>>,
+    dry_run: bool,
+) -> BoxFuture<'static, Result> {
+    async move {
+        let context = context.await?;
+        if dry_run {
+            print!("{}", context.describe_plan()?);
+        } else {
+            context.build().await?;
+        }
+        Ok(())
+    }
+    .boxed()
+}
+
 pub trait Resolvable: IsTarget + IsTargetSource + Clone {
     fn prepare_target(context: &Processor) -> Result<Self>;
 
@@ -617,22 +739,37 @@ impl Resolvable for Wasm {
     ) -> BoxFuture<'static, Result<<Self as IsTarget>::BuildInput>> {
         let arg::wasm::BuildInput {
             crate_path,
+            wasm_builder,
             wasm_profile,
+            wasm_opt_preset,
             wasm_opt_option: wasm_opt_options,
             cargo_options,
             profiling_level,
             wasm_size_limit,
+            wasm_size_baseline,
+            wasm_size_max_growth,
             skip_wasm_opt,
         } = from;
-        ok_ready_boxed(wasm::BuildInput {
-            crate_path,
+        let profile: wasm::Profile = wasm_profile.into();
+        let wasm_opt = wasm::resolve_wasm_opt_options(
+            crate::arg::wasm::wasm_opt_presets(),
+            wasm_opt_preset.as_deref(),
+            profile,
             wasm_opt_options,
+        );
+        let input = wasm_opt.map(|wasm_opt| wasm::BuildInput {
+            crate_path,
+            builder: wasm_builder,
+            wasm_opt,
             skip_wasm_opt,
             extra_cargo_options: cargo_options,
-            profile: wasm_profile.into(),
+            profile,
             profiling_level: profiling_level.map(into),
             wasm_size_limit: wasm_size_limit.filter(|size_limit| size_limit.get_bytes() > 0),
-        })
+            wasm_size_baseline,
+            wasm_size_max_growth,
+        });
+        ready(input).boxed()
     }
 }
 
@@ -647,7 +784,8 @@ impl Resolvable for Gui {
     ) -> BoxFuture<'static, Result<<Self as IsTarget>::BuildInput>> {
         let wasm_source = ctx.resolve(Wasm, from.wasm);
         let build_info = ctx.js_build_info();
-        async move { Ok(gui::BuildInput { wasm: wasm_source.await?, build_info }) }.boxed()
+        let assets = crate::arg::gui::asset_pins().clone();
+        async move { Ok(gui::BuildInput { wasm: wasm_source.await?, build_info, assets }) }.boxed()
     }
 }
 
@@ -740,7 +878,8 @@ impl WatchResolvable for Wasm {
         _ctx: &Processor,
         from: <Self as IsWatchableSource>::WatchInput,
     ) -> Result<<Self as IsWatchable>::WatchInput> {
-        Ok(wasm::WatchInput { cargo_watch_options: from.cargo_watch_option })
+        let options = WatchOptions { debounce: from.watch_debounce.into() };
+        Ok(wasm::WatchInput { options })
     }
 }
 
@@ -761,6 +900,8 @@ pub async fn main_internal(config: enso_build::config::Config) -> Result {
     if let Some(wasm_size_limit) = config.wasm_size_limit {
         crate::arg::wasm::initialize_default_wasm_size_limit(wasm_size_limit)?;
     }
+    crate::arg::wasm::initialize_wasm_opt_presets(config.wasm_opt_presets.clone())?;
+    crate::arg::gui::initialize_asset_pins(config.gui_assets.clone())?;
 
     let cli = Cli::parse();
 
@@ -836,15 +977,48 @@ pub async fn main_internal(config: enso_build::config::Config) -> Result {
                 enso_build::release::create_release(&*ctx).await?;
             }
             Action::DeployToEcr(args) => {
-                enso_build::release::deploy_to_ecr(&*ctx, args.ecr_repository).await?;
+                let repository = args.ecr_repository;
+                enso_build::release::deploy_to_ecr(&*ctx, repository, args.architectures).await?;
             }
-            Action::Publish => {
-                enso_build::release::publish_release(&*ctx).await?;
+            Action::DeployImage(args) => {
+                let options = enso_build::release::DeployImageOptions {
+                    registries:    args.registries,
+                    architectures: args.architectures,
+                    cache_from:    args.cache_from,
+                    cache_to:      args.cache_to,
+                };
+                for image in enso_build::release::deploy_image(&*ctx, &options).await? {
+                    info!("Pushed {image}.");
+                }
+            }
+            Action::Publish(args) => {
+                let store = args.editions_store.as_ref();
+                enso_build::release::publish_release(&*ctx, store, args.dry_run).await?;
+            }
+            Action::Mirror(args) => {
+                let tag = args.tag.as_deref();
+                let index = enso_build::release::mirror_release(&*ctx, &args.store, tag).await?;
+                info!("Mirrored {} assets of {}.", index.assets.len(), index.version);
             }
         },
         Target::CiGen => ci_gen::generate(
             &enso_build::paths::generated::RepoRootGithubWorkflows::new(cli.repo_path),
         )?,
+        Target::Which(options) => {
+            let program = ide_ci::program::Unknown(options.program.clone());
+            let selected = program.lookup()?.executable_path;
+            let candidates = program.lookup_all()?;
+            for candidate in once(&selected).chain(candidates.iter().filter(|c| **c != selected)) {
+                let version = Command::new(candidate)
+                    .arg("--version")
+                    .run_stdout()
+                    .await
+                    .map(|output| output.lines().next().unwrap_or_default().trim().to_owned())
+                    .unwrap_or_else(|e| format!("<unknown version: {e}>"));
+                let marker = if *candidate == selected { "*" } else { " " };
+                println!("{marker} {} ({version})", candidate.display());
+            }
+        }
         Target::JavaGen(command) => {
             let repo_root = ctx.repo_root.clone();
             async move {